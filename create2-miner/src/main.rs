@@ -0,0 +1,101 @@
+/*
+    mine a CREATE2 salt so the deployed contract address starts with a given prefix.
+
+    address = keccak256(0xff ++ deployer ++ salt ++ keccak256(init_code))[12..]
+
+    the deployer defaults to the CREATE2 factory forge scripts deploy through, so a mined salt
+    can be used directly as `new DynamicExample{salt: ...}()` in a script:
+
+    forge build
+    cargo run --release -- --init-code-file ../tx-hash-miner-data/dynamic-example/out/DynamicExample.sol/DynamicExample.json --target dead
+
+    factories that only accept salts starting with the caller take `--salt-prefix <caller>`.
+*/
+
+use std::{path::PathBuf, sync::Arc};
+
+use alloy::primitives::{Address, Bytes, B256};
+use clap::{ArgGroup, Parser};
+use eyre::{bail, ensure, Result};
+use miner_core::{
+    create2::{self, CREATE2_DEPLOYER},
+    engine::{self, Progress},
+    Options, Target,
+};
+
+#[derive(Parser, Debug)]
+#[command(group(ArgGroup::new("init").required(true).args(["init_code", "init_code_file", "init_code_hash"])))]
+struct Args {
+    /// Address doing the CREATE2: a factory, or the deploying contract.
+    #[arg(long, default_value_t = CREATE2_DEPLOYER)]
+    deployer: Address,
+    /// Creation bytecode, constructor arguments included.
+    #[arg(long)]
+    init_code: Option<Bytes>,
+    /// Hex bytecode, or a forge artifact JSON (`bytecode.object` is used).
+    #[arg(long)]
+    init_code_file: Option<PathBuf>,
+    #[arg(long)]
+    init_code_hash: Option<B256>,
+    /// Hex prefix the contract address has to start with.
+    #[arg(long, default_value = "dead")]
+    target: Target,
+    /// Fixed leading salt bytes, at most 24; the rest of the salt is mined.
+    #[arg(long)]
+    salt_prefix: Option<Bytes>,
+    /// Search threads; defaults to all cores.
+    #[arg(long)]
+    threads: Option<usize>,
+}
+
+impl Args {
+    fn init_code_hash(&self) -> Result<B256> {
+        if let Some(hash) = self.init_code_hash {
+            return Ok(hash);
+        }
+        let code = match (&self.init_code, &self.init_code_file) {
+            (Some(code), _) => code.clone(),
+            (None, Some(path)) => create2::read_init_code(path)?,
+            (None, None) => bail!("no init code given"),
+        };
+        Ok(create2::init_code_hash(&code))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let init_code_hash = args.init_code_hash()?;
+    let prefix = args.salt_prefix.clone().unwrap_or_default();
+    let deployer = args.deployer;
+    let target = args.target.clone();
+    let threads = args.threads.unwrap_or(Options::default().threads);
+
+    println!("Deployer: {}", deployer);
+    println!("Init code hash: {}", init_code_hash);
+
+    let progress = Arc::new(Progress::default());
+    let reporter = engine::spawn_reporter(progress.clone());
+
+    let search = {
+        let progress = progress.clone();
+        tokio::task::spawn_blocking(move || {
+            create2::search(deployer, init_code_hash, &prefix, &target, threads, &progress)
+        })
+    };
+
+    let result = search.await?;
+    reporter.abort();
+    let ((salt, address), attempts) = result?;
+
+    // recompute with alloy rather than trusting the batched hashing
+    let expected = deployer.create2(salt, init_code_hash);
+    ensure!(address == expected, "mined address {address} but alloy computes {expected}");
+
+    println!("Found an address starting with prefix: {}", address);
+    println!("salt: {}", salt);
+    println!("attempts: {}", attempts);
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+/*
+    generate a private key whose address starts with a given prefix, e.g. to send mined txs
+    from `0xdead...`. all-lowercase patterns match either case, patterns with an uppercase
+    letter have to match the EIP-55 checksummed address.
+
+    keys are drawn one by one from the OS CSPRNG, never from a seed (see miner_core::vanity),
+    and the found key only ever lands in an encrypted keystore:
+
+    cargo run --release -- --pattern dead --keystore-dir ./keys
+    cargo run --release -- --pattern DeAd --keystore-dir ./keys --password-file pw.txt
+
+    the keystore works with the miners' `--keystore` flag and with `cast wallet`.
+*/
+
+use std::{path::PathBuf, sync::Arc};
+
+use clap::Parser;
+use eyre::{ensure, Result, WrapErr};
+use miner_core::{
+    engine::{self, Progress},
+    vanity::{self, AddressPattern},
+    Options,
+};
+
+#[derive(Parser, Debug)]
+struct Args {
+    /// Hex prefix of the address; uppercase letters make it checksum-case sensitive.
+    #[arg(long)]
+    pattern: AddressPattern,
+    /// Where the encrypted keystore is written.
+    #[arg(long, default_value = "keystores")]
+    keystore_dir: PathBuf,
+    /// Keystore password; prompted for when not set.
+    #[arg(long)]
+    password_file: Option<PathBuf>,
+    /// Search threads; defaults to all cores.
+    #[arg(long)]
+    threads: Option<usize>,
+}
+
+fn password(args: &Args) -> Result<String> {
+    if let Some(file) = &args.password_file {
+        let password = std::fs::read_to_string(file)
+            .wrap_err_with(|| format!("failed to read {}", file.display()))?;
+        return Ok(password.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    let password = rpassword::prompt_password("Keystore password: ")?;
+    let confirm = rpassword::prompt_password("Confirm password: ")?;
+    ensure!(password == confirm, "passwords don't match");
+    Ok(password)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    // ask up front, not after a long search
+    let password = password(&args)?;
+    let threads = args.threads.unwrap_or(Options::default().threads);
+
+    let progress = Arc::new(Progress::default());
+    let reporter = engine::spawn_reporter(progress.clone());
+
+    let search = {
+        let (pattern, progress) = (args.pattern.clone(), progress.clone());
+        tokio::task::spawn_blocking(move || vanity::search(&pattern, threads, &progress))
+    };
+
+    let result = search.await?;
+    reporter.abort();
+    let (key, attempts) = result?;
+
+    let path = vanity::write_keystore(&key, &args.keystore_dir, &password)?;
+
+    println!("Found an address matching the pattern: {}", key.address());
+    println!("keystore: {}", path.display());
+    println!("attempts: {}", attempts);
+
+    Ok(())
+}
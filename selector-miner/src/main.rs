@@ -0,0 +1,91 @@
+/*
+    mine a function name suffix so the 4-byte selector starts with a given prefix.
+
+    selector = keccak256("setName_a7f(string)")[..4]
+
+    leading zero bytes are cheaper in calldata (4 gas instead of 16 per byte) and sort first in
+    solc's dispatcher, so the function is found after fewer comparisons. two zero bytes:
+
+    cargo run --release -- --function "setName(string)" --target 0000
+
+    with `--abi` the other functions and the custom errors of the contract are checked too, and
+    suffixes whose selector would clash with one of them are skipped. the ABI can be a forge
+    artifact:
+
+    cargo run --release -- --function "setName(string)" --abi ../tx-hash-miner-data/dynamic-example/out/DynamicExample.sol/DynamicExample.json
+
+    rename the function in the contract and its `sol!` bindings (tx-hash-miner-data/gas) to match.
+*/
+
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use alloy::primitives::{keccak256, Selector};
+use clap::Parser;
+use eyre::{ensure, Result};
+use miner_core::{
+    engine::{self, Progress},
+    selector, Options, Target,
+};
+
+#[derive(Parser, Debug)]
+struct Args {
+    /// Function to rename, e.g. `setName(string)` or `function setName(string name)`.
+    #[arg(long)]
+    function: String,
+    /// Hex prefix the selector has to start with.
+    #[arg(long, default_value = "0000")]
+    target: Target,
+    /// Contract ABI or forge artifact; selectors of its other functions and errors are avoided.
+    #[arg(long)]
+    abi: Option<PathBuf>,
+    /// Search threads; defaults to all cores.
+    #[arg(long)]
+    threads: Option<usize>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let function = selector::parse_function(&args.function)?;
+    let signature = function.signature();
+
+    let others = match &args.abi {
+        Some(path) => selector::taken_selectors(path, &signature)?,
+        None => HashMap::new(),
+    };
+
+    println!("Original: {} {}", function.selector(), signature);
+    if let Some(clash) = others.get(&function.selector()) {
+        println!("warning: the original selector already collides with {clash}");
+    }
+
+    let target = args.target.clone();
+    let threads = args.threads.unwrap_or(Options::default().threads);
+
+    let progress = Arc::new(Progress::default());
+    let reporter = engine::spawn_reporter(progress.clone());
+
+    let search = {
+        let (progress, others) = (progress.clone(), others.clone());
+        tokio::task::spawn_blocking(move || {
+            selector::search(&function, &target, &others, threads, &progress)
+        })
+    };
+
+    let result = search.await?;
+    reporter.abort();
+    let ((signature, selector), attempts) = result?;
+
+    // recompute with alloy rather than trusting the batched hashing
+    let expected = Selector::from_slice(&keccak256(signature.as_bytes())[..4]);
+    ensure!(selector == expected, "mined selector {selector} but alloy computes {expected}");
+
+    println!("Found a selector starting with prefix: {} {}", selector, signature);
+    if args.abi.is_some() {
+        println!("no collision with the contract's {} other selectors", others.len());
+    }
+    println!("attempts: {}", attempts);
+
+    Ok(())
+}
@@ -0,0 +1,201 @@
+//! Calldata decoding against a local directory of ABIs.
+//!
+//! `<address>.json` files describe the contract at that address; any other `.json` file is a
+//! generic interface (ERC-20, a router, ...) tried on every address. Either can be a plain ABI
+//! array or a compiler artifact with an `abi` field. Selectors no ABI knows are tried against
+//! the text signatures of a [`SignatureDb`], without argument names.
+
+use std::{borrow::Cow, collections::HashMap, fmt, path::Path};
+
+use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy_json_abi::{Function, JsonAbi};
+use alloy_primitives::{hex, Address, FixedBytes};
+use eyre::{Result, WrapErr};
+
+use crate::SignatureDb;
+
+#[derive(Debug, Default)]
+pub struct AbiRegistry {
+    by_address: HashMap<Address, HashMap<FixedBytes<4>, Vec<Function>>>,
+    generic: HashMap<FixedBytes<4>, Vec<Function>>,
+    signatures: SignatureDb,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arg {
+    pub name: String,
+    pub ty: String,
+    pub value: DynSolValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Call {
+    Decoded {
+        name: String,
+        signature: String,
+        args: Vec<Arg>,
+    },
+    /// No ABI knows the selector, or none decodes the arguments.
+    Unknown {
+        selector: FixedBytes<4>,
+        data: Vec<u8>,
+    },
+    /// Plain value transfer, or calldata too short for a selector.
+    Raw(Vec<u8>),
+}
+
+impl AbiRegistry {
+    /// Loads every `.json` file in `dir`.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut registry = Self::default();
+        let entries =
+            std::fs::read_dir(dir).wrap_err_with(|| format!("failed to read {}", dir.display()))?;
+
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let abi = read_abi(&path)?;
+            let address = path.file_stem().and_then(|stem| stem.to_str()?.parse().ok());
+            match address {
+                Some(address) => registry.add_contract(address, &abi),
+                None => registry.add_interface(&abi),
+            }
+        }
+
+        Ok(registry)
+    }
+
+    pub fn add_contract(&mut self, address: Address, abi: &JsonAbi) {
+        index(self.by_address.entry(address).or_default(), abi);
+    }
+
+    pub fn add_interface(&mut self, abi: &JsonAbi) {
+        index(&mut self.generic, abi);
+    }
+
+    pub fn set_signatures(&mut self, signatures: SignatureDb) {
+        self.signatures = signatures;
+    }
+
+    /// Decodes `input` sent to `to`, preferring the ABI of that address over the interfaces.
+    pub fn decode(&self, to: Option<Address>, input: &[u8]) -> Call {
+        let Some((selector, data)) = input.split_first_chunk::<4>() else {
+            return Call::Raw(input.to_vec());
+        };
+        let selector = FixedBytes(*selector);
+
+        let own = to.and_then(|to| self.by_address.get(&to)).and_then(|abi| abi.get(&selector));
+        let candidates = own.into_iter().chain(self.generic.get(&selector)).flatten();
+        // overloads and colliding selectors: the first one whose encoding is exactly the
+        // calldata wins, else the first one that decodes at all, as extra bytes are ignored
+        let parsed =
+            self.signatures.function(selector.0).iter().filter_map(|s| Function::parse(s).ok());
+        let mut loose = None;
+        for function in candidates.map(Cow::Borrowed).chain(parsed.map(Cow::Owned)) {
+            let Some((call, exact)) = try_decode(&function, data) else { continue };
+            if exact {
+                return call;
+            }
+            loose.get_or_insert(call);
+        }
+
+        loose.unwrap_or_else(|| Call::Unknown { selector, data: data.to_vec() })
+    }
+}
+
+/// The decoded call, and whether encoding it again gives back all of `data`.
+fn try_decode(function: &Function, data: &[u8]) -> Option<(Call, bool)> {
+    let values = function.abi_decode_input(data, true).ok()?;
+    let exact = DynSolValue::Tuple(values.clone()).abi_encode_params() == data;
+    let args = function
+        .inputs
+        .iter()
+        .zip(values)
+        .map(|(param, value)| Arg {
+            name: param.name.clone(),
+            ty: param.selector_type().into_owned(),
+            value,
+        })
+        .collect();
+    let call = Call::Decoded { name: function.name.clone(), signature: function.signature(), args };
+    Some((call, exact))
+}
+
+fn index(functions: &mut HashMap<FixedBytes<4>, Vec<Function>>, abi: &JsonAbi) {
+    for function in abi.functions() {
+        let known = functions.entry(function.selector()).or_default();
+        if !known.iter().any(|f| f.signature() == function.signature()) {
+            known.push(function.clone());
+        }
+    }
+}
+
+fn read_abi(path: &Path) -> Result<JsonAbi> {
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    let mut json: serde_json::Value = serde_json::from_str(&contents)
+        .wrap_err_with(|| format!("{} is not JSON", path.display()))?;
+    // forge and hardhat artifacts keep the ABI under `abi`
+    if let Some(abi) = json.get_mut("abi") {
+        json = abi.take();
+    }
+    serde_json::from_value(json).wrap_err_with(|| format!("{} is not an ABI", path.display()))
+}
+
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decoded { name, args, .. } => {
+                write!(f, "{name}(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    if !arg.name.is_empty() {
+                        write!(f, "{}: ", arg.name)?;
+                    }
+                    write_value(f, &arg.value)?;
+                }
+                f.write_str(")")
+            }
+            Self::Unknown { selector, data } => write!(f, "{selector}({} bytes)", data.len()),
+            Self::Raw(input) if input.is_empty() => f.write_str("()"),
+            Self::Raw(input) => write!(f, "0x{}", hex::encode(input)),
+        }
+    }
+}
+
+fn write_value(f: &mut fmt::Formatter<'_>, value: &DynSolValue) -> fmt::Result {
+    match value {
+        DynSolValue::Bool(b) => write!(f, "{b}"),
+        DynSolValue::Int(i, _) => write!(f, "{i}"),
+        DynSolValue::Uint(u, _) => write!(f, "{u}"),
+        DynSolValue::FixedBytes(word, size) => write!(f, "0x{}", hex::encode(&word[..*size])),
+        DynSolValue::Address(address) => write!(f, "{address}"),
+        DynSolValue::Function(function) => write!(f, "{function}"),
+        DynSolValue::Bytes(bytes) => write!(f, "0x{}", hex::encode(bytes)),
+        DynSolValue::String(s) => write!(f, "{s:?}"),
+        DynSolValue::Array(values) | DynSolValue::FixedArray(values) => {
+            write_list(f, values, "[", "]")
+        }
+        DynSolValue::Tuple(values) => write_list(f, values, "(", ")"),
+    }
+}
+
+fn write_list(
+    f: &mut fmt::Formatter<'_>,
+    values: &[DynSolValue],
+    open: &str,
+    close: &str,
+) -> fmt::Result {
+    f.write_str(open)?;
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write_value(f, value)?;
+    }
+    f.write_str(close)
+}
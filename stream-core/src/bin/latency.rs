@@ -0,0 +1,49 @@
+/*
+
+Reports how long the pending txs recorded by stream-mempool `--db` took to get mined, as
+percentiles per bucket of the priority fee they paid, plus how many were replaced or dropped.
+
+cargo run --bin latency -- --db mempool.db --buckets 0.1,1,2,5,10
+
+Latency runs from the first sighting to the block timestamp.
+*/
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use eyre::{ensure, Result};
+use stream_core::Store;
+
+#[derive(Parser, Debug)]
+struct Args {
+    /// The database stream-mempool writes with `--db`.
+    #[arg(long, default_value = "mempool.db")]
+    db: PathBuf,
+    /// Bucket bounds in gwei, ascending.
+    #[arg(long, value_delimiter = ',', default_value = "0.1,1,2,5,10")]
+    buckets: Vec<f64>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    ensure!(args.buckets.is_sorted(), "--buckets must be ascending");
+    let bounds: Vec<u128> = args.buckets.iter().map(|gwei| (gwei * 1e9) as u128).collect();
+
+    let store = Store::open(&args.db)?;
+    let counts = store.counts()?;
+    println!(
+        "{} txs: {} included, {} pending, {} replaced, {} dropped",
+        counts.pending + counts.included + counts.replaced + counts.dropped,
+        counts.included,
+        counts.pending,
+        counts.replaced,
+        counts.dropped
+    );
+
+    println!("{:<12} {:>8} {:>8} {:>8} {:>8}", "tip (gwei)", "txs", "p50", "p90", "p99");
+    for bucket in store.latency_report(&bounds)? {
+        println!("{bucket}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+/*
+
+Looks up function selectors and event topics in the offline signature database, and imports
+4byte or openchain dumps into it.
+
+cargo run --bin signatures -- import openchain-export.json 4byte-signatures.csv
+cargo run --bin signatures -- lookup 0xa9059cbb 0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef
+
+The stream binaries read the same file with `--signatures`.
+*/
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use eyre::Result;
+use stream_core::SignatureDb;
+
+#[derive(Parser, Debug)]
+struct Cli {
+    /// The database file; it starts out with the built-in signatures only.
+    #[arg(long, default_value = "signatures.txt")]
+    db: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the signatures of 4 byte selectors or 32 byte event topics.
+    Lookup { ids: Vec<String> },
+    /// Add the signatures in 4byte or openchain dumps to the database.
+    Import { dumps: Vec<PathBuf> },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let mut db = SignatureDb::open(&cli.db)?;
+
+    match cli.command {
+        Command::Lookup { ids } => {
+            for id in ids {
+                let (kind, signatures) = db.lookup(&id)?;
+                match signatures {
+                    [] => println!("{id}: unknown {kind}"),
+                    [signature] => println!("{id}: {kind} {signature}"),
+                    // a collision; most likely the first, built-in ones come first
+                    _ => {
+                        println!("{id}: {} {kind}s", signatures.len());
+                        for signature in signatures {
+                            println!("  {signature}");
+                        }
+                    }
+                }
+            }
+        }
+        Command::Import { dumps } => {
+            for dump in dumps {
+                let stats = db.import(&std::fs::read_to_string(&dump)?)?;
+                println!(
+                    "{}: {} added, {} already known, {} rejected",
+                    dump.display(),
+                    stats.added,
+                    stats.known,
+                    stats.rejected
+                );
+            }
+            db.save(&cli.db)?;
+            println!("{} signatures in {}", db.len(), cli.db.display());
+        }
+    }
+
+    Ok(())
+}
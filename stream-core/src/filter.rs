@@ -0,0 +1,442 @@
+//! Filter expressions for pending txs, e.g.
+//!
+//! ```text
+//! to in @routers.txt && selector == 0xa9059cbb && value > 1 ether
+//! ```
+//!
+//! Fields: `from`, `to`, `selector`, `value`, `gas_price`, `type` and `calldata_len`.
+//! Comparisons are `==`, `!=`, `<`, `<=`, `>`, `>=` and `in [a, b, ...]`; a list can pull in a
+//! file with `@path`, one value per line, `#` starting a comment. Conditions combine with `&&`,
+//! `||`, `!` and parentheses. Amounts take a `wei`, `gwei` or `ether` unit, `to` can be
+//! compared with `none` for contract creations, and `type` takes a number or a name like
+//! `eip1559`.
+
+use std::{fmt, path::Path};
+
+use alloy_primitives::{Address, FixedBytes, U256};
+use eyre::{bail, ensure, eyre, Result, WrapErr};
+
+/// The fields of a pending tx a filter can look at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxFields {
+    pub from: Address,
+    /// `None` for a contract creation.
+    pub to: Option<Address>,
+    /// `None` when the calldata is shorter than 4 bytes.
+    pub selector: Option<FixedBytes<4>>,
+    pub value: U256,
+    /// The gas price, or the max fee per gas of txs that have one.
+    pub gas_price: u128,
+    pub tx_type: u8,
+    pub calldata_len: usize,
+}
+
+impl TxFields {
+    /// The first 4 bytes of `input`, if it has that many.
+    pub fn selector_of(input: &[u8]) -> Option<FixedBytes<4>> {
+        input.get(..4).map(FixedBytes::from_slice)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    From,
+    To,
+    Selector,
+    Value,
+    GasPrice,
+    Type,
+    CalldataLen,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "from" => Self::From,
+            "to" => Self::To,
+            "selector" => Self::Selector,
+            "value" => Self::Value,
+            "gas_price" => Self::GasPrice,
+            "type" => Self::Type,
+            "calldata_len" => Self::CalldataLen,
+            _ => return None,
+        })
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, Self::Value | Self::GasPrice | Self::CalldataLen)
+    }
+
+    fn of(&self, tx: &TxFields) -> Value {
+        match self {
+            Self::From => Value::Address(Some(tx.from)),
+            Self::To => Value::Address(tx.to),
+            Self::Selector => Value::Selector(tx.selector),
+            Self::Value => Value::Number(tx.value),
+            Self::GasPrice => Value::Number(U256::from(tx.gas_price)),
+            Self::Type => Value::Number(U256::from(tx.tx_type)),
+            Self::CalldataLen => Value::Number(U256::from(tx.calldata_len)),
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::From => "from",
+            Self::To => "to",
+            Self::Selector => "selector",
+            Self::Value => "value",
+            Self::GasPrice => "gas_price",
+            Self::Type => "type",
+            Self::CalldataLen => "calldata_len",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Address(Option<Address>),
+    Selector(Option<FixedBytes<4>>),
+    Number(U256),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Compare { field: Field, cmp: Cmp, value: Value },
+    In { field: Field, values: Vec<Value> },
+}
+
+impl Filter {
+    /// Parses `source`, reading `@path` lists relative to the working directory.
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let filter = parser.or()?;
+        if let Some((token, at)) = parser.tokens.get(parser.pos) {
+            bail!("unexpected {token} at {at}");
+        }
+        Ok(filter)
+    }
+
+    pub fn matches(&self, tx: &TxFields) -> bool {
+        match self {
+            Self::And(a, b) => a.matches(tx) && b.matches(tx),
+            Self::Or(a, b) => a.matches(tx) || b.matches(tx),
+            Self::Not(filter) => !filter.matches(tx),
+            Self::Compare { field, cmp, value } => {
+                let actual = field.of(tx);
+                match (cmp, &actual, value) {
+                    (Cmp::Eq, ..) => actual == *value,
+                    (Cmp::Ne, ..) => actual != *value,
+                    (cmp, Value::Number(a), Value::Number(b)) => match cmp {
+                        Cmp::Lt => a < b,
+                        Cmp::Le => a <= b,
+                        Cmp::Gt => a > b,
+                        Cmp::Ge => a >= b,
+                        Cmp::Eq | Cmp::Ne => unreachable!(),
+                    },
+                    // the parser only orders numbers
+                    _ => false,
+                }
+            }
+            Self::In { field, values } => values.contains(&field.of(tx)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    /// A number, hex string or decimal, unit not included.
+    Literal(String),
+    File(String),
+    Op(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ident(s) | Self::Literal(s) => write!(f, "`{s}`"),
+            Self::File(path) => write!(f, "`@{path}`"),
+            Self::Op(op) => write!(f, "`{op}`"),
+        }
+    }
+}
+
+const OPS: &[&str] = &["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "(", ")", "[", "]", ","];
+
+/// Tokens with their byte offsets, for error messages.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+
+    loop {
+        rest = rest.trim_start();
+        let at = source.len() - rest.len();
+        let Some(c) = rest.chars().next() else { break };
+
+        if let Some(op) = OPS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push((Token::Op(op), at));
+            rest = &rest[op.len()..];
+        } else if c == '@' {
+            let end = rest[1..]
+                .find(|c: char| c.is_whitespace() || c == ',' || c == ']' || c == ')')
+                .map_or(rest.len(), |end| end + 1);
+            ensure!(end > 1, "missing file name after `@` at {at}");
+            tokens.push((Token::File(rest[1..end].to_string()), at));
+            rest = &rest[end..];
+        } else if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                .unwrap_or(rest.len());
+            let word = rest[..end].to_string();
+            if c.is_ascii_digit() || c == '.' {
+                tokens.push((Token::Literal(word), at));
+            } else {
+                tokens.push((Token::Ident(word), at));
+            }
+            rest = &rest[end..];
+        } else {
+            bail!("unexpected `{c}` at {at}");
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<(Token, usize)> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token.ok_or_else(|| eyre!("expected {expected}, found the end of the filter"))
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if self.peek() == Some(&Token::Op(OPS.iter().find(|o| **o == op).unwrap())) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &'static str) -> Result<()> {
+        match self.next(&format!("`{op}`"))? {
+            (Token::Op(found), _) if found == op => Ok(()),
+            (token, at) => bail!("expected `{op}`, found {token} at {at}"),
+        }
+    }
+
+    fn or(&mut self) -> Result<Filter> {
+        let mut filter = self.and()?;
+        while self.eat("||") {
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter> {
+        let mut filter = self.unary()?;
+        while self.eat("&&") {
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+        Ok(filter)
+    }
+
+    fn unary(&mut self) -> Result<Filter> {
+        if self.eat("!") {
+            return Ok(Filter::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let filter = self.or()?;
+            self.expect(")")?;
+            return Ok(filter);
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> Result<Filter> {
+        let field = match self.next("a field")? {
+            (Token::Ident(name), at) => {
+                Field::parse(&name).ok_or_else(|| eyre!("unknown field `{name}` at {at}"))?
+            }
+            (token, at) => bail!("expected a field, found {token} at {at}"),
+        };
+
+        let (token, at) = self.next("a comparison")?;
+        let cmp = match token {
+            Token::Ident(word) if word == "in" => {
+                return Ok(Filter::In { field, values: self.list(field)? });
+            }
+            Token::Op("==") => Cmp::Eq,
+            Token::Op("!=") => Cmp::Ne,
+            Token::Op("<") => Cmp::Lt,
+            Token::Op("<=") => Cmp::Le,
+            Token::Op(">") => Cmp::Gt,
+            Token::Op(">=") => Cmp::Ge,
+            token => bail!("expected a comparison after `{field}`, found {token} at {at}"),
+        };
+        ensure!(
+            matches!(cmp, Cmp::Eq | Cmp::Ne) || field.is_numeric(),
+            "`{field}` can only be compared with `==`, `!=` or `in` (at {at})"
+        );
+
+        Ok(Filter::Compare { field, cmp, value: self.value(field)? })
+    }
+
+    fn list(&mut self, field: Field) -> Result<Vec<Value>> {
+        if let Some(Token::File(_)) = self.peek() {
+            return self.file(field);
+        }
+
+        self.expect("[")?;
+        let mut values = Vec::new();
+        if self.eat("]") {
+            return Ok(values);
+        }
+        loop {
+            if let Some(Token::File(_)) = self.peek() {
+                values.extend(self.file(field)?);
+            } else {
+                values.push(self.value(field)?);
+            }
+            if self.eat("]") {
+                return Ok(values);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn file(&mut self, field: Field) -> Result<Vec<Value>> {
+        let (Token::File(path), _) = self.next("a file")? else { unreachable!() };
+        read_list(Path::new(&path), field)
+    }
+
+    fn value(&mut self, field: Field) -> Result<Value> {
+        let (token, at) = self.next(&format!("a value for `{field}`"))?;
+        let word = match &token {
+            Token::Literal(word) | Token::Ident(word) => word.as_str(),
+            _ => bail!("expected a value for `{field}`, found {token} at {at}"),
+        };
+
+        let value = match field {
+            Field::Value | Field::GasPrice => {
+                let unit = match self.peek() {
+                    Some(Token::Ident(unit)) if unit_decimals(unit).is_some() => {
+                        let unit = unit.clone();
+                        self.pos += 1;
+                        Some(unit)
+                    }
+                    _ => None,
+                };
+                parse_amount(word, unit.as_deref())
+            }
+            Field::Type => parse_tx_type(word),
+            _ => parse_value(field, word),
+        };
+        value.wrap_err_with(|| format!("bad value for `{field}` at {at}"))
+    }
+}
+
+/// Parses a value of `field` as written in a filter or a list file; amounts take no unit.
+fn parse_value(field: Field, word: &str) -> Result<Value> {
+    Ok(match field {
+        Field::From | Field::To if word == "none" || word == "null" => Value::Address(None),
+        Field::From | Field::To => Value::Address(Some(word.parse()?)),
+        Field::Selector => Value::Selector(Some(word.parse()?)),
+        Field::Value | Field::GasPrice => return parse_amount(word, None),
+        Field::Type => return parse_tx_type(word),
+        Field::CalldataLen => Value::Number(parse_integer(word)?),
+    })
+}
+
+fn parse_integer(word: &str) -> Result<U256> {
+    match word.strip_prefix("0x") {
+        Some(hex) => U256::from_str_radix(hex, 16).map_err(|err| eyre!("{err}")),
+        None => U256::from_str_radix(word, 10).map_err(|err| eyre!("{err}")),
+    }
+}
+
+fn unit_decimals(unit: &str) -> Option<usize> {
+    match unit {
+        "wei" => Some(0),
+        "gwei" => Some(9),
+        "ether" | "eth" => Some(18),
+        _ => None,
+    }
+}
+
+/// `1.5` with unit `gwei` is 1500000000 wei; fractions of a wei are an error.
+fn parse_amount(word: &str, unit: Option<&str>) -> Result<Value> {
+    let decimals = unit.and_then(unit_decimals).unwrap_or(0);
+    let (whole, fraction) = word.split_once('.').unwrap_or((word, ""));
+    if fraction.is_empty() && !word.contains('.') {
+        let scale = U256::from(10).pow(U256::from(decimals));
+        let amount = parse_integer(whole)?
+            .checked_mul(scale)
+            .ok_or_else(|| eyre!("{word} {} overflows", unit.unwrap_or("wei")))?;
+        return Ok(Value::Number(amount));
+    }
+
+    ensure!(
+        fraction.len() <= decimals && fraction.chars().all(|c| c.is_ascii_digit()),
+        "{word} {} is not a whole number of wei",
+        unit.unwrap_or("wei")
+    );
+    let digits = format!("{whole}{fraction:0<decimals$}");
+    Ok(Value::Number(U256::from_str_radix(&digits, 10).map_err(|err| eyre!("{err}"))?))
+}
+
+fn parse_tx_type(word: &str) -> Result<Value> {
+    let tx_type = match word {
+        "legacy" => 0,
+        "eip2930" => 1,
+        "eip1559" => 2,
+        "eip4844" => 3,
+        "eip7702" => 4,
+        _ => return Ok(Value::Number(parse_integer(word)?)),
+    };
+    Ok(Value::Number(U256::from(tx_type)))
+}
+
+/// One value of `field` per line; blank lines and `#` comments are skipped.
+pub fn read_list(path: &Path, field: Field) -> Result<Vec<Value>> {
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+
+    let mut values = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let value = parse_value(field, line)
+            .wrap_err_with(|| format!("{}:{}: bad `{field}`", path.display(), number + 1))?;
+        values.push(value);
+    }
+    Ok(values)
+}
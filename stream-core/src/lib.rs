@@ -0,0 +1,21 @@
+//! Shared pieces of the stream binaries that don't depend on their alloy provider version.
+
+pub use alloy_primitives as primitives;
+
+pub mod abi;
+pub mod filter;
+pub mod pipeline;
+pub mod resilient;
+pub mod signatures;
+pub mod sink;
+pub mod store;
+pub mod trace;
+
+pub use abi::{AbiRegistry, Call};
+pub use filter::{Filter, TxFields};
+pub use pipeline::{Counts, DropPolicy, Pipeline, PipelineConfig};
+pub use resilient::{resilient, Backoff, Event};
+pub use signatures::SignatureDb;
+pub use sink::{NdjsonSink, OutputArgs, Record, Sink, SinkWriter};
+pub use store::Store;
+pub use trace::{Backend, CallNode, GethTracer, StateDiff, TraceResult};
@@ -0,0 +1,241 @@
+//! A bounded queue in front of a fixed number of workers, for per-item RPC calls that are too
+//! slow to make one after another and too many to make all at once.
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use futures::FutureExt;
+use tokio::{sync::Notify, task::JoinHandle};
+
+/// What to give up when an item arrives at a full queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DropPolicy {
+    /// Keep the queue as is and drop the arriving item.
+    Newest,
+    /// Drop the item that waited longest, it's the most likely to be stale.
+    Oldest,
+}
+
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+    /// Items worked on at the same time.
+    pub concurrency: usize,
+    /// Items waiting for a worker, on top of the ones being worked on.
+    pub queue: usize,
+    /// Each item fails once its work runs longer than this.
+    pub timeout: Duration,
+    pub drop_policy: DropPolicy,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            queue: 1_000,
+            timeout: Duration::from_secs(10),
+            drop_policy: DropPolicy::Oldest,
+        }
+    }
+}
+
+/// How many items ended which way, so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub submitted: u64,
+    pub completed: u64,
+    pub failed: u64,
+    pub timed_out: u64,
+    /// Dropped by the drop policy, or left in the queue at shutdown.
+    pub dropped: u64,
+    /// In flight when a shutdown without drain abandoned them.
+    pub cancelled: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    submitted: AtomicU64,
+    completed: AtomicU64,
+    failed: AtomicU64,
+    timed_out: AtomicU64,
+    dropped: AtomicU64,
+    cancelled: AtomicU64,
+    /// Taken off the queue and not counted any other way yet.
+    in_flight: AtomicU64,
+}
+
+impl Counters {
+    fn snapshot(&self) -> Counts {
+        Counts {
+            submitted: self.submitted.load(Ordering::Relaxed),
+            completed: self.completed.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            cancelled: self.cancelled.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct Shared<T> {
+    queue: Mutex<VecDeque<T>>,
+    ready: Notify,
+    closed: AtomicBool,
+    counters: Counters,
+}
+
+pub struct Pipeline<T> {
+    shared: Arc<Shared<T>>,
+    config: PipelineConfig,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl<T: fmt::Display + Send + 'static> Pipeline<T> {
+    /// Starts `config.concurrency` workers running `work` on submitted items.
+    ///
+    /// Failures, timeouts and panics in `work` are logged with the item and counted; they never
+    /// stop the pipeline.
+    pub fn spawn<F, Fut>(config: PipelineConfig, work: F) -> Self
+    where
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = eyre::Result<()>> + Send + 'static,
+    {
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(VecDeque::with_capacity(config.queue)),
+            ready: Notify::new(),
+            closed: AtomicBool::new(false),
+            counters: Counters::default(),
+        });
+        let work = Arc::new(work);
+
+        let workers = (0..config.concurrency.max(1))
+            .map(|_| tokio::spawn(worker(shared.clone(), work.clone(), config.timeout)))
+            .collect();
+
+        Self { shared, config, workers }
+    }
+
+    /// Queues `item`, applying the drop policy if the queue is full. False if `item` itself
+    /// was dropped.
+    pub fn submit(&self, item: T) -> bool {
+        let counters = &self.shared.counters;
+        counters.submitted.fetch_add(1, Ordering::Relaxed);
+
+        {
+            let mut queue = self.shared.queue.lock().unwrap();
+            if queue.len() >= self.config.queue {
+                counters.dropped.fetch_add(1, Ordering::Relaxed);
+                match self.config.drop_policy {
+                    DropPolicy::Newest => return false,
+                    DropPolicy::Oldest => {
+                        queue.pop_front();
+                    }
+                }
+            }
+            queue.push_back(item);
+        }
+
+        self.shared.ready.notify_one();
+        true
+    }
+
+    pub fn counts(&self) -> Counts {
+        self.shared.counters.snapshot()
+    }
+
+    /// Items waiting for a worker.
+    pub fn queued(&self) -> usize {
+        self.shared.queue.lock().unwrap().len()
+    }
+
+    /// Stops taking items. With `drain` the workers finish everything queued first; otherwise
+    /// the queue is dropped and calls in flight are abandoned.
+    pub async fn shutdown(self, drain: bool) -> Counts {
+        self.shared.closed.store(true, Ordering::Relaxed);
+
+        if !drain {
+            let abandoned = {
+                let mut queue = self.shared.queue.lock().unwrap();
+                let abandoned = queue.len() as u64;
+                queue.clear();
+                abandoned
+            };
+            self.shared.counters.dropped.fetch_add(abandoned, Ordering::Relaxed);
+            for worker in &self.workers {
+                worker.abort();
+            }
+        }
+
+        self.shared.ready.notify_waiters();
+        for worker in self.workers {
+            let _ = worker.await;
+        }
+
+        // the aborted workers never got to count what they were working on
+        let counters = &self.shared.counters;
+        counters
+            .cancelled
+            .fetch_add(counters.in_flight.swap(0, Ordering::Relaxed), Ordering::Relaxed);
+        counters.snapshot()
+    }
+}
+
+async fn worker<T, F, Fut>(shared: Arc<Shared<T>>, work: Arc<F>, timeout: Duration)
+where
+    T: fmt::Display,
+    F: Fn(T) -> Fut,
+    Fut: Future<Output = eyre::Result<()>>,
+{
+    while let Some(item) = next_item(&shared).await {
+        let label = item.to_string();
+        let run = AssertUnwindSafe(work(item)).catch_unwind();
+        let counters = &shared.counters;
+
+        match tokio::time::timeout(timeout, run).await {
+            Ok(Ok(Ok(()))) => {
+                counters.completed.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(Ok(Err(err))) => {
+                eprintln!("{label}: {err}");
+                counters.failed.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(Err(_)) => {
+                eprintln!("{label}: panicked");
+                counters.failed.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(_) => {
+                eprintln!("{label}: timed out after {timeout:?}");
+                counters.timed_out.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        counters.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The next queued item, waiting for one if needed; `None` once closed and drained.
+async fn next_item<T>(shared: &Shared<T>) -> Option<T> {
+    loop {
+        // registered before checking, so a submit or shutdown in between still wakes us
+        let ready = shared.ready.notified();
+        tokio::pin!(ready);
+        ready.as_mut().enable();
+
+        if let Some(item) = shared.queue.lock().unwrap().pop_front() {
+            shared.counters.in_flight.fetch_add(1, Ordering::Relaxed);
+            return Some(item);
+        }
+        if shared.closed.load(Ordering::Relaxed) {
+            return None;
+        }
+        ready.await;
+    }
+}
@@ -0,0 +1,168 @@
+//! Subscriptions that outlive node restarts: when the stream ends, fails to deliver for too
+//! long, or can't be opened, subscribe again with exponential backoff and jitter.
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    time::{Duration, Instant},
+};
+
+use futures::{Stream, StreamExt};
+use rand::Rng;
+
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    /// Each delay is shortened by a random fraction up to this, so restarted clients don't
+    /// all reconnect at the same moment.
+    pub jitter: f64,
+    /// Treat the subscription as dead after this long without an item; a node that went away
+    /// without closing the socket otherwise hangs the stream forever.
+    pub idle_timeout: Option<Duration>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(500),
+            max: Duration::from_secs(30),
+            jitter: 0.3,
+            idle_timeout: None,
+        }
+    }
+}
+
+impl Backoff {
+    /// The wait before retry number `attempt`, counting from 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponential = self.initial.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+        let delay = exponential.min(self.max);
+        let jitter = rand::thread_rng().gen_range(0.0..=self.jitter.clamp(0.0, 1.0));
+        delay.mul_f64(1.0 - jitter)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T> {
+    Item(T),
+    /// The subscription was lost; items sent from now until `Reconnected` are missed.
+    Disconnected {
+        reason: String,
+    },
+    /// Subscribing failed; trying again after `delay`.
+    Retrying {
+        attempt: u32,
+        delay: Duration,
+        error: String,
+    },
+    /// Subscribed again, `downtime` after the disconnect.
+    Reconnected {
+        downtime: Duration,
+        attempts: u32,
+    },
+}
+
+struct State<F, S, T> {
+    subscribe: F,
+    backoff: Backoff,
+    stream: Option<Pin<Box<S>>>,
+    /// When the subscription was lost, `None` before the first one.
+    down_since: Option<Instant>,
+    attempt: u32,
+    /// Backoff to sleep off before the next subscribe attempt.
+    wait: Option<Duration>,
+    queued: VecDeque<Event<T>>,
+}
+
+/// Subscribes with `subscribe` and keeps subscribing again whenever the stream ends, yielding
+/// its items along with the gaps in between. The stream itself never ends.
+///
+/// `subscribe` should open a fresh connection each time, not reuse one that may be dead.
+pub fn resilient<T, F, Fut, S>(subscribe: F, backoff: Backoff) -> impl Stream<Item = Event<T>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = eyre::Result<S>>,
+    S: Stream<Item = T>,
+{
+    let state = State {
+        subscribe,
+        backoff,
+        stream: None,
+        down_since: None,
+        attempt: 0,
+        wait: None,
+        queued: VecDeque::new(),
+    };
+
+    futures::stream::unfold(state, |mut state| async move {
+        let event = state.next().await;
+        Some((event, state))
+    })
+}
+
+impl<T, F, Fut, S> State<F, S, T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = eyre::Result<S>>,
+    S: Stream<Item = T>,
+{
+    async fn next(&mut self) -> Event<T> {
+        loop {
+            if let Some(event) = self.queued.pop_front() {
+                return event;
+            }
+
+            let Some(stream) = &mut self.stream else {
+                self.connect().await;
+                continue;
+            };
+
+            let next = match self.backoff.idle_timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, stream.next()).await {
+                    Ok(next) => next,
+                    Err(_) => return self.lost(format!("no item for {timeout:?}")),
+                },
+                None => stream.next().await,
+            };
+            match next {
+                Some(item) => return Event::Item(item),
+                None => return self.lost("subscription ended".to_string()),
+            }
+        }
+    }
+
+    fn lost(&mut self, reason: String) -> Event<T> {
+        self.stream = None;
+        self.down_since = Some(Instant::now());
+        Event::Disconnected { reason }
+    }
+
+    async fn connect(&mut self) {
+        if let Some(delay) = self.wait.take() {
+            tokio::time::sleep(delay).await;
+        }
+
+        match (self.subscribe)().await {
+            Ok(stream) => {
+                self.stream = Some(Box::pin(stream));
+                let attempts = std::mem::take(&mut self.attempt) + 1;
+                if let Some(since) = self.down_since.take() {
+                    self.queued
+                        .push_back(Event::Reconnected { downtime: since.elapsed(), attempts });
+                }
+            }
+            Err(err) => {
+                self.attempt += 1;
+                let delay = self.backoff.delay(self.attempt);
+                self.queued.push_back(Event::Retrying {
+                    attempt: self.attempt,
+                    delay,
+                    error: err.to_string(),
+                });
+                self.wait = Some(delay);
+            }
+        }
+    }
+}
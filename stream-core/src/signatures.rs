@@ -0,0 +1,250 @@
+//! Offline lookup of function selectors and event topics.
+//!
+//! The database is a text file of `function <signature>` and `event <signature>` lines on top
+//! of a built-in list of common signatures. Dumps from 4byte.directory (API pages or the CSV
+//! export) and openchain (`/signature-database/v1/export`) import into it; ids given in a dump
+//! are checked against the signature, so mislabelled rows are rejected instead of stored.
+//!
+//! Different signatures can share a selector; all of them are kept, built-in ones first. Callers
+//! that have the calldata pick the one it decodes with.
+
+use std::{collections::HashMap, fmt, path::Path};
+
+use alloy_primitives::{hex, keccak256};
+use eyre::{bail, Result, WrapErr};
+use serde_json::Value;
+
+const EMBEDDED: &str = include_str!("signatures.txt");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Function,
+    Event,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Function => "function",
+            Self::Event => "event",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportStats {
+    pub added: usize,
+    pub known: usize,
+    /// Malformed, or not matching the id given next to it.
+    pub rejected: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SignatureDb {
+    functions: HashMap<[u8; 4], Vec<String>>,
+    events: HashMap<[u8; 32], Vec<String>>,
+}
+
+impl SignatureDb {
+    /// Only the built-in signatures.
+    pub fn embedded() -> Self {
+        let mut db = Self::default();
+        db.import(EMBEDDED).expect("built-in signatures are valid");
+        db
+    }
+
+    /// The built-in signatures plus those saved at `path`, if it exists yet.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut db = Self::embedded();
+        if path.exists() {
+            let contents = std::fs::read_to_string(path)
+                .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+            db.import(&contents).wrap_err_with(|| format!("bad database {}", path.display()))?;
+        }
+        Ok(db)
+    }
+
+    /// Writes every signature, built-in ones included, in the format [`Self::open`] reads.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let functions = self.functions.values().flatten().map(|s| (Kind::Function, s));
+        let events = self.events.values().flatten().map(|s| (Kind::Event, s));
+        let mut lines: Vec<String> = functions
+            .chain(events)
+            .map(|(kind, signature)| format!("{kind} {signature}"))
+            .collect();
+        lines.sort();
+        let mut contents = lines.join("\n");
+        contents.push('\n');
+        std::fs::write(path, contents)
+            .wrap_err_with(|| format!("failed to write {}", path.display()))
+    }
+
+    pub fn function(&self, selector: [u8; 4]) -> &[String] {
+        self.functions.get(&selector).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn event(&self, topic: [u8; 32]) -> &[String] {
+        self.events.get(&topic).map_or(&[], Vec::as_slice)
+    }
+
+    /// Functions and events, by hex id of either length.
+    pub fn lookup(&self, id: &str) -> Result<(Kind, &[String])> {
+        let bytes = hex::decode(id).wrap_err_with(|| format!("{id} is not hex"))?;
+        match bytes.len() {
+            4 => Ok((Kind::Function, self.function(bytes.try_into().unwrap()))),
+            32 => Ok((Kind::Event, self.event(bytes.try_into().unwrap()))),
+            len => bail!("{id} is {len} bytes, expected a 4 byte selector or a 32 byte topic"),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.functions.values().chain(self.events.values()).map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds `signature`; false if it was already known.
+    pub fn insert(&mut self, kind: Kind, signature: &str) -> bool {
+        let hash = keccak256(signature);
+        let known = match kind {
+            Kind::Function => self.functions.entry(hash[..4].try_into().unwrap()).or_default(),
+            Kind::Event => self.events.entry(hash.0).or_default(),
+        };
+        if known.iter().any(|s| s == signature) {
+            return false;
+        }
+        known.push(signature.to_string());
+        true
+    }
+
+    /// Imports a 4byte or openchain JSON dump, or lines holding a signature and optionally its
+    /// hex id, which covers the 4byte CSV export and this database's own format.
+    pub fn import(&mut self, dump: &str) -> Result<ImportStats> {
+        let mut stats = ImportStats::default();
+
+        if dump.trim_start().starts_with('{') {
+            let json: Value = serde_json::from_str(dump).wrap_err("dump is not valid JSON")?;
+            let entries = json_entries(&json)?;
+            for (id, kind, signature) in entries {
+                self.add(&mut stats, Some(id), kind, signature);
+            }
+            return Ok(stats);
+        }
+
+        for line in dump.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_line(line) {
+                Some((id, kind, signature)) => self.add(&mut stats, id, kind, signature),
+                None => stats.rejected += 1,
+            }
+        }
+        Ok(stats)
+    }
+
+    fn add(&mut self, stats: &mut ImportStats, id: Option<&str>, kind: Kind, signature: &str) {
+        if !is_signature(signature) || id.is_some_and(|id| !id_matches(id, kind, signature)) {
+            stats.rejected += 1;
+        } else if self.insert(kind, signature) {
+            stats.added += 1;
+        } else {
+            stats.known += 1;
+        }
+    }
+}
+
+/// `(id, kind, signature)` of each entry in a JSON dump.
+fn json_entries(json: &Value) -> Result<Vec<(&str, Kind, &str)>> {
+    let mut entries = Vec::new();
+
+    // 4byte API page: {"results": [{"text_signature": ..., "hex_signature": ...}]}
+    if let Some(results) = json.get("results").and_then(Value::as_array) {
+        for result in results {
+            let (Some(signature), Some(id)) = (
+                result.get("text_signature").and_then(Value::as_str),
+                result.get("hex_signature").and_then(Value::as_str),
+            ) else {
+                continue;
+            };
+            let kind = if id.len() > 10 { Kind::Event } else { Kind::Function };
+            entries.push((id, kind, signature));
+        }
+        return Ok(entries);
+    }
+
+    // openchain: {"result": {"function": {id: [{"name": ..., "filtered": bool}]}, "event": ...}}
+    if let Some(result) = json.get("result") {
+        for (key, kind) in [("function", Kind::Function), ("event", Kind::Event)] {
+            let Some(ids) = result.get(key).and_then(Value::as_object) else { continue };
+            for (id, signatures) in ids {
+                for signature in signatures.as_array().into_iter().flatten() {
+                    // openchain flags spam it found for a selector as filtered
+                    if signature.get("filtered").and_then(Value::as_bool) == Some(true) {
+                        continue;
+                    }
+                    if let Some(name) = signature.get("name").and_then(Value::as_str) {
+                        entries.push((id.as_str(), kind, name));
+                    }
+                }
+            }
+        }
+        return Ok(entries);
+    }
+
+    bail!("unknown JSON dump, expected a 4byte `results` page or an openchain export")
+}
+
+/// Finds the signature in a line of any text format, along with a hex id next to it.
+fn parse_line(line: &str) -> Option<(Option<&str>, Kind, &str)> {
+    let open = line.find('(')?;
+    let start = line[..open]
+        .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+        .map_or(0, |i| i + 1);
+
+    let mut depth = 0;
+    let mut end = None;
+    for (i, c) in line[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => continue,
+        }
+        if depth == 0 {
+            end = Some(open + i + 1);
+            break;
+        }
+    }
+    let signature = &line[start..end?];
+
+    let id = line
+        .split(|c: char| c == ',' || c == '"' || c.is_whitespace())
+        .find(|word| word.starts_with("0x") && matches!(word.len(), 10 | 66));
+    let kind = match id {
+        Some(id) if id.len() == 66 => Kind::Event,
+        _ if line[..start].trim_end().ends_with("event") => Kind::Event,
+        _ => Kind::Function,
+    };
+    Some((id, kind, signature))
+}
+
+/// `name(types)` with nothing around it; the types themselves aren't checked.
+fn is_signature(signature: &str) -> bool {
+    let Some((name, rest)) = signature.split_once('(') else { return false };
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && rest.ends_with(')')
+        && !signature.contains(char::is_whitespace)
+}
+
+fn id_matches(id: &str, kind: Kind, signature: &str) -> bool {
+    let Ok(id) = hex::decode(id) else { return false };
+    let hash = keccak256(signature);
+    match kind {
+        Kind::Function => id == hash[..4],
+        Kind::Event => id == hash[..],
+    }
+}
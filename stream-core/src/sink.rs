@@ -0,0 +1,426 @@
+//! Machine-readable output: one JSON object per line (NDJSON), to stdout or to a file that
+//! rotates by size.
+//!
+//! # Schema, version 1
+//!
+//! Every line has `v` (the schema version, bumped on any change that can break a consumer),
+//! `kind` and `ts` (unix milliseconds when it was written), plus the fields of its kind.
+//! Addresses, hashes and byte strings are 0x-prefixed hex; wei amounts are decimal strings, as
+//! they overflow JSON numbers. Fields that don't apply are `null`, never missing, unless
+//! projected away.
+//!
+//! - `pending_tx`: `hash`, `from`, `to` (null for a create), `nonce`, `value`, `gas_price`
+//!   (max fee per gas on EIP-1559 txs), `priority_fee` (null on legacy txs), `gas_limit`,
+//!   `tx_type`, `input_len`, `selector`, `function` (text signature, if known).
+//! - `trace`: `tx_hash`, `backend` (`parity` or `geth`), `calls` (count, the top-level one
+//!   included), `error` (why the tx reverts), `state_diff` (address to `balance`, `nonce`,
+//!   `code` as `[before, after]` or null, and `storage` as slot to `[before, after]`),
+//!   `selectors` (list of `selector`, `size`, `count`).
+//! - `call`: one per call in a trace: `tx_hash`, `block`, `depth` (0 for the tx itself),
+//!   `call_type`, `from`, `to`, `value`, `selector`, `function`, `gas_used`, `error`.
+//! - `block`: `number`, `hash`, `traces` (count).
+//!
+//! `--fields` keeps only the named top-level fields; `v`, `kind` and `ts` always stay so lines
+//! can still be told apart.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use alloy_primitives::{Address, Bytes, FixedBytes, B256, U256};
+use clap::{builder::ArgPredicate, ValueEnum};
+use eyre::{ensure, Result, WrapErr};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    store::unix_ms, trace::SelectorCount, AbiRegistry, Backend, Call, CallNode, StateDiff,
+};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Record {
+    PendingTx(PendingTxRecord),
+    Trace(TraceRecord),
+    Call(CallRecord),
+    Block(BlockRecord),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingTxRecord {
+    pub hash: B256,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub nonce: u64,
+    #[serde(serialize_with = "decimal")]
+    pub value: U256,
+    #[serde(serialize_with = "decimal")]
+    pub gas_price: u128,
+    #[serde(serialize_with = "optional_decimal")]
+    pub priority_fee: Option<u128>,
+    pub gas_limit: u64,
+    pub tx_type: u8,
+    pub input_len: usize,
+    pub selector: Option<FixedBytes<4>>,
+    pub function: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceRecord {
+    pub tx_hash: B256,
+    pub backend: String,
+    pub calls: Option<usize>,
+    pub error: Option<String>,
+    pub state_diff: Option<BTreeMap<Address, AccountRecord>>,
+    pub selectors: Option<Vec<SelectorRecord>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountRecord {
+    #[serde(serialize_with = "optional_decimal_pair")]
+    pub balance: Option<(U256, U256)>,
+    pub nonce: Option<(u64, u64)>,
+    pub code: Option<(Bytes, Bytes)>,
+    pub storage: BTreeMap<B256, (B256, B256)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelectorRecord {
+    pub selector: FixedBytes<4>,
+    pub size: usize,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CallRecord {
+    pub tx_hash: Option<B256>,
+    pub block: Option<u64>,
+    pub depth: usize,
+    pub call_type: String,
+    pub from: Address,
+    pub to: Option<Address>,
+    #[serde(serialize_with = "decimal")]
+    pub value: U256,
+    pub selector: Option<FixedBytes<4>>,
+    pub function: Option<String>,
+    pub gas_used: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockRecord {
+    pub number: u64,
+    pub hash: Option<B256>,
+    pub traces: usize,
+}
+
+impl TraceRecord {
+    /// `backend` is the one that served the trace, parity unless it's geth.
+    pub fn new(
+        tx_hash: B256,
+        backend: Backend,
+        calls: Option<&CallNode>,
+        state_diff: Option<&StateDiff>,
+        selectors: Option<&[SelectorCount]>,
+    ) -> Self {
+        let state_diff = state_diff.map(|diff| {
+            (diff.iter())
+                .map(|(address, account)| {
+                    let account = AccountRecord {
+                        balance: account.balance,
+                        nonce: account.nonce,
+                        code: account.code.clone(),
+                        storage: account.storage.clone(),
+                    };
+                    (*address, account)
+                })
+                .collect()
+        });
+        let selectors = selectors.map(|selectors| {
+            (selectors.iter())
+                .map(|s| SelectorRecord {
+                    selector: s.selector.into(),
+                    size: s.size,
+                    count: s.count,
+                })
+                .collect()
+        });
+
+        Self {
+            tx_hash,
+            backend: if backend == Backend::Geth { "geth" } else { "parity" }.to_string(),
+            calls: calls.map(|root| root.walk().len()),
+            error: calls.and_then(|root| root.error.clone()),
+            state_diff,
+            selectors,
+        }
+    }
+}
+
+impl CallRecord {
+    /// One record per call in the tree under `root`, named with `abis`.
+    pub fn tree(
+        tx_hash: Option<B256>,
+        block: Option<u64>,
+        root: &CallNode,
+        abis: &AbiRegistry,
+    ) -> Vec<Self> {
+        (root.walk().into_iter())
+            .map(|(depth, node)| {
+                let calls = !node.kind.is_create();
+                Self {
+                    tx_hash,
+                    block,
+                    depth,
+                    call_type: node.kind.to_string(),
+                    from: node.from,
+                    to: node.to,
+                    value: node.value,
+                    selector: calls
+                        .then(|| node.input.get(..4).map(FixedBytes::from_slice))
+                        .flatten(),
+                    function: calls.then(|| function(abis, node.to, &node.input)).flatten(),
+                    gas_used: node.gas_used,
+                    error: node.error.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The text signature `input` calls, if `abis` knows it.
+pub fn function(abis: &AbiRegistry, to: Option<Address>, input: &[u8]) -> Option<String> {
+    match abis.decode(to, input) {
+        Call::Decoded { signature, .. } => Some(signature),
+        _ => None,
+    }
+}
+
+fn decimal<S: Serializer, T: ToString>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+fn optional_decimal<S: Serializer>(value: &Option<u128>, serializer: S) -> Result<S::Ok, S::Error> {
+    value.map(|value| value.to_string()).serialize(serializer)
+}
+
+fn optional_decimal_pair<S: Serializer>(
+    value: &Option<(U256, U256)>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    value.map(|(before, after)| [before.to_string(), after.to_string()]).serialize(serializer)
+}
+
+/// Where stream events go.
+pub trait Sink: Send {
+    fn emit(&mut self, record: Record) -> Result<()>;
+}
+
+enum SinkOp {
+    Record(Box<Record>),
+    Flush(oneshot::Sender<u64>),
+}
+
+/// Hands a sink to a blocking thread, so writes and file rotations never stall the runtime. A
+/// record that can't be written, say once the reader of stdout went away or the file failed to
+/// rotate, is reported and counted instead of ending the stream.
+#[derive(Clone)]
+pub struct SinkWriter {
+    ops: mpsc::UnboundedSender<SinkOp>,
+}
+
+impl SinkWriter {
+    /// Has to be called on a tokio runtime.
+    pub fn spawn(mut sink: impl Sink + 'static) -> Self {
+        let (ops, mut rx) = mpsc::unbounded_channel();
+        tokio::task::spawn_blocking(move || {
+            let mut errors = 0;
+            while let Some(op) = rx.blocking_recv() {
+                match op {
+                    SinkOp::Record(record) => {
+                        if let Err(err) = sink.emit(*record) {
+                            eprintln!("failed to write a record: {err:#}");
+                            errors += 1;
+                        }
+                    }
+                    SinkOp::Flush(reply) => {
+                        let _ = reply.send(errors);
+                    }
+                }
+            }
+        });
+        Self { ops }
+    }
+
+    pub fn emit(&self, record: Record) {
+        // the writer only stops once every sender is gone
+        let _ = self.ops.send(SinkOp::Record(Box::new(record)));
+    }
+
+    /// How many records couldn't be written, once every record emitted so far was tried.
+    pub async fn flush(&self) -> u64 {
+        let (reply, errors) = oneshot::channel();
+        let _ = self.ops.send(SinkOp::Flush(reply));
+        errors.await.unwrap_or_default()
+    }
+}
+
+pub struct NdjsonSink {
+    out: Box<dyn Write + Send>,
+    fields: Vec<String>,
+}
+
+impl NdjsonSink {
+    /// Lines go to `out`; `fields`, if not empty, is the projection.
+    pub fn new(out: Box<dyn Write + Send>, fields: Vec<String>) -> Self {
+        Self { out, fields }
+    }
+
+    /// The JSON object for `record` as it would be written.
+    pub fn line(&self, record: Record) -> Result<Value> {
+        let mut value = serde_json::to_value(record)?;
+        let object = value.as_object_mut().expect("records are structs");
+        if !self.fields.is_empty() {
+            object.retain(|key, _| key == "kind" || self.fields.contains(key));
+        }
+
+        let mut line = serde_json::Map::new();
+        line.insert("v".into(), SCHEMA_VERSION.into());
+        line.insert("kind".into(), object.remove("kind").unwrap_or_default());
+        line.insert("ts".into(), unix_ms().into());
+        line.append(object);
+        Ok(Value::Object(line))
+    }
+}
+
+impl Sink for NdjsonSink {
+    fn emit(&mut self, record: Record) -> Result<()> {
+        let mut line = serde_json::to_vec(&self.line(record)?)?;
+        line.push(b'\n');
+        // one write per line, so a rotating file only ever splits between lines
+        self.out.write_all(&line)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// A file that moves to `<path>.1` once it reaches `max_bytes`, shifting older ones up to
+/// `<path>.<keep>` and deleting what falls off the end.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: BufWriter<File>,
+    written: u64,
+}
+
+impl RotatingFile {
+    /// Appends to `path` if it exists.
+    pub fn open(path: &Path, max_bytes: u64, keep: usize) -> Result<Self> {
+        let file = open_append(path)?;
+        let written = file.metadata()?.len();
+        Ok(Self { path: path.to_path_buf(), max_bytes, keep, file: BufWriter::new(file), written })
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{n}"));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(self.rotated(self.keep));
+            for n in (1..self.keep).rev() {
+                let from = self.rotated(n);
+                if from.exists() {
+                    fs::rename(from, self.rotated(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated(1))?;
+        }
+        self.file = BufWriter::new(open_append(&self.path).map_err(io::Error::other)?);
+        self.written = 0;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> Result<File> {
+    File::options()
+        .create(true)
+        .append(true)
+        .open(path)
+        .wrap_err_with(|| format!("failed to open {}", path.display()))
+}
+
+impl Write for RotatingFile {
+    /// Rotates before a write that would go past the size limit, never in the middle of one.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(buf)?;
+        self.written += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Human-readable lines.
+    Text,
+    /// One JSON object per event, see the schema in `stream_core::sink`.
+    Ndjson,
+}
+
+/// Output options shared by the stream binaries.
+#[derive(Debug, Clone, clap::Args)]
+pub struct OutputArgs {
+    #[arg(
+        long,
+        value_enum,
+        default_value = "text",
+        default_value_if("out", ArgPredicate::IsPresent, "ndjson")
+    )]
+    pub format: Format,
+    /// Write NDJSON to this file instead of stdout; implies `--format ndjson`.
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+    /// Rotate `--out` once it reaches this many megabytes.
+    #[arg(long, default_value_t = 100)]
+    pub rotate_mb: u64,
+    /// Rotated files to keep next to `--out`.
+    #[arg(long, default_value_t = 5)]
+    pub keep: usize,
+    /// Only write these top-level fields of each NDJSON line.
+    #[arg(long, value_delimiter = ',')]
+    pub fields: Vec<String>,
+}
+
+impl OutputArgs {
+    /// The NDJSON sink, or `None` for text output.
+    pub fn sink(&self) -> Result<Option<NdjsonSink>> {
+        if self.format == Format::Text {
+            ensure!(self.out.is_none(), "--out takes NDJSON, not --format text");
+            return Ok(None);
+        }
+        let out: Box<dyn Write + Send> = match &self.out {
+            Some(path) => Box::new(RotatingFile::open(path, self.rotate_mb << 20, self.keep)?),
+            None => Box::new(io::stdout()),
+        };
+        Ok(Some(NdjsonSink::new(out, self.fields.clone())))
+    }
+}
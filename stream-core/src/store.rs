@@ -0,0 +1,298 @@
+//! Pending txs as first seen, and what became of them, in SQLite.
+//!
+//! A tx is `pending` until a block includes it, a tx with the same sender and nonce gets seen
+//! or mined (`replaced`), or it has been pending too long (`dropped`). `block` is the block
+//! that included or replaced it, so a reorged block can be undone. Inclusion latency runs
+//! from the first sighting to the block timestamp, so it has the block time's second
+//! resolution.
+
+use std::{
+    fmt,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use alloy_primitives::{Address, B256};
+use eyre::{Result, WrapErr};
+use rusqlite::{params, Connection, OptionalExtension};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS pending_txs (
+    hash BLOB PRIMARY KEY,
+    sender BLOB NOT NULL,
+    nonce INTEGER NOT NULL,
+    max_fee INTEGER NOT NULL,
+    priority_fee INTEGER,
+    first_seen INTEGER NOT NULL,
+    source TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending',
+    block INTEGER,
+    replaced_by BLOB
+);
+CREATE INDEX IF NOT EXISTS pending_txs_sender_nonce ON pending_txs (sender, nonce);
+CREATE INDEX IF NOT EXISTS pending_txs_status ON pending_txs (status, first_seen);
+CREATE TABLE IF NOT EXISTS blocks (
+    number INTEGER PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    base_fee INTEGER
+);
+";
+
+/// Milliseconds since the unix epoch.
+pub fn unix_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingTx {
+    pub hash: B256,
+    pub sender: Address,
+    pub nonce: u64,
+    /// Gas price of legacy txs, max fee per gas otherwise.
+    pub max_fee: u128,
+    pub priority_fee: Option<u128>,
+    /// Unix milliseconds.
+    pub first_seen: u64,
+    /// The node it came from.
+    pub source: String,
+}
+
+/// A tx as it appears in a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinedTx {
+    pub hash: B256,
+    pub sender: Address,
+    pub nonce: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockUpdate {
+    /// Seen pending before.
+    pub included: usize,
+    pub replaced: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatusCounts {
+    pub pending: u64,
+    pub included: u64,
+    pub replaced: u64,
+    pub dropped: u64,
+}
+
+/// Inclusion latencies of the txs whose effective priority fee was in `low..high` wei.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeBucket {
+    pub low: u128,
+    pub high: Option<u128>,
+    pub count: usize,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+}
+
+impl fmt::Display for FeeBucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gwei = |wei: u128| wei as f64 / 1e9;
+        let range = match self.high {
+            Some(high) => format!("{}-{}", gwei(self.low), gwei(high)),
+            None => format!(">={}", gwei(self.low)),
+        };
+        let secs = |latency: Duration| format!("{:.1}s", latency.as_secs_f64());
+        write!(
+            f,
+            "{range:<12} {:>8} {:>8} {:>8} {:>8}",
+            self.count,
+            secs(self.p50),
+            secs(self.p90),
+            secs(self.p99)
+        )
+    }
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+/// Fees go in as INTEGER, so anything over 9.2 ether per gas is stored as that.
+fn fee(wei: u128) -> i64 {
+    i64::try_from(wei).unwrap_or(i64::MAX)
+}
+
+impl Store {
+    /// Creates the database and its tables if they don't exist yet.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .wrap_err_with(|| format!("failed to open {}", path.display()))?;
+        // lets the latency report read while stream-mempool writes
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// False if `tx` was already stored. A pending tx with the same sender and nonce is marked
+    /// replaced by it.
+    pub fn insert_pending(&mut self, tx: &PendingTx) -> Result<bool> {
+        let db = self.conn.transaction()?;
+        let inserted = db.execute(
+            "INSERT OR IGNORE INTO pending_txs
+             (hash, sender, nonce, max_fee, priority_fee, first_seen, source)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                tx.hash.as_slice(),
+                tx.sender.as_slice(),
+                tx.nonce,
+                fee(tx.max_fee),
+                tx.priority_fee.map(fee),
+                tx.first_seen,
+                tx.source,
+            ],
+        )? == 1;
+        if inserted {
+            db.execute(
+                "UPDATE pending_txs SET status = 'replaced', replaced_by = ?1
+                 WHERE sender = ?2 AND nonce = ?3 AND hash != ?1 AND status = 'pending'",
+                params![tx.hash.as_slice(), tx.sender.as_slice(), tx.nonce],
+            )?;
+        }
+        db.commit()?;
+        Ok(inserted)
+    }
+
+    /// Marks the txs of block `number` included. Whatever else was waiting on one of their
+    /// nonces is replaced, by the mined tx if it's the same nonce or by one never seen if it's
+    /// a lower one. A block seen again after a reorg overwrites the first, and whatever the
+    /// first included or replaced is pending again unless the new one decides it too.
+    pub fn insert_block(
+        &mut self,
+        number: u64,
+        timestamp: u64,
+        base_fee: Option<u128>,
+        txs: &[MinedTx],
+    ) -> Result<BlockUpdate> {
+        let db = self.conn.transaction()?;
+        db.execute(
+            "INSERT OR REPLACE INTO blocks (number, timestamp, base_fee) VALUES (?1, ?2, ?3)",
+            params![number, timestamp, base_fee.map(fee)],
+        )?;
+
+        db.execute(
+            "UPDATE pending_txs SET status = 'pending', block = NULL, replaced_by = NULL
+             WHERE block = ?1",
+            params![number],
+        )?;
+
+        let mut update = BlockUpdate::default();
+        for tx in txs {
+            update.included += db.execute(
+                "UPDATE pending_txs SET status = 'included', block = ?1, replaced_by = NULL
+                 WHERE hash = ?2",
+                params![number, tx.hash.as_slice()],
+            )?;
+            update.replaced += db.execute(
+                "UPDATE pending_txs SET status = 'replaced', replaced_by = ?1, block = ?4
+                 WHERE sender = ?2 AND nonce = ?3 AND hash != ?1 AND status IN ('pending', 'dropped')",
+                params![tx.hash.as_slice(), tx.sender.as_slice(), tx.nonce, number],
+            )?;
+            update.replaced += db.execute(
+                "UPDATE pending_txs SET status = 'replaced', replaced_by = NULL, block = ?3
+                 WHERE sender = ?1 AND nonce < ?2 AND status IN ('pending', 'dropped')",
+                params![tx.sender.as_slice(), tx.nonce, number],
+            )?;
+        }
+        db.commit()?;
+        Ok(update)
+    }
+
+    /// Marks txs first seen before `before` (unix milliseconds) and still pending as dropped.
+    pub fn drop_stale(&mut self, before: u64) -> Result<usize> {
+        Ok(self.conn.execute(
+            "UPDATE pending_txs SET status = 'dropped' WHERE status = 'pending' AND first_seen < ?1",
+            params![before],
+        )?)
+    }
+
+    /// The status of the tx with `hash`, if it was stored.
+    pub fn status(&self, hash: B256) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT status FROM pending_txs WHERE hash = ?1",
+                params![hash.as_slice()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    pub fn counts(&self) -> Result<StatusCounts> {
+        let mut counts = StatusCounts::default();
+        let mut statement =
+            self.conn.prepare("SELECT status, COUNT(*) FROM pending_txs GROUP BY status")?;
+        let rows = statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (status, count) = row?;
+            match status.as_str() {
+                "pending" => counts.pending = count,
+                "included" => counts.included = count,
+                "replaced" => counts.replaced = count,
+                "dropped" => counts.dropped = count,
+                _ => {}
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Latency percentiles of included txs, bucketed by the priority fee they actually paid:
+    /// the max priority fee capped by what the max fee left over the base fee, or a legacy
+    /// gas price minus the base fee. `bounds` are ascending wei amounts; the first bucket
+    /// starts at zero and the last has no upper bound. Empty buckets are left out.
+    pub fn latency_report(&self, bounds: &[u128]) -> Result<Vec<FeeBucket>> {
+        let mut statement = self.conn.prepare(
+            "SELECT p.max_fee, p.priority_fee, p.first_seen, b.timestamp, b.base_fee
+             FROM pending_txs p JOIN blocks b ON p.block = b.number
+             WHERE p.status = 'included'",
+        )?;
+        let rows = statement.query_map([], |row| {
+            let max_fee: i64 = row.get(0)?;
+            let priority_fee: Option<i64> = row.get(1)?;
+            let first_seen: u64 = row.get(2)?;
+            let timestamp: u64 = row.get(3)?;
+            let base_fee: Option<i64> = row.get(4)?;
+
+            let left = (max_fee as u128).saturating_sub(base_fee.unwrap_or(0) as u128);
+            let tip = priority_fee.map_or(left, |priority_fee| left.min(priority_fee as u128));
+            // seen after the block was built: its timestamp is from before it was broadcast
+            let latency = Duration::from_millis((timestamp * 1000).saturating_sub(first_seen));
+            Ok((tip, latency))
+        })?;
+
+        let mut buckets = vec![Vec::new(); bounds.len() + 1];
+        for row in rows {
+            let (tip, latency) = row?;
+            buckets[bounds.partition_point(|&bound| bound <= tip)].push(latency);
+        }
+
+        Ok(buckets
+            .into_iter()
+            .enumerate()
+            .filter(|(_, latencies)| !latencies.is_empty())
+            .map(|(i, mut latencies)| {
+                latencies.sort();
+                FeeBucket {
+                    low: if i == 0 { 0 } else { bounds[i - 1] },
+                    high: bounds.get(i).copied(),
+                    count: latencies.len(),
+                    p50: percentile(&latencies, 50),
+                    p90: percentile(&latencies, 90),
+                    p99: percentile(&latencies, 99),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Nearest-rank percentile of sorted, non-empty `values`.
+fn percentile(values: &[Duration], p: usize) -> Duration {
+    let rank = (values.len() * p).div_ceil(100);
+    values[rank.saturating_sub(1)]
+}
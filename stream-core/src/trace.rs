@@ -0,0 +1,386 @@
+//! One model for traces from either kind of node: the parity `trace_*` namespace (reth,
+//! erigon, nethermind) or geth's `debug_trace*` with its built-in tracers.
+//!
+//! Parity traces come as a flat list addressed by `trace_address`, see [`CallNode::tree`]; geth
+//! tracer results are JSON, see [`GethTracer::normalize`].
+
+use std::{collections::BTreeMap, fmt};
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use clap::ValueEnum;
+use eyre::{bail, ensure, eyre, Result, WrapErr};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// Pick by the node's `web3_clientVersion`.
+    Auto,
+    /// `trace_call`, served by reth, erigon and nethermind.
+    Parity,
+    /// `debug_traceCall`, served by geth and most of its forks, and by anvil.
+    Geth,
+}
+
+impl Backend {
+    /// The backend a node with this client version serves; geth unless it's known otherwise.
+    pub fn detect(client_version: &str) -> Self {
+        let client = client_version.to_ascii_lowercase();
+        let parity = ["reth", "erigon", "nethermind", "openethereum", "parity"];
+        if parity.iter().any(|name| client.starts_with(name)) {
+            Self::Parity
+        } else {
+            Self::Geth
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+    Call,
+    StaticCall,
+    DelegateCall,
+    CallCode,
+    Create,
+    Create2,
+    SelfDestruct,
+}
+
+impl CallKind {
+    /// Geth's frame type, e.g. `DELEGATECALL`; case doesn't matter.
+    pub fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_uppercase().as_str() {
+            "CALL" => Self::Call,
+            "STATICCALL" => Self::StaticCall,
+            "DELEGATECALL" => Self::DelegateCall,
+            "CALLCODE" => Self::CallCode,
+            "CREATE" => Self::Create,
+            "CREATE2" => Self::Create2,
+            "SELFDESTRUCT" => Self::SelfDestruct,
+            _ => return None,
+        })
+    }
+
+    pub fn is_create(&self) -> bool {
+        matches!(self, Self::Create | Self::Create2)
+    }
+}
+
+impl fmt::Display for CallKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Call => "call",
+            Self::StaticCall => "staticcall",
+            Self::DelegateCall => "delegatecall",
+            Self::CallCode => "callcode",
+            Self::Create => "create",
+            Self::Create2 => "create2",
+            Self::SelfDestruct => "selfdestruct",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallNode {
+    pub kind: CallKind,
+    pub from: Address,
+    /// The callee, the created contract, or the beneficiary of a selfdestruct; `None` for a
+    /// create that failed.
+    pub to: Option<Address>,
+    pub value: U256,
+    /// Calldata, or init code for a create.
+    pub input: Bytes,
+    pub output: Bytes,
+    pub gas_used: u64,
+    /// Why the call reverted, if it did.
+    pub error: Option<String>,
+    pub calls: Vec<CallNode>,
+}
+
+impl CallNode {
+    /// Builds the tree out of parity-style traces: each with its `trace_address`, the path of
+    /// child indexes from the top-level call, in the order the node returns them.
+    pub fn tree(flat: impl IntoIterator<Item = (Vec<usize>, CallNode)>) -> Result<Self> {
+        let mut flat = flat.into_iter();
+        let (address, mut root) = flat.next().ok_or_else(|| eyre!("no traces"))?;
+        ensure!(address.is_empty(), "the first trace isn't the top-level call");
+
+        for (address, node) in flat {
+            let Some((&index, path)) = address.split_last() else {
+                bail!("more than one top-level call");
+            };
+            let mut parent = &mut root;
+            for &i in path {
+                parent = parent
+                    .calls
+                    .get_mut(i)
+                    .ok_or_else(|| eyre!("trace {address:?} comes before its parent"))?;
+            }
+            ensure!(parent.calls.len() == index, "trace {address:?} is out of order");
+            parent.calls.push(node);
+        }
+
+        Ok(root)
+    }
+
+    /// This call and every call under it, depth first, with their depth.
+    pub fn walk(&self) -> Vec<(usize, &CallNode)> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![(0, self)];
+        while let Some((depth, node)) = stack.pop() {
+            nodes.push((depth, node));
+            stack.extend(node.calls.iter().rev().map(|call| (depth + 1, call)));
+        }
+        nodes
+    }
+
+    /// What geth's `4byteTracer` would report for this tree.
+    pub fn selectors(&self) -> Vec<SelectorCount> {
+        let mut counts = BTreeMap::new();
+        for (_, node) in self.walk() {
+            if node.kind.is_create() || node.kind == CallKind::SelfDestruct {
+                continue;
+            }
+            if let Some(selector) = node.input.get(..4) {
+                let key = (selector.try_into().unwrap(), node.input.len() - 4);
+                *counts.entry(key).or_default() += 1;
+            }
+        }
+        SelectorCount::from_map(counts)
+    }
+}
+
+/// How often a selector was called with calldata of a given size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectorCount {
+    pub selector: [u8; 4],
+    /// Calldata size after the selector.
+    pub size: usize,
+    pub count: u64,
+}
+
+impl SelectorCount {
+    fn from_map(counts: BTreeMap<([u8; 4], usize), u64>) -> Vec<Self> {
+        counts.into_iter().map(|((selector, size), count)| Self { selector, size, count }).collect()
+    }
+}
+
+/// Changes to one account; each field is `(before, after)` and only set if it changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountDiff {
+    pub balance: Option<(U256, U256)>,
+    pub nonce: Option<(u64, u64)>,
+    pub code: Option<(Bytes, Bytes)>,
+    pub storage: BTreeMap<B256, (B256, B256)>,
+}
+
+impl AccountDiff {
+    /// One line per change, e.g. `balance -1.2 ETH` or `slot 0x1: 0x0 -> 0x5`.
+    pub fn changes(&self) -> Vec<String> {
+        let mut changes = Vec::new();
+        if let Some((before, after)) = self.balance {
+            let (sign, delta) =
+                if after >= before { ("+", after - before) } else { ("-", before - after) };
+            changes.push(format!("balance {sign}{} ETH", format_ether(delta)));
+        }
+        if let Some((before, after)) = self.nonce {
+            changes.push(format!("nonce {before} -> {after}"));
+        }
+        if let Some((before, after)) = &self.code {
+            let change = match (before.is_empty(), after.is_empty()) {
+                (true, _) => format!("deployed, {} bytes", after.len()),
+                (_, true) => "removed".to_string(),
+                _ => format!("{} -> {} bytes", before.len(), after.len()),
+            };
+            changes.push(format!("code {change}"));
+        }
+        for (slot, (before, after)) in &self.storage {
+            changes.push(format!("slot {}: {} -> {}", word(slot), word(before), word(after)));
+        }
+        changes
+    }
+}
+
+/// A storage word as a number, so small values and slots stay short.
+fn word(word: &B256) -> String {
+    format!("{:#x}", U256::from_be_bytes(word.0))
+}
+
+/// Wei as ETH without trailing zeros, e.g. `1.2`.
+fn format_ether(wei: U256) -> String {
+    let unit = U256::from(10u64.pow(18));
+    let (whole, fraction) = wei.div_rem(unit);
+    if fraction.is_zero() {
+        return whole.to_string();
+    }
+    let fraction = format!("{fraction:0>18}");
+    format!("{whole}.{}", fraction.trim_end_matches('0'))
+}
+
+pub type StateDiff = BTreeMap<Address, AccountDiff>;
+
+/// A trace in the common model; which parts are set depends on what was asked for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceResult {
+    pub calls: Option<CallNode>,
+    pub state_diff: Option<StateDiff>,
+    pub selectors: Option<Vec<SelectorCount>>,
+}
+
+/// The geth built-in tracers we understand; each one is a separate `debug_traceCall`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GethTracer {
+    Call,
+    /// `prestateTracer` in diff mode.
+    PrestateDiff,
+    FourByte,
+}
+
+impl GethTracer {
+    /// The tracer options, the last `debug_traceCall` parameter.
+    pub fn options(&self) -> Value {
+        match self {
+            Self::Call => json!({ "tracer": "callTracer" }),
+            Self::PrestateDiff => {
+                json!({ "tracer": "prestateTracer", "tracerConfig": { "diffMode": true } })
+            }
+            Self::FourByte => json!({ "tracer": "4byteTracer" }),
+        }
+    }
+
+    /// Adds this tracer's result to `trace`.
+    pub fn normalize(&self, result: &Value, trace: &mut TraceResult) -> Result<()> {
+        match self {
+            Self::Call => trace.calls = Some(call_frame(result).wrap_err("bad callTracer result")?),
+            Self::PrestateDiff => {
+                trace.state_diff =
+                    Some(prestate_diff(result).wrap_err("bad prestateTracer result")?)
+            }
+            Self::FourByte => {
+                trace.selectors = Some(four_byte(result).wrap_err("bad 4byteTracer result")?)
+            }
+        }
+        Ok(())
+    }
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    value.get(name).filter(|v| !v.is_null())
+}
+
+/// A JSON field that's optional, parsed with serde.
+fn parse<T: serde::de::DeserializeOwned>(value: &Value, name: &str) -> Result<Option<T>> {
+    field(value, name)
+        .map(|v| serde_json::from_value(v.clone()).wrap_err_with(|| format!("bad `{name}`")))
+        .transpose()
+}
+
+fn call_frame(frame: &Value) -> Result<CallNode> {
+    let kind = field(frame, "type").and_then(Value::as_str).unwrap_or_default();
+    let kind = CallKind::parse(kind).ok_or_else(|| eyre!("unknown call type `{kind}`"))?;
+    let gas_used: Option<U256> = parse(frame, "gasUsed")?;
+    let error = parse::<String>(frame, "error")?;
+    // a revert reason says more than "execution reverted"
+    let error = match parse::<String>(frame, "revertReason")? {
+        Some(reason) => Some(reason),
+        None => error,
+    };
+
+    let calls = field(frame, "calls").and_then(Value::as_array).map_or(&[][..], Vec::as_slice);
+
+    Ok(CallNode {
+        kind,
+        from: parse(frame, "from")?.ok_or_else(|| eyre!("missing `from`"))?,
+        to: parse(frame, "to")?,
+        value: parse(frame, "value")?.unwrap_or_default(),
+        input: parse(frame, "input")?.unwrap_or_default(),
+        output: parse(frame, "output")?.unwrap_or_default(),
+        gas_used: gas_used.map_or(0, |gas| gas.saturating_to()),
+        error,
+        calls: calls.iter().map(call_frame).collect::<Result<_>>()?,
+    })
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct Account {
+    balance: Option<U256>,
+    nonce: Option<u64>,
+    code: Option<Bytes>,
+    #[serde(default)]
+    storage: BTreeMap<B256, B256>,
+}
+
+/// Diff mode lists only accounts that changed: `pre` holds their changed fields before and
+/// `post` after, leaving out whatever became zero or empty, and the whole account if it's gone.
+fn prestate_diff(result: &Value) -> Result<StateDiff> {
+    let accounts = |name| -> Result<BTreeMap<Address, Account>> {
+        Ok(parse(result, name)?.unwrap_or_default())
+    };
+    let (pre, mut post) = (accounts("pre")?, accounts("post")?);
+
+    let mut diff = StateDiff::new();
+    for (address, before) in pre {
+        let after = post.remove(&address);
+        let deleted = after.is_none();
+        let after = after.unwrap_or_default();
+
+        let mut account = AccountDiff {
+            balance: changed(before.balance, after.balance, deleted),
+            nonce: changed(before.nonce, after.nonce, deleted),
+            code: changed(before.code, after.code, deleted),
+            ..Default::default()
+        };
+        for (slot, value) in before.storage {
+            let new = after.storage.get(&slot).copied().unwrap_or_default();
+            account.storage.insert(slot, (value, new));
+        }
+        for (slot, value) in after.storage {
+            account.storage.entry(slot).or_insert((B256::ZERO, value));
+        }
+        diff.insert(address, account);
+    }
+
+    // created by the call
+    for (address, after) in post {
+        let account = AccountDiff {
+            balance: changed(None, after.balance, false),
+            nonce: changed(None, after.nonce, false),
+            code: changed(None, after.code, false),
+            storage: after.storage.into_iter().map(|(slot, v)| (slot, (B256::ZERO, v))).collect(),
+        };
+        diff.insert(address, account);
+    }
+
+    Ok(diff)
+}
+
+/// A field missing from `pre` was zero, one missing from `post` kept its value unless the
+/// account is gone.
+fn changed<T: Default + PartialEq>(
+    before: Option<T>,
+    after: Option<T>,
+    deleted: bool,
+) -> Option<(T, T)> {
+    let (before, after) = match (before, after) {
+        (None, None) => return None,
+        (Some(_), None) if !deleted => return None,
+        (before, after) => (before.unwrap_or_default(), after.unwrap_or_default()),
+    };
+    (before != after).then_some((before, after))
+}
+
+/// `{"0xa9059cbb-64": 1, ...}`
+fn four_byte(result: &Value) -> Result<Vec<SelectorCount>> {
+    let entries = result.as_object().ok_or_else(|| eyre!("not an object"))?;
+    let mut counts = BTreeMap::new();
+    for (key, count) in entries {
+        let (selector, size) = key.split_once('-').ok_or_else(|| eyre!("bad key `{key}`"))?;
+        let selector: [u8; 4] = alloy_primitives::hex::decode(selector)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| eyre!("bad selector in `{key}`"))?;
+        let size = size.parse().wrap_err_with(|| format!("bad size in `{key}`"))?;
+        let count = count.as_u64().ok_or_else(|| eyre!("bad count for `{key}`"))?;
+        counts.insert((selector, size), count);
+    }
+    Ok(SelectorCount::from_map(counts))
+}
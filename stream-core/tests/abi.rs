@@ -0,0 +1,115 @@
+use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy_json_abi::{Function, JsonAbi};
+use alloy_primitives::{address, fixed_bytes, Address, U256};
+use stream_core::{AbiRegistry, Call};
+
+const TOKEN: Address = address!("a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+const RECIPIENT: Address = address!("00000000000000000000000000000000000000aa");
+
+fn abi(signatures: &[&str]) -> JsonAbi {
+    JsonAbi::parse(signatures.iter().copied()).unwrap()
+}
+
+fn transfer_input() -> Vec<u8> {
+    let transfer = Function::parse("transfer(address to, uint256 amount)").unwrap();
+    transfer
+        .abi_encode_input(&[
+            DynSolValue::Address(RECIPIENT),
+            DynSolValue::Uint(U256::from(1000), 256),
+        ])
+        .unwrap()
+}
+
+fn erc20() -> JsonAbi {
+    abi(&["function transfer(address to, uint256 amount) returns (bool)"])
+}
+
+#[test]
+fn decodes_with_a_generic_interface() {
+    let mut registry = AbiRegistry::default();
+    registry.add_interface(&erc20());
+
+    let call = registry.decode(Some(RECIPIENT), &transfer_input());
+    let Call::Decoded { name, signature, args } = &call else { panic!("not decoded: {call:?}") };
+    assert_eq!(name, "transfer");
+    assert_eq!(signature, "transfer(address,uint256)");
+    assert_eq!(args[0].name, "to");
+    assert_eq!(args[1].ty, "uint256");
+    assert_eq!(args[1].value, DynSolValue::Uint(U256::from(1000), 256));
+    assert_eq!(call.to_string(), format!("transfer(to: {RECIPIENT}, amount: 1000)"));
+}
+
+#[test]
+fn the_contract_abi_wins_over_interfaces() {
+    let mut registry = AbiRegistry::default();
+    registry.add_interface(&erc20());
+    // same selector and types, different names
+    registry.add_contract(TOKEN, &abi(&["function transfer(address dst, uint256 wad)"]));
+
+    let Call::Decoded { args, .. } = registry.decode(Some(TOKEN), &transfer_input()) else {
+        panic!("not decoded")
+    };
+    assert_eq!(args[0].name, "dst");
+
+    let Call::Decoded { args, .. } = registry.decode(None, &transfer_input()) else {
+        panic!("not decoded")
+    };
+    assert_eq!(args[0].name, "to");
+}
+
+#[test]
+fn falls_back_to_the_selector() {
+    let registry = AbiRegistry::default();
+    let call = registry.decode(Some(TOKEN), &transfer_input());
+    assert_eq!(
+        call,
+        Call::Unknown { selector: fixed_bytes!("a9059cbb"), data: transfer_input()[4..].to_vec() }
+    );
+    assert_eq!(call.to_string(), "0xa9059cbb(64 bytes)");
+
+    // a known selector with arguments that don't fit
+    let mut registry = AbiRegistry::default();
+    registry.add_interface(&erc20());
+    let call = registry.decode(Some(TOKEN), &transfer_input()[..20]);
+    assert!(matches!(call, Call::Unknown { .. }), "{call:?}");
+}
+
+#[test]
+fn short_input_is_raw() {
+    let registry = AbiRegistry::default();
+    assert_eq!(registry.decode(Some(TOKEN), &[]).to_string(), "()");
+    assert_eq!(registry.decode(Some(TOKEN), &[0xab, 0xcd]).to_string(), "0xabcd");
+}
+
+#[test]
+fn loads_abis_and_artifacts_from_a_directory() {
+    let dir = std::env::temp_dir().join(format!("abis-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("erc20.json"),
+        r#"[{"type":"function","name":"transfer","stateMutability":"nonpayable",
+            "inputs":[{"name":"to","type":"address"},{"name":"amount","type":"uint256"}],
+            "outputs":[{"name":"","type":"bool"}]}]"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join(format!("{TOKEN}.json")),
+        r#"{"abi":[{"type":"function","name":"transfer","stateMutability":"nonpayable",
+            "inputs":[{"name":"dst","type":"address"},{"name":"wad","type":"uint256"}],
+            "outputs":[]}],"bytecode":"0x"}"#,
+    )
+    .unwrap();
+    std::fs::write(dir.join("README.md"), "not an abi").unwrap();
+
+    let registry = AbiRegistry::load(&dir).unwrap();
+    let Call::Decoded { args, .. } = registry.decode(Some(TOKEN), &transfer_input()) else {
+        panic!("not decoded")
+    };
+    assert_eq!(args[0].name, "dst");
+    let Call::Decoded { args, .. } = registry.decode(Some(RECIPIENT), &transfer_input()) else {
+        panic!("not decoded")
+    };
+    assert_eq!(args[0].name, "to");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
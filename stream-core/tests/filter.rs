@@ -0,0 +1,135 @@
+use alloy_primitives::{address, fixed_bytes, Address, U256};
+use stream_core::filter::{Cmp, Field, Filter, TxFields, Value};
+
+const ROUTER: Address = address!("7a250d5630b4cf539739df2c5dacb4c659f2488d");
+const OTHER: Address = address!("00000000000000000000000000000000000000aa");
+
+fn transfer() -> TxFields {
+    let input = [0xa9, 0x05, 0x9c, 0xbb, 0, 0, 0, 0];
+    TxFields {
+        from: OTHER,
+        to: Some(ROUTER),
+        selector: TxFields::selector_of(&input),
+        value: U256::from(2u128 * 10u128.pow(18)),
+        gas_price: 30_000_000_000,
+        tx_type: 2,
+        calldata_len: input.len(),
+    }
+}
+
+fn matches(source: &str, tx: &TxFields) -> bool {
+    Filter::parse(source).unwrap().matches(tx)
+}
+
+#[test]
+fn parses_a_comparison() {
+    let filter = Filter::parse("selector == 0xa9059cbb").unwrap();
+    assert_eq!(
+        filter,
+        Filter::Compare {
+            field: Field::Selector,
+            cmp: Cmp::Eq,
+            value: Value::Selector(Some(fixed_bytes!("a9059cbb"))),
+        }
+    );
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    let filter = Filter::parse("type == 0 || type == 1 && value > 0").unwrap();
+    let Filter::Or(_, right) = filter else { panic!("expected an or: {filter:?}") };
+    assert!(matches!(*right, Filter::And(..)));
+}
+
+#[test]
+fn value_units() {
+    let ether = U256::from(10u128.pow(18));
+    for (source, wei) in [
+        ("value == 1 ether", ether),
+        ("value == 1.5 gwei", U256::from(1_500_000_000u64)),
+        ("value == 7 wei", U256::from(7)),
+        ("value == 7", U256::from(7)),
+        ("value == 0x10", U256::from(16)),
+        ("value == .5 ether", ether / U256::from(2)),
+    ] {
+        let Filter::Compare { value, .. } = Filter::parse(source).unwrap() else { unreachable!() };
+        assert_eq!(value, Value::Number(wei), "{source}");
+    }
+}
+
+#[test]
+fn rejects_bad_filters() {
+    for source in [
+        "",
+        "value >",
+        "nonce == 1",
+        "to > 0x7a250d5630b4cf539739df2c5dacb4c659f2488d",
+        "to == 0x1234",
+        "selector == 0xa9059c",
+        "value == 1.5 wei",
+        "value == 1 ether ether",
+        "(value > 1",
+        "to in [0x7a250d5630b4cf539739df2c5dacb4c659f2488d",
+        "value > 1 $",
+    ] {
+        assert!(Filter::parse(source).is_err(), "{source:?} should not parse");
+    }
+}
+
+#[test]
+fn the_example_filter() {
+    let source = "to in [0x7a250d5630b4cf539739df2c5dacb4c659f2488d, 0x00000000000000000000000000000000000000bb] \
+                  && selector == 0xa9059cbb && value > 1 ether";
+    let tx = transfer();
+    assert!(matches(source, &tx));
+    assert!(!matches(source, &TxFields { value: U256::from(1), ..tx.clone() }));
+    assert!(!matches(source, &TxFields { to: Some(OTHER), ..tx.clone() }));
+    assert!(!matches(source, &TxFields { selector: None, ..tx }));
+}
+
+#[test]
+fn not_and_parentheses() {
+    let tx = transfer();
+    assert!(matches("!(type == legacy)", &tx));
+    assert!(matches("!type == 0 && (calldata_len < 4 || gas_price >= 30 gwei)", &tx));
+    assert!(matches("!!(from == 0x00000000000000000000000000000000000000aa)", &tx));
+    assert!(!matches("!(from == 0x00000000000000000000000000000000000000aa)", &tx));
+}
+
+#[test]
+fn contract_creations_have_no_to() {
+    let creation = TxFields { to: None, selector: None, ..transfer() };
+    assert!(matches("to == none", &creation));
+    assert!(!matches("to == 0x7a250d5630b4cf539739df2c5dacb4c659f2488d", &creation));
+    assert!(matches("to != 0x7a250d5630b4cf539739df2c5dacb4c659f2488d", &creation));
+    assert!(!matches("to == none", &transfer()));
+}
+
+#[test]
+fn address_lists_from_files() {
+    let path = std::env::temp_dir().join(format!("routers-{}.txt", std::process::id()));
+    std::fs::write(
+        &path,
+        "# uniswap v2\n0x7a250d5630b4cf539739df2c5dacb4c659f2488d\n\n0x00000000000000000000000000000000000000bb # other\n",
+    )
+    .unwrap();
+    let file = path.display();
+
+    let filter = Filter::parse(&format!("to in @{file}")).unwrap();
+    let Filter::In { values, .. } = &filter else { panic!("expected a list: {filter:?}") };
+    assert_eq!(values.len(), 2);
+    assert!(filter.matches(&transfer()));
+
+    // files mix with literals inside brackets
+    assert!(matches(
+        &format!("from in [@{file}, 0x00000000000000000000000000000000000000aa]"),
+        &transfer()
+    ));
+    assert!(!matches(&format!("from in @{file}"), &transfer()));
+
+    std::fs::write(&path, "0x7a250d5630b4cf539739df2c5dacb4c659f2488d\nnot an address\n").unwrap();
+    let err = Filter::parse(&format!("to in @{file}")).unwrap_err();
+    assert!(format!("{err:#}").contains(":2:"), "{err:#}");
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,126 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use stream_core::{Counts, DropPolicy, Pipeline, PipelineConfig};
+use tokio::sync::Semaphore;
+
+fn config(concurrency: usize, queue: usize, drop_policy: DropPolicy) -> PipelineConfig {
+    PipelineConfig { concurrency, queue, timeout: Duration::from_secs(5), drop_policy }
+}
+
+#[tokio::test]
+async fn never_runs_more_than_the_concurrency() {
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let pipeline = Pipeline::spawn(config(3, 100, DropPolicy::Newest), {
+        let (running, peak) = (running.clone(), peak.clone());
+        move |_: u64| {
+            let (running, peak) = (running.clone(), peak.clone());
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+    });
+    for i in 0..30 {
+        assert!(pipeline.submit(i));
+    }
+
+    let counts = pipeline.shutdown(true).await;
+    assert_eq!(counts, Counts { submitted: 30, completed: 30, ..Default::default() });
+    assert_eq!(peak.load(Ordering::SeqCst), 3);
+}
+
+/// A pipeline whose single worker is stuck on the first item until `gate` opens.
+async fn blocked(drop_policy: DropPolicy) -> (Pipeline<u64>, Arc<Semaphore>, Arc<Mutex<Vec<u64>>>) {
+    let gate = Arc::new(Semaphore::new(0));
+    let done = Arc::new(Mutex::new(Vec::new()));
+    let pipeline = Pipeline::spawn(config(1, 2, drop_policy), {
+        let (gate, done) = (gate.clone(), done.clone());
+        move |item: u64| {
+            let (gate, done) = (gate.clone(), done.clone());
+            async move {
+                gate.acquire().await.unwrap().forget();
+                done.lock().unwrap().push(item);
+                Ok(())
+            }
+        }
+    });
+
+    pipeline.submit(0);
+    // let the worker take item 0 off the queue
+    while pipeline.queued() > 0 {
+        tokio::task::yield_now().await;
+    }
+    (pipeline, gate, done)
+}
+
+#[tokio::test]
+async fn drops_the_newest_when_full() {
+    let (pipeline, gate, done) = blocked(DropPolicy::Newest).await;
+    assert!(pipeline.submit(1));
+    assert!(pipeline.submit(2));
+    assert!(!pipeline.submit(3));
+
+    gate.add_permits(10);
+    let counts = pipeline.shutdown(true).await;
+    assert_eq!(*done.lock().unwrap(), [0, 1, 2]);
+    assert_eq!((counts.completed, counts.dropped), (3, 1));
+}
+
+#[tokio::test]
+async fn drops_the_oldest_when_full() {
+    let (pipeline, gate, done) = blocked(DropPolicy::Oldest).await;
+    assert!(pipeline.submit(1));
+    assert!(pipeline.submit(2));
+    assert!(pipeline.submit(3));
+
+    gate.add_permits(10);
+    let counts = pipeline.shutdown(true).await;
+    assert_eq!(*done.lock().unwrap(), [0, 2, 3]);
+    assert_eq!((counts.completed, counts.dropped), (3, 1));
+}
+
+#[tokio::test]
+async fn counts_errors_timeouts_and_panics_without_stopping() {
+    let config =
+        PipelineConfig { timeout: Duration::from_millis(20), ..config(2, 10, DropPolicy::Newest) };
+    let pipeline = Pipeline::spawn(config, |item: u64| async move {
+        match item {
+            0 => eyre::bail!("node said no"),
+            1 => tokio::time::sleep(Duration::from_secs(60)).await,
+            2 => panic!("bad item"),
+            _ => {}
+        }
+        Ok(())
+    });
+    for i in 0..5 {
+        pipeline.submit(i);
+    }
+
+    let counts = pipeline.shutdown(true).await;
+    assert_eq!(
+        counts,
+        Counts { submitted: 5, completed: 2, failed: 2, timed_out: 1, dropped: 0, cancelled: 0 }
+    );
+}
+
+#[tokio::test]
+async fn shutdown_without_drain_abandons_the_queue() {
+    let (pipeline, _gate, done) = blocked(DropPolicy::Newest).await;
+    pipeline.submit(1);
+    pipeline.submit(2);
+
+    let counts = pipeline.shutdown(false).await;
+    assert!(done.lock().unwrap().is_empty());
+    assert_eq!((counts.completed, counts.dropped, counts.cancelled), (0, 2, 1));
+}
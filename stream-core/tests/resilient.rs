@@ -0,0 +1,140 @@
+use std::time::Duration;
+
+use futures_util::{SinkExt, Stream, StreamExt};
+use stream_core::{resilient, Backoff, Event};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+/// A WebSocket node stand-in: every connection gets the next `per_connection` numbers of one
+/// shared sequence, then the socket is dropped, or left open and silent with `hang`.
+async fn spawn_dropping_server(listener: TcpListener, per_connection: u64, hang: bool) {
+    tokio::spawn(async move {
+        let mut next = 0;
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            for _ in 0..per_connection {
+                ws.send(Message::text(next.to_string())).await.unwrap();
+                next += 1;
+            }
+            if hang {
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_secs(3600)).await;
+                    drop(ws);
+                });
+            }
+        }
+    });
+}
+
+/// Subscribes the way the stream binaries do: a fresh connection per call.
+async fn subscribe(url: String) -> eyre::Result<impl Stream<Item = u64>> {
+    let (ws, _) = tokio_tungstenite::connect_async(url).await?;
+    Ok(ws.filter_map(|message| async move {
+        match message {
+            Ok(Message::Text(text)) => text.parse().ok(),
+            _ => None,
+        }
+    }))
+}
+
+fn fast_backoff() -> Backoff {
+    Backoff {
+        initial: Duration::from_millis(10),
+        max: Duration::from_millis(50),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn resubscribes_after_the_server_drops_the_connection() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    spawn_dropping_server(listener, 2, false).await;
+
+    let events: Vec<_> =
+        resilient(move || subscribe(url.clone()), fast_backoff()).take(8).collect().await;
+
+    let items: Vec<u64> = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Item(item) => Some(*item),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(items, [0, 1, 2, 3]);
+
+    assert!(matches!(events[2], Event::Disconnected { .. }), "{events:?}");
+    assert!(matches!(events[3], Event::Reconnected { attempts: 1, .. }), "{events:?}");
+    assert!(matches!(events[6], Event::Disconnected { .. }), "{events:?}");
+}
+
+#[tokio::test]
+async fn retries_with_backoff_until_the_node_is_up() {
+    // reserve a port, then leave nothing listening on it for a while
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let url = format!("ws://{addr}");
+
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let listener = TcpListener::bind(addr).await.unwrap();
+        spawn_dropping_server(listener, 1, false).await;
+    });
+
+    let mut events = Box::pin(resilient(move || subscribe(url.clone()), fast_backoff()));
+    let mut attempts = 0;
+    loop {
+        match events.next().await.unwrap() {
+            Event::Retrying { attempt, delay, .. } => {
+                attempts += 1;
+                assert_eq!(attempt, attempts);
+                assert!(delay <= Duration::from_millis(50));
+            }
+            // the first subscription isn't a reconnect
+            Event::Item(item) => {
+                assert_eq!(item, 0);
+                break;
+            }
+            other => panic!("{other:?}"),
+        }
+    }
+    assert!(attempts >= 2);
+}
+
+#[tokio::test]
+async fn treats_a_silent_connection_as_lost() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    spawn_dropping_server(listener, 1, true).await;
+
+    let backoff = Backoff { idle_timeout: Some(Duration::from_millis(100)), ..fast_backoff() };
+    let events: Vec<_> = resilient(move || subscribe(url.clone()), backoff).take(4).collect().await;
+
+    assert_eq!(events[0], Event::Item(0));
+    match &events[1] {
+        Event::Disconnected { reason } => assert!(reason.contains("no item"), "{reason}"),
+        other => panic!("{other:?}"),
+    }
+    assert!(matches!(events[2], Event::Reconnected { .. }), "{events:?}");
+    assert_eq!(events[3], Event::Item(1));
+}
+
+#[test]
+fn backoff_doubles_up_to_the_cap() {
+    let backoff = Backoff {
+        initial: Duration::from_millis(100),
+        max: Duration::from_secs(1),
+        jitter: 0.0,
+        idle_timeout: None,
+    };
+    let delays: Vec<_> = (1..=6).map(|attempt| backoff.delay(attempt).as_millis()).collect();
+    assert_eq!(delays, [100, 200, 400, 800, 1000, 1000]);
+
+    let jittered = Backoff { jitter: 0.5, ..backoff };
+    for _ in 0..100 {
+        let delay = jittered.delay(3);
+        assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400));
+    }
+}
@@ -0,0 +1,130 @@
+use alloy_dyn_abi::{DynSolValue, JsonAbiExt};
+use alloy_json_abi::Function;
+use alloy_primitives::{address, hex, U256};
+use stream_core::{
+    signatures::{ImportStats, Kind},
+    AbiRegistry, Call, SignatureDb,
+};
+
+const TRANSFER_FROM: [u8; 4] = hex!("23b872dd");
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("{name}-{}", std::process::id()))
+}
+
+#[test]
+fn built_in_signatures() {
+    let db = SignatureDb::embedded();
+    assert_eq!(db.function(hex!("a9059cbb")), ["transfer(address,uint256)"]);
+    let (kind, signatures) =
+        db.lookup("0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef").unwrap();
+    assert_eq!(
+        (kind, signatures),
+        (Kind::Event, &["Transfer(address,address,uint256)".to_string()][..])
+    );
+    assert!(db.lookup("0x12345678").unwrap().1.is_empty());
+    assert!(db.lookup("0x1234").is_err());
+}
+
+#[test]
+fn keeps_colliding_signatures() {
+    let mut db = SignatureDb::embedded();
+    assert!(db.insert(Kind::Function, "gasprice_bit_ether(int128)"));
+    assert!(!db.insert(Kind::Function, "gasprice_bit_ether(int128)"));
+    assert_eq!(
+        db.function(TRANSFER_FROM),
+        ["transferFrom(address,address,uint256)", "gasprice_bit_ether(int128)"]
+    );
+}
+
+#[test]
+fn imports_text_and_csv_lines() {
+    let mut db = SignatureDb::default();
+    let dump = r#"
+# comment
+function transfer(address,uint256)
+event Transfer(address,address,uint256)
+0x23b872dd transferFrom(address,address,uint256)
+1,2016-07-09 03:58:28,"approve(address,uint256)",0x095ea7b3,"\x09^\xa7\xb3"
+0x12345678,notTheRightId(uint256)
+not a signature
+transfer(address,uint256)
+"#;
+    let stats = db.import(dump).unwrap();
+    assert_eq!(stats, ImportStats { added: 4, known: 1, rejected: 2 });
+    assert_eq!(db.function(hex!("095ea7b3")), ["approve(address,uint256)"]);
+    assert_eq!(db.len(), 4);
+}
+
+#[test]
+fn imports_4byte_and_openchain_json() {
+    let mut db = SignatureDb::default();
+    let four_byte = r#"{"count": 2, "next": null, "results": [
+        {"id": 1, "text_signature": "transfer(address,uint256)", "hex_signature": "0xa9059cbb"},
+        {"id": 2, "text_signature": "gasprice_bit_ether(int128)", "hex_signature": "0x23b872dd"}
+    ]}"#;
+    assert_eq!(db.import(four_byte).unwrap(), ImportStats { added: 2, known: 0, rejected: 0 });
+
+    let openchain = r#"{"ok": true, "result": {
+        "function": {
+            "0x23b872dd": [
+                {"name": "transferFrom(address,address,uint256)", "filtered": false},
+                {"name": "spam_collision(uint256)", "filtered": true}
+            ],
+            "0xdeadbeef": null
+        },
+        "event": {
+            "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef": [
+                {"name": "Transfer(address,address,uint256)", "filtered": false}
+            ]
+        }
+    }}"#;
+    assert_eq!(db.import(openchain).unwrap(), ImportStats { added: 2, known: 0, rejected: 0 });
+    assert_eq!(db.function(TRANSFER_FROM).len(), 2);
+
+    assert!(db.import(r#"{"something": "else"}"#).is_err());
+}
+
+#[test]
+fn saves_and_reopens() {
+    let path = temp_path("signatures.txt");
+    let _ = std::fs::remove_file(&path);
+
+    let mut db = SignatureDb::open(&path).unwrap();
+    let built_in = db.len();
+    db.insert(Kind::Function, "gasprice_bit_ether(int128)");
+    db.insert(Kind::Event, "Named(string)");
+    db.save(&path).unwrap();
+
+    let reopened = SignatureDb::open(&path).unwrap();
+    assert_eq!(reopened.len(), built_in + 2);
+    assert_eq!(reopened.function(TRANSFER_FROM)[0], "transferFrom(address,address,uint256)");
+    assert_eq!(reopened.function(TRANSFER_FROM)[1], "gasprice_bit_ether(int128)");
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn decodes_with_the_signature_that_fits() {
+    let mut db = SignatureDb::default();
+    // the wrong one first, so only decoding can tell them apart
+    db.insert(Kind::Function, "gasprice_bit_ether(int128)");
+    db.insert(Kind::Function, "transferFrom(address,address,uint256)");
+    let mut registry = AbiRegistry::default();
+    registry.set_signatures(db);
+
+    let from = address!("00000000000000000000000000000000000000aa");
+    let to = address!("00000000000000000000000000000000000000bb");
+    let input = Function::parse("transferFrom(address,address,uint256)")
+        .unwrap()
+        .abi_encode_input(&[
+            DynSolValue::Address(from),
+            DynSolValue::Address(to),
+            DynSolValue::Uint(U256::from(5), 256),
+        ])
+        .unwrap();
+
+    let call = registry.decode(None, &input);
+    assert!(matches!(&call, Call::Decoded { name, .. } if name == "transferFrom"), "{call:?}");
+    assert_eq!(call.to_string(), format!("transferFrom({from}, {to}, 5)"));
+}
@@ -0,0 +1,236 @@
+use std::{
+    fs,
+    io::{self, Write},
+    sync::{Arc, Mutex},
+};
+
+use alloy_primitives::{address, b256, bytes, Address, Bytes, B256, U256};
+use clap::Parser;
+use serde_json::json;
+use stream_core::{
+    sink::{
+        BlockRecord, CallRecord, Format, PendingTxRecord, RotatingFile, TraceRecord, SCHEMA_VERSION,
+    },
+    trace::{AccountDiff, CallKind},
+    AbiRegistry, Backend, CallNode, NdjsonSink, OutputArgs, Record, SignatureDb, Sink, SinkWriter,
+};
+
+const EOA: Address = address!("00000000000000000000000000000000000000aa");
+const TOKEN: Address = address!("00000000000000000000000000000000000000cc");
+const HASH: B256 = b256!("1111111111111111111111111111111111111111111111111111111111111111");
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("{name}-{}", std::process::id()))
+}
+
+fn pending_tx() -> Record {
+    Record::PendingTx(PendingTxRecord {
+        hash: HASH,
+        from: EOA,
+        to: None,
+        nonce: 7,
+        value: U256::from(10u64).pow(U256::from(20)),
+        gas_price: 30_000_000_000,
+        priority_fee: None,
+        gas_limit: 21_000,
+        tx_type: 0,
+        input_len: 0,
+        selector: None,
+        function: None,
+    })
+}
+
+fn sink(fields: &[&str]) -> NdjsonSink {
+    NdjsonSink::new(Box::new(std::io::sink()), fields.iter().map(|f| f.to_string()).collect())
+}
+
+#[test]
+fn writes_versioned_lines() {
+    let mut line = sink(&[]).line(pending_tx()).unwrap();
+    assert!(line["ts"].as_u64().unwrap() > 0);
+    line.as_object_mut().unwrap().remove("ts");
+    assert_eq!(
+        line,
+        json!({
+            "v": SCHEMA_VERSION,
+            "kind": "pending_tx",
+            "hash": HASH,
+            "from": EOA,
+            "to": null,
+            "nonce": 7,
+            "value": "100000000000000000000",
+            "gas_price": "30000000000",
+            "priority_fee": null,
+            "gas_limit": 21000,
+            "tx_type": 0,
+            "input_len": 0,
+            "selector": null,
+            "function": null,
+        })
+    );
+
+    let block = Record::Block(BlockRecord { number: 21_000_000, hash: None, traces: 3 });
+    let line = sink(&[]).line(block).unwrap();
+    assert_eq!(
+        (&line["kind"], &line["number"], &line["traces"]),
+        (&json!("block"), &json!(21_000_000), &json!(3))
+    );
+}
+
+#[test]
+fn projects_fields() {
+    let line = sink(&["hash", "value", "missing"]).line(pending_tx()).unwrap();
+    let mut keys: Vec<_> = line.as_object().unwrap().keys().cloned().collect();
+    keys.sort();
+    assert_eq!(keys, ["hash", "kind", "ts", "v", "value"]);
+}
+
+#[test]
+fn records_traces_and_calls() {
+    let transfer = CallNode {
+        kind: CallKind::Call,
+        from: EOA,
+        to: Some(TOKEN),
+        value: U256::ZERO,
+        input: bytes!("a9059cbb00000000000000000000000000000000000000000000000000000000000000aa0000000000000000000000000000000000000000000000000000000000000001"),
+        output: Bytes::new(),
+        gas_used: 30_000,
+        error: Some("execution reverted".into()),
+        calls: vec![CallNode {
+            kind: CallKind::Create,
+            from: TOKEN,
+            to: None,
+            value: U256::from(1),
+            input: bytes!("6080604052"),
+            output: Bytes::new(),
+            gas_used: 100,
+            error: None,
+            calls: Vec::new(),
+        }],
+    };
+
+    let mut abis = AbiRegistry::default();
+    abis.set_signatures(SignatureDb::embedded());
+    let calls = CallRecord::tree(Some(HASH), None, &transfer, &abis);
+    assert_eq!(calls.len(), 2);
+    assert_eq!((calls[0].depth, calls[0].call_type.as_str()), (0, "call"));
+    assert_eq!(calls[0].function.as_deref(), Some("transfer(address,uint256)"));
+    assert_eq!(calls[0].selector.map(|s| s.0), Some([0xa9, 0x05, 0x9c, 0xbb]));
+    // init code has no selector
+    assert_eq!((calls[1].depth, calls[1].selector, calls[1].function.as_deref()), (1, None, None));
+
+    let diff = [(
+        EOA,
+        AccountDiff {
+            balance: Some((U256::from(16), U256::from(8))),
+            nonce: Some((4, 5)),
+            ..Default::default()
+        },
+    )]
+    .into();
+    let trace = TraceRecord::new(HASH, Backend::Geth, Some(&transfer), Some(&diff), None);
+    let line = sink(&[]).line(Record::Trace(trace)).unwrap();
+    assert_eq!(line["backend"], "geth");
+    assert_eq!(line["calls"], 2);
+    assert_eq!(line["error"], "execution reverted");
+    assert_eq!(line["selectors"], json!(null));
+    assert_eq!(
+        line["state_diff"]["0x00000000000000000000000000000000000000aa"],
+        json!({ "balance": ["16", "8"], "nonce": [4, 5], "code": null, "storage": {} })
+    );
+}
+
+#[test]
+fn rotates_between_lines() {
+    let path = temp_path("sink-rotates.ndjson");
+    let rotated = |n: usize| std::path::PathBuf::from(format!("{}.{n}", path.display()));
+    for file in [path.clone(), rotated(1), rotated(2), rotated(3)] {
+        let _ = fs::remove_file(file);
+    }
+
+    let line_len =
+        serde_json::to_vec(&sink(&["hash"]).line(pending_tx()).unwrap()).unwrap().len() + 1;
+    // two lines per file, keeping two old ones
+    let file = RotatingFile::open(&path, 2 * line_len as u64, 2).unwrap();
+    let mut sink = NdjsonSink::new(Box::new(file), vec!["hash".into()]);
+    for _ in 0..7 {
+        sink.emit(pending_tx()).unwrap();
+    }
+
+    for (file, lines) in [(path.clone(), 1), (rotated(1), 2), (rotated(2), 2)] {
+        let text = fs::read_to_string(&file).unwrap();
+        assert_eq!(text.lines().count(), lines, "{}", file.display());
+        for line in text.lines() {
+            let line: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(line["hash"], json!(HASH));
+        }
+    }
+    assert!(!rotated(3).exists());
+
+    for file in [rotated(1), rotated(2), path] {
+        fs::remove_file(file).unwrap();
+    }
+}
+
+/// Fails the first `failures` writes, like a closed pipe, and keeps the rest.
+struct FailingWriter {
+    failures: usize,
+    written: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        self.written.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn keeps_writing_after_a_failed_record() {
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let out = FailingWriter { failures: 1, written: written.clone() };
+    let writer = SinkWriter::spawn(NdjsonSink::new(Box::new(out), vec!["hash".into()]));
+    for _ in 0..3 {
+        writer.emit(pending_tx());
+    }
+
+    assert_eq!(writer.flush().await, 1);
+    let text = String::from_utf8(written.lock().unwrap().clone()).unwrap();
+    assert_eq!(text.lines().count(), 2);
+
+    writer.emit(pending_tx());
+    assert_eq!(writer.flush().await, 1);
+    assert_eq!(String::from_utf8(written.lock().unwrap().clone()).unwrap().lines().count(), 3);
+}
+
+#[derive(Parser)]
+struct Cli {
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+#[test]
+fn out_implies_ndjson() {
+    let path = temp_path("sink-out.ndjson");
+    let out = path.to_str().unwrap();
+
+    let cli = Cli::try_parse_from(["cli"]).unwrap();
+    assert_eq!(cli.output.format, Format::Text);
+
+    let cli = Cli::try_parse_from(["cli", "--out", out]).unwrap();
+    assert_eq!(cli.output.format, Format::Ndjson);
+    assert!(cli.output.sink().unwrap().is_some());
+    fs::remove_file(&path).unwrap();
+
+    let cli = Cli::try_parse_from(["cli", "--format", "text", "--out", out]).unwrap();
+    assert!(cli.output.sink().is_err());
+    assert!(!path.exists());
+}
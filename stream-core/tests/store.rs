@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use alloy_primitives::{address, Address, B256};
+use stream_core::{
+    store::{BlockUpdate, FeeBucket, MinedTx, PendingTx, StatusCounts},
+    Store,
+};
+
+const ALICE: Address = address!("00000000000000000000000000000000000000aa");
+const BOB: Address = address!("00000000000000000000000000000000000000bb");
+const GWEI: u128 = 1_000_000_000;
+
+fn temp_store(name: &str) -> Store {
+    let path = std::env::temp_dir().join(format!("{name}-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    Store::open(&path).unwrap()
+}
+
+fn pending(n: u8, sender: Address, nonce: u64, first_seen: u64) -> PendingTx {
+    PendingTx {
+        hash: B256::with_last_byte(n),
+        sender,
+        nonce,
+        max_fee: 30 * GWEI,
+        priority_fee: Some(2 * GWEI),
+        first_seen,
+        source: "ws://127.0.0.1:8546".into(),
+    }
+}
+
+fn mined(tx: &PendingTx) -> MinedTx {
+    MinedTx { hash: tx.hash, sender: tx.sender, nonce: tx.nonce }
+}
+
+fn status(store: &Store, n: u8) -> Option<String> {
+    store.status(B256::with_last_byte(n)).unwrap()
+}
+
+#[test]
+fn tracks_what_became_of_pending_txs() {
+    let mut store = temp_store("store-tracks");
+
+    let first = pending(1, ALICE, 7, 1_000);
+    assert!(store.insert_pending(&first).unwrap());
+    // seen again from another node: first sighting wins
+    assert!(!store.insert_pending(&PendingTx { first_seen: 2_000, ..first.clone() }).unwrap());
+
+    // same nonce, higher fee
+    let bumped = PendingTx { max_fee: 40 * GWEI, ..pending(2, ALICE, 7, 3_000) };
+    assert!(store.insert_pending(&bumped).unwrap());
+    assert_eq!(status(&store, 1).as_deref(), Some("replaced"));
+
+    let (stale, lower, other) =
+        (pending(3, BOB, 1, 500), pending(4, BOB, 2, 4_000), pending(5, BOB, 3, 4_000));
+    for tx in [&stale, &lower, &other] {
+        store.insert_pending(tx).unwrap();
+    }
+    assert_eq!(store.drop_stale(1_000).unwrap(), 1);
+    assert_eq!(status(&store, 3).as_deref(), Some("dropped"));
+
+    // bob's nonce 3 got mined, so 2 went through as a tx never seen here, and 1 too
+    let update =
+        store.insert_block(100, 12, Some(10 * GWEI), &[mined(&bumped), mined(&other)]).unwrap();
+    assert_eq!(update, BlockUpdate { included: 2, replaced: 2 });
+    assert_eq!(status(&store, 2).as_deref(), Some("included"));
+    assert_eq!(status(&store, 3).as_deref(), Some("replaced"));
+    assert_eq!(status(&store, 4).as_deref(), Some("replaced"));
+    assert_eq!(status(&store, 9), None);
+
+    // a tx mined without being seen pending replaces a pending one with its nonce
+    store.insert_pending(&pending(6, ALICE, 8, 5_000)).unwrap();
+    let unseen = MinedTx { hash: B256::with_last_byte(7), sender: ALICE, nonce: 8 };
+    assert_eq!(
+        store.insert_block(101, 24, None, &[unseen]).unwrap(),
+        BlockUpdate { included: 0, replaced: 1 }
+    );
+
+    assert_eq!(
+        store.counts().unwrap(),
+        StatusCounts { pending: 0, included: 2, replaced: 4, dropped: 0 }
+    );
+}
+
+#[test]
+fn undoes_a_reorged_block() {
+    let mut store = temp_store("store-reorg");
+    let (kept, dropped_out, replaced) =
+        (pending(1, ALICE, 1, 1_000), pending(2, BOB, 1, 1_000), pending(3, BOB, 0, 1_000));
+    for tx in [&kept, &dropped_out, &replaced] {
+        store.insert_pending(tx).unwrap();
+    }
+
+    store.insert_block(100, 12, None, &[mined(&kept), mined(&dropped_out)]).unwrap();
+    assert_eq!(status(&store, 2).as_deref(), Some("included"));
+    assert_eq!(status(&store, 3).as_deref(), Some("replaced"));
+
+    // the block that replaced it only has alice's tx
+    let update = store.insert_block(100, 13, None, &[mined(&kept)]).unwrap();
+    assert_eq!(update, BlockUpdate { included: 1, replaced: 0 });
+    assert_eq!(status(&store, 1).as_deref(), Some("included"));
+    assert_eq!(status(&store, 2).as_deref(), Some("pending"));
+    assert_eq!(status(&store, 3).as_deref(), Some("pending"));
+    assert_eq!(
+        store.counts().unwrap(),
+        StatusCounts { pending: 2, included: 1, replaced: 0, dropped: 0 }
+    );
+
+    // bob's tx makes it into the next one
+    store.insert_block(101, 24, None, &[mined(&dropped_out)]).unwrap();
+    assert_eq!(status(&store, 2).as_deref(), Some("included"));
+    assert_eq!(status(&store, 3).as_deref(), Some("replaced"));
+    let buckets = store.latency_report(&[]).unwrap();
+    assert_eq!(buckets[0].count, 2);
+}
+
+#[test]
+fn reports_latency_by_fee_bucket() {
+    let mut store = temp_store("store-latency");
+    let block_time = 100;
+
+    let mut txs = Vec::new();
+    for i in 0..10u8 {
+        // seen 1 to 10 seconds before the block
+        let seen = (block_time - 1 - i as u64) * 1000;
+        let tx = match i {
+            // legacy: 12 gwei over a 10 gwei base fee
+            0..=4 => PendingTx {
+                max_fee: 12 * GWEI,
+                priority_fee: None,
+                ..pending(i, ALICE, i as u64, seen)
+            },
+            // capped by the max fee: 11 - 10 = 1 gwei, not 3
+            _ => PendingTx {
+                max_fee: 11 * GWEI,
+                priority_fee: Some(3 * GWEI),
+                ..pending(i, BOB, i as u64, seen)
+            },
+        };
+        store.insert_pending(&tx).unwrap();
+        txs.push(mined(&tx));
+    }
+    // seen only after the block's timestamp, counts as no wait
+    let late = pending(20, BOB, 20, (block_time + 1) * 1000);
+    store.insert_pending(&late).unwrap();
+    txs.push(mined(&late));
+    store.insert_block(1, block_time, Some(10 * GWEI), &txs).unwrap();
+
+    let secs = Duration::from_secs;
+    assert_eq!(
+        store.latency_report(&[GWEI, 2 * GWEI, 5 * GWEI]).unwrap(),
+        [
+            FeeBucket {
+                low: GWEI,
+                high: Some(2 * GWEI),
+                count: 5,
+                p50: secs(8),
+                p90: secs(10),
+                p99: secs(10)
+            },
+            FeeBucket {
+                low: 2 * GWEI,
+                high: Some(5 * GWEI),
+                count: 6,
+                p50: secs(2),
+                p90: secs(5),
+                p99: secs(5)
+            },
+        ]
+    );
+    assert_eq!(
+        store.latency_report(&[]).unwrap()[0].to_string(),
+        ">=0                11     5.0s     9.0s    10.0s"
+    );
+}
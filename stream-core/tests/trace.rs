@@ -0,0 +1,220 @@
+use alloy_primitives::{address, b256, bytes, Address, Bytes, B256, U256};
+use serde_json::json;
+use stream_core::{
+    trace::{AccountDiff, CallKind, SelectorCount},
+    Backend, CallNode, GethTracer, TraceResult,
+};
+
+const EOA: Address = address!("00000000000000000000000000000000000000aa");
+const ROUTER: Address = address!("00000000000000000000000000000000000000bb");
+const TOKEN: Address = address!("00000000000000000000000000000000000000cc");
+
+fn call(kind: CallKind, from: Address, to: Address, input: Bytes) -> CallNode {
+    CallNode {
+        kind,
+        from,
+        to: Some(to),
+        value: U256::ZERO,
+        input,
+        output: Bytes::new(),
+        gas_used: 0,
+        error: None,
+        calls: Vec::new(),
+    }
+}
+
+#[test]
+fn detects_the_backend() {
+    assert_eq!(Backend::detect("reth/v1.1.0-abc/x86_64-unknown-linux-gnu"), Backend::Parity);
+    assert_eq!(Backend::detect("erigon/2.60.0/linux-amd64/go1.21"), Backend::Parity);
+    assert_eq!(Backend::detect("Nethermind/v1.29.0+1"), Backend::Parity);
+    assert_eq!(Backend::detect("Geth/v1.14.11-stable/linux-amd64/go1.23.2"), Backend::Geth);
+    assert_eq!(Backend::detect("anvil/v0.2.0"), Backend::Geth);
+}
+
+#[test]
+fn builds_the_tree_from_parity_traces() {
+    let swap = call(CallKind::Call, EOA, ROUTER, bytes!("38ed1739"));
+    let balance = call(CallKind::StaticCall, ROUTER, TOKEN, bytes!("70a08231"));
+    let transfer = call(CallKind::Call, ROUTER, TOKEN, bytes!("a9059cbb00"));
+    let inner = call(CallKind::DelegateCall, TOKEN, ROUTER, bytes!("a9059cbb00"));
+
+    let root = CallNode::tree([
+        (vec![], swap.clone()),
+        (vec![0], balance.clone()),
+        (vec![1], transfer),
+        (vec![1, 0], inner.clone()),
+    ])
+    .unwrap();
+
+    assert_eq!(root.calls.len(), 2);
+    assert_eq!(root.calls[1].calls, [inner]);
+    let walked: Vec<_> = root.walk().into_iter().map(|(depth, node)| (depth, node.kind)).collect();
+    assert_eq!(
+        walked,
+        [
+            (0, CallKind::Call),
+            (1, CallKind::StaticCall),
+            (1, CallKind::Call),
+            (2, CallKind::DelegateCall)
+        ]
+    );
+    assert_eq!(
+        root.selectors(),
+        [
+            SelectorCount { selector: [0x38, 0xed, 0x17, 0x39], size: 0, count: 1 },
+            SelectorCount { selector: [0x70, 0xa0, 0x82, 0x31], size: 0, count: 1 },
+            SelectorCount { selector: [0xa9, 0x05, 0x9c, 0xbb], size: 1, count: 2 },
+        ]
+    );
+
+    assert!(CallNode::tree([]).is_err());
+    assert!(CallNode::tree([(vec![0], balance.clone())]).is_err());
+    assert!(CallNode::tree([(vec![], swap.clone()), (vec![1], balance.clone())]).is_err());
+    assert!(CallNode::tree([(vec![], swap), (vec![0, 0], balance)]).is_err());
+}
+
+#[test]
+fn normalizes_call_tracer_frames() {
+    let frame = json!({
+        "type": "CALL",
+        "from": EOA,
+        "to": ROUTER,
+        "value": "0xde0b6b3a7640000",
+        "gas": "0x30000",
+        "gasUsed": "0x5208",
+        "input": "0x38ed1739",
+        "output": "0x",
+        "error": "execution reverted",
+        "revertReason": "INSUFFICIENT_OUTPUT_AMOUNT",
+        "calls": [
+            { "type": "STATICCALL", "from": ROUTER, "to": TOKEN, "gas": "0x100", "gasUsed": "0x10", "input": "0x70a08231" },
+            { "type": "CREATE2", "from": ROUTER, "to": TOKEN, "gas": "0x100", "gasUsed": "0x10", "input": "0x6080", "value": "0x0" }
+        ]
+    });
+
+    let mut trace = TraceResult::default();
+    GethTracer::Call.normalize(&frame, &mut trace).unwrap();
+    let root = trace.calls.take().unwrap();
+
+    assert_eq!(root.kind, CallKind::Call);
+    assert_eq!(root.value, U256::from(10u64.pow(18)));
+    assert_eq!(root.gas_used, 21_000);
+    assert_eq!(root.error.as_deref(), Some("INSUFFICIENT_OUTPUT_AMOUNT"));
+    assert_eq!(root.calls[0].kind, CallKind::StaticCall);
+    assert_eq!(root.calls[0].value, U256::ZERO);
+    assert_eq!(root.calls[1].kind, CallKind::Create2);
+    // creates don't count as selectors
+    assert_eq!(root.selectors().len(), 2);
+
+    assert!(GethTracer::Call.normalize(&json!({ "type": "JUMP" }), &mut trace).is_err());
+}
+
+#[test]
+fn normalizes_prestate_diffs() {
+    let slot = b256!("0000000000000000000000000000000000000000000000000000000000000001");
+    let cleared = b256!("0000000000000000000000000000000000000000000000000000000000000002");
+    let set = b256!("0000000000000000000000000000000000000000000000000000000000000003");
+    let one = b256!("0000000000000000000000000000000000000000000000000000000000000001");
+    let five = b256!("0000000000000000000000000000000000000000000000000000000000000005");
+
+    let result = json!({
+        "pre": {
+            EOA.to_string(): { "balance": "0x10", "nonce": 4 },
+            TOKEN.to_string(): {
+                "balance": "0x0",
+                "nonce": 1,
+                "code": "0x6080",
+                "storage": { slot.to_string(): one, cleared.to_string(): one }
+            },
+            ROUTER.to_string(): { "balance": "0x5", "storage": { slot.to_string(): one } }
+        },
+        "post": {
+            EOA.to_string(): { "balance": "0x8", "nonce": 5 },
+            TOKEN.to_string(): { "storage": { slot.to_string(): five, set.to_string(): five } },
+            address!("00000000000000000000000000000000000000dd").to_string(): { "balance": "0x2", "code": "0x00" }
+        }
+    });
+
+    let mut trace = TraceResult::default();
+    GethTracer::PrestateDiff.normalize(&result, &mut trace).unwrap();
+    let diff = trace.state_diff.unwrap();
+
+    assert_eq!(
+        diff[&EOA],
+        AccountDiff {
+            balance: Some((U256::from(16), U256::from(8))),
+            nonce: Some((4, 5)),
+            ..Default::default()
+        }
+    );
+
+    let token = &diff[&TOKEN];
+    assert_eq!((token.balance, token.nonce, &token.code), (None, None, &None));
+    assert_eq!(
+        token.storage.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+        [(slot, (one, five)), (cleared, (one, B256::ZERO)), (set, (B256::ZERO, five))]
+    );
+
+    // missing from post: selfdestructed
+    let router = &diff[&ROUTER];
+    assert_eq!(router.balance, Some((U256::from(5), U256::ZERO)));
+    assert_eq!(router.storage[&slot], (one, B256::ZERO));
+
+    // missing from pre: created
+    let created = &diff[&address!("00000000000000000000000000000000000000dd")];
+    assert_eq!(created.balance, Some((U256::ZERO, U256::from(2))));
+    assert_eq!(created.code, Some((Bytes::new(), bytes!("00"))));
+}
+
+#[test]
+fn normalizes_4byte_results() {
+    let result = json!({ "0xa9059cbb-64": 2, "0x70a08231-32": 1 });
+    let mut trace = TraceResult::default();
+    GethTracer::FourByte.normalize(&result, &mut trace).unwrap();
+    assert_eq!(
+        trace.selectors.take().unwrap(),
+        [
+            SelectorCount { selector: [0x70, 0xa0, 0x82, 0x31], size: 32, count: 1 },
+            SelectorCount { selector: [0xa9, 0x05, 0x9c, 0xbb], size: 64, count: 2 },
+        ]
+    );
+
+    assert!(GethTracer::FourByte.normalize(&json!({ "0xa9059c-64": 1 }), &mut trace).is_err());
+    assert!(GethTracer::FourByte.normalize(&json!({ "0xa9059cbb": 1 }), &mut trace).is_err());
+}
+
+#[test]
+fn summarizes_account_changes() {
+    let ether = U256::from(10u64.pow(18));
+    let account = AccountDiff {
+        balance: Some((ether * U256::from(3), ether * U256::from(18) / U256::from(10))),
+        nonce: Some((4, 5)),
+        code: Some((Bytes::new(), bytes!("6080604052"))),
+        storage: [
+            (B256::ZERO, (B256::ZERO, B256::with_last_byte(5))),
+            (B256::with_last_byte(0x1f), (B256::repeat_byte(0xff), B256::ZERO)),
+        ]
+        .into(),
+    };
+    assert_eq!(
+        account.changes(),
+        [
+            "balance -1.2 ETH",
+            "nonce 4 -> 5",
+            "code deployed, 5 bytes",
+            "slot 0x0: 0x0 -> 0x5",
+            &format!("slot 0x1f: 0x{} -> 0x0", "ff".repeat(32)),
+        ]
+    );
+
+    let received =
+        AccountDiff { balance: Some((ether, ether * U256::from(2))), ..Default::default() };
+    assert_eq!(received.changes(), ["balance +1 ETH"]);
+    let gas = AccountDiff {
+        balance: Some((ether, ether - U256::from(21_000u64 * 10u64.pow(9)))),
+        ..Default::default()
+    };
+    assert_eq!(gas.changes(), ["balance -0.000021 ETH"]);
+    assert!(AccountDiff::default().changes().is_empty());
+}
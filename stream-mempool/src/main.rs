@@ -1,47 +1,581 @@
-use eyre::Result;
-use futures_util::StreamExt;
+/*
+
+Streams pending transactions from a node over WebSocket and traces them,
+`--concurrency` at a time, until Ctrl-C or `--limit` txs. When traces can't keep up, txs
+queue up to `--queue` and then get dropped by `--drop-policy`. `--filter` picks which txs are
+worth a trace at all, e.g. `--filter 'to in @routers.txt && value > 1 ether'`. Calldata of
+the tx and of every call in its trace is decoded with the ABIs in `--abi-dir`, then with the
+signature database in `--signatures` (see the `signatures` binary of stream-core), falling back
+to the bare selector.
+
+cargo run --release -- --ws-url ws://127.0.0.1:8546 --trace-types trace,state-diff
+
+With `state-diff` each tx shows what it would change once mined, per account: `balance -1.2 ETH`,
+`nonce 4 -> 5`, `slot 0x3: 0x0 -> 0x5`. `vm-trace` (parity only) is summed up in a line.
+
+Traces come from `trace_call` on nodes with the parity trace namespace (reth, erigon,
+nethermind), or `debug_traceCall` with geth's built-in tracers on the rest; `--backend auto`
+picks by the node's client version. If the node restarts, the stream subscribes again; txs sent
+while it was down are missed and reported as a gap.
+
+`--format ndjson` writes a `pending_tx` line for every tx that passes the filter, then a `trace`
+line and a `call` line per call once it's traced, in the schema documented in stream-core's
+`sink` module; `--out events.ndjson` sends them to a file rotated every `--rotate-mb`.
+
+`--db mempool.db` records every pending tx, filtered or not, with when and where it was first
+seen, and follows new blocks to mark it included, replaced or, after `--drop-after`, dropped.
+The `latency` binary of stream-core reports inclusion times from it.
+*/
+
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use alloy::{
-    network::TransactionBuilder,
-    primitives::{address, U256},
-    providers::{ext::TraceApi, ProviderBuilder},
-    rpc::types::{trace::parity::TraceType, TransactionRequest, Transaction},
+    consensus::Transaction as _,
+    network::TransactionResponse,
+    primitives::TxHash,
+    providers::{Provider, ProviderBuilder, RootProvider, WsConnect},
+    pubsub::PubSubFrontend,
+    rpc::types::{trace::parity::VmTrace, BlockTransactionsKind, Transaction},
 };
-use alloy::providers::Provider;
-use alloy::providers::WsConnect;
+use clap::{builder::RangedU64ValueParser, Parser};
+use eyre::Result;
+use futures_util::StreamExt;
+use stream_core::{
+    resilient,
+    sink::{self, CallRecord, PendingTxRecord, TraceRecord},
+    store::{self, unix_ms, MinedTx, StatusCounts},
+    trace::CallKind,
+    AbiRegistry, Backend, Backoff, CallNode, DropPolicy, Event, Filter, OutputArgs, Pipeline,
+    PipelineConfig, Record, SignatureDb, SinkWriter, Store, TxFields,
+};
+use tokio::sync::{mpsc, oneshot};
+use trace::{TraceKind, Traced, Tracer};
+
+mod trace;
+
+#[derive(Parser, Debug)]
+struct Args {
+    /// WebSocket endpoint of the node.
+    #[arg(long, default_value = "ws://127.0.0.1:8546")]
+    ws_url: String,
+    /// Trace types to request for every pending tx.
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "trace")]
+    trace_types: Vec<TraceKind>,
+    /// Which trace API to use.
+    #[arg(long, value_enum, default_value = "auto")]
+    backend: Backend,
+    /// Stop after queueing this many pending txs; runs until Ctrl-C otherwise.
+    #[arg(long)]
+    limit: Option<u64>,
+    /// Only trace txs matching this expression, e.g. `selector == 0xa9059cbb && value > 1 ether`.
+    /// Fields: from, to, selector, value, gas_price, type, calldata_len.
+    #[arg(long)]
+    filter: Option<String>,
+    /// ABIs to decode calldata with: `<address>.json` per contract, any other `.json` file is
+    /// tried on every address.
+    #[arg(long)]
+    abi_dir: Option<PathBuf>,
+    /// Signature database for selectors no ABI covers; the built-in signatures otherwise.
+    #[arg(long)]
+    signatures: Option<PathBuf>,
+    /// Also print the whole call tree and VM trace with every field, over several lines.
+    #[arg(long)]
+    pretty: bool,
+    /// Subscribe again after this many seconds without a pending tx.
+    #[arg(long, default_value_t = 60)]
+    idle_timeout: u64,
+    /// Traces in flight at once.
+    #[arg(long, default_value_t = 8)]
+    concurrency: usize,
+    /// Pending txs waiting for a trace slot; past this the drop policy kicks in.
+    #[arg(
+        long,
+        default_value_t = 1_000,
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    queue: usize,
+    /// Which tx to give up when the queue is full.
+    #[arg(long, value_enum, default_value = "oldest")]
+    drop_policy: DropPolicy,
+    /// Seconds before a single trace call is abandoned.
+    #[arg(long, default_value_t = 10)]
+    trace_timeout: u64,
+    #[command(flatten)]
+    output: OutputArgs,
+    /// Record pending txs and what became of them in this SQLite database.
+    #[arg(long)]
+    db: Option<PathBuf>,
+    /// Seconds a recorded tx may stay pending before it counts as dropped.
+    #[arg(long, default_value_t = 600)]
+    drop_after: u64,
+}
+
+/// The most recent tx hashes, so re-announced txs aren't traced twice.
+struct Seen {
+    hashes: HashSet<TxHash>,
+    order: VecDeque<TxHash>,
+    capacity: usize,
+}
+
+impl Seen {
+    fn new(capacity: usize) -> Self {
+        Self { hashes: HashSet::new(), order: VecDeque::new(), capacity }
+    }
+
+    /// False if `hash` was already seen.
+    fn insert(&mut self, hash: TxHash) -> bool {
+        if !self.hashes.insert(hash) {
+            return false;
+        }
+        self.order.push_back(hash);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// A pending tx waiting to be traced on the connection it arrived on.
+struct Job {
+    provider: RootProvider<PubSubFrontend>,
+    tx: Transaction,
+}
+
+impl fmt::Display for Job {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tx {}", self.tx.tx_hash())
+    }
+}
+
+fn tx_fields(tx: &Transaction) -> TxFields {
+    let envelope = &tx.inner;
+    TxFields {
+        from: tx.from,
+        to: envelope.to(),
+        selector: TxFields::selector_of(envelope.input()),
+        value: envelope.value(),
+        gas_price: envelope.gas_price().unwrap_or_else(|| envelope.max_fee_per_gas()),
+        tx_type: envelope.ty(),
+        calldata_len: envelope.input().len(),
+    }
+}
+
+fn pending_tx_record(tx: &Transaction, abis: &AbiRegistry) -> PendingTxRecord {
+    let envelope = &tx.inner;
+    PendingTxRecord {
+        hash: tx.tx_hash(),
+        from: tx.from,
+        to: envelope.to(),
+        nonce: envelope.nonce(),
+        value: envelope.value(),
+        gas_price: envelope.gas_price().unwrap_or_else(|| envelope.max_fee_per_gas()),
+        priority_fee: envelope.max_priority_fee_per_gas(),
+        gas_limit: envelope.gas_limit(),
+        tx_type: envelope.ty(),
+        input_len: envelope.input().len(),
+        selector: TxFields::selector_of(envelope.input()),
+        function: sink::function(abis, envelope.to(), envelope.input()),
+    }
+}
+
+fn stored_tx(tx: &Transaction, source: &str) -> store::PendingTx {
+    let envelope = &tx.inner;
+    store::PendingTx {
+        hash: tx.tx_hash(),
+        sender: tx.from,
+        nonce: envelope.nonce(),
+        max_fee: envelope.gas_price().unwrap_or_else(|| envelope.max_fee_per_gas()),
+        priority_fee: envelope.max_priority_fee_per_gas(),
+        first_seen: unix_ms(),
+        source: source.to_string(),
+    }
+}
+
+/// A write for the store, or a request for its counts.
+enum StoreOp {
+    Pending(store::PendingTx),
+    /// Block `number` and its txs, then whatever was first seen before `drop_before` dropped.
+    Block {
+        number: u64,
+        timestamp: u64,
+        base_fee: Option<u128>,
+        txs: Vec<MinedTx>,
+        drop_before: u64,
+    },
+    Counts(oneshot::Sender<Result<StatusCounts>>),
+}
+
+/// Hands the store to a blocking thread, so SQLite's disk I/O never stalls the runtime. A
+/// write that fails is reported and counted; the stream goes on without it.
+#[derive(Clone)]
+struct StoreWriter {
+    ops: mpsc::UnboundedSender<StoreOp>,
+    errors: Arc<AtomicU64>,
+}
+
+impl StoreWriter {
+    fn spawn(mut store: Store) -> Self {
+        let (ops, mut rx) = mpsc::unbounded_channel();
+        let errors = Arc::new(AtomicU64::new(0));
+        let failed = errors.clone();
+        tokio::task::spawn_blocking(move || {
+            while let Some(op) = rx.blocking_recv() {
+                let written = match op {
+                    StoreOp::Pending(tx) => store.insert_pending(&tx).map(drop),
+                    StoreOp::Block { number, timestamp, base_fee, txs, drop_before } => store
+                        .insert_block(number, timestamp, base_fee, &txs)
+                        .and_then(|_| store.drop_stale(drop_before))
+                        .map(drop),
+                    StoreOp::Counts(reply) => {
+                        let _ = reply.send(store.counts());
+                        Ok(())
+                    }
+                };
+                if let Err(err) = written {
+                    eprintln!("failed to record in the database: {err:#}");
+                    failed.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+        Self { ops, errors }
+    }
+
+    fn send(&self, op: StoreOp) {
+        // the writer only stops once every sender is gone
+        let _ = self.ops.send(op);
+    }
+
+    /// Status counts once every write sent so far is done.
+    async fn counts(&self) -> Result<StatusCounts> {
+        let (reply, counts) = oneshot::channel();
+        self.send(StoreOp::Counts(reply));
+        counts.await?
+    }
+
+    fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+}
+
+/// Follows new blocks on its own connection, marking recorded txs included or replaced, and
+/// those pending longer than `drop_after` dropped. Blocks missed while disconnected are
+/// fetched on reconnect; a block number seen again after a reorg is recorded over the first.
+async fn watch_blocks(url: String, store: StoreWriter, drop_after: Duration) {
+    let subscribe = move || {
+        let url = url.clone();
+        async move {
+            let provider = ProviderBuilder::new().on_ws(WsConnect::new(url)).await?;
+            let sub = provider.subscribe_blocks().await?;
+            Ok::<_, eyre::Report>(sub.into_stream().map(move |header| (provider.clone(), header)))
+        }
+    };
+    // a block every 12 seconds, so a minute of silence means the subscription is gone
+    let backoff = Backoff { idle_timeout: Some(Duration::from_secs(60)), ..Default::default() };
+    let mut stream = Box::pin(resilient(subscribe, backoff));
+
+    let mut last_seen: Option<u64> = None;
+    while let Some(event) = stream.next().await {
+        // the pending tx stream already reports connection trouble
+        let Event::Item((provider, header)) = event else { continue };
+        let number = header.number;
+        let first = match last_seen {
+            Some(last) if number > last + 1 => last + 1,
+            _ => number,
+        };
+        last_seen = Some(last_seen.map_or(number, |last| last.max(number)));
+
+        for number in first..=number {
+            let block = match provider
+                .get_block_by_number(number.into(), BlockTransactionsKind::Full)
+                .await
+            {
+                Ok(Some(block)) => block,
+                Ok(None) => continue,
+                Err(err) => {
+                    eprintln!("failed to fetch block {number}: {err}");
+                    continue;
+                }
+            };
+            let txs: Vec<MinedTx> = (block.transactions.txns())
+                .map(|tx| MinedTx { hash: tx.tx_hash(), sender: tx.from, nonce: tx.inner.nonce() })
+                .collect();
+
+            store.send(StoreOp::Block {
+                number,
+                timestamp: block.header.timestamp,
+                base_fee: block.header.base_fee_per_gas.map(u128::from),
+                txs,
+                drop_before: unix_ms().saturating_sub(drop_after.as_millis() as u64),
+            });
+        }
+    }
+}
+
+/// The `trace` line of a traced tx followed by its `call` lines.
+fn trace_records(
+    tx: &Transaction,
+    traced: &Traced,
+    backend: Backend,
+    abis: &AbiRegistry,
+) -> Vec<Record> {
+    let (hash, result) = (tx.tx_hash(), &traced.result);
+    let trace = TraceRecord::new(
+        hash,
+        backend,
+        result.calls.as_ref(),
+        result.state_diff.as_ref(),
+        result.selectors.as_deref(),
+    );
+    let calls = result.calls.iter().flat_map(|root| CallRecord::tree(Some(hash), None, root, abis));
+    std::iter::once(Record::Trace(trace)).chain(calls.map(Record::Call)).collect()
+}
+
+/// One line per call, indented by depth.
+fn render_calls(root: &CallNode, abis: &AbiRegistry) -> String {
+    let mut out = String::new();
+    for (depth, node) in root.walk() {
+        let to = node.to.map_or_else(|| "nothing".to_string(), |to| to.to_string());
+        let line = match node.kind {
+            CallKind::Create | CallKind::Create2 => format!("{} {to}", node.kind),
+            CallKind::SelfDestruct => format!("selfdestruct {} to {to}", node.from),
+            kind => format!("{kind} {to}: {}", abis.decode(node.to, &node.input)),
+        };
+        let indent = "  ".repeat(depth + 1);
+        out += &format!("\n{indent}{line}");
+        if let Some(error) = &node.error {
+            out += &format!(" ({error})");
+        }
+    }
+    out
+}
+
+/// Everything printed for one tx, as one string so concurrent workers don't interleave.
+fn render_trace(tx: &Transaction, traced: &Traced, abis: &AbiRegistry, pretty: bool) -> String {
+    let call = abis.decode(tx.inner.to(), tx.inner.input());
+    let mut out = format!("tx {} from {}: {call}", tx.tx_hash(), tx.from);
+    let result = &traced.result;
+    if let Some(calls) = &result.calls {
+        out += &render_calls(calls, abis);
+        if pretty {
+            out += &format!("\n{calls:#?}");
+        }
+    }
+    if let Some(selectors) = &result.selectors {
+        let selectors: Vec<String> = selectors
+            .iter()
+            .map(|s| {
+                let hex: String = s.selector.iter().map(|b| format!("{b:02x}")).collect();
+                format!("0x{hex}-{} x{}", s.size, s.count)
+            })
+            .collect();
+        out += &format!("\nselectors: {}", selectors.join(", "));
+    }
+    if let Some(state_diff) = &result.state_diff {
+        out += "\nstate diff:";
+        for (address, account) in state_diff {
+            out += &format!("\n  {address}");
+            for change in account.changes() {
+                out += &format!("\n    {change}");
+            }
+        }
+    }
+    if let Some(vm_trace) = &traced.vm_trace {
+        out += &format!("\nvm trace: {}", vm_trace_summary(vm_trace));
+        if pretty {
+            out += &format!("\n{vm_trace:#?}");
+        }
+    }
+    out
+}
+
+/// Counts over the whole VM trace, subcalls included; `--pretty` prints all of it.
+fn vm_trace_summary(vm_trace: &VmTrace) -> String {
+    let (mut instructions, mut stores, mut calls) = (0, 0, 0);
+    let mut stack = vec![vm_trace];
+    while let Some(vm_trace) = stack.pop() {
+        for op in &vm_trace.ops {
+            instructions += 1;
+            if op.ex.as_ref().is_some_and(|ex| ex.store.is_some()) {
+                stores += 1;
+            }
+            if let Some(sub) = &op.sub {
+                calls += 1;
+                stack.push(sub);
+            }
+        }
+    }
+    format!("{instructions} instructions, {stores} storage writes, {calls} subcalls")
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let rpc_url = "ws://127.0.0.1:8546";
+    let args = Args::parse();
+    let filter = args.filter.as_deref().map(Filter::parse).transpose()?;
+    let mut abis = match &args.abi_dir {
+        Some(dir) => AbiRegistry::load(dir)?,
+        None => AbiRegistry::default(),
+    };
+    abis.set_signatures(match &args.signatures {
+        Some(path) => SignatureDb::open(path)?,
+        None => SignatureDb::embedded(),
+    });
+    let abis = Arc::new(abis);
+    let sink = args.output.sink()?.map(SinkWriter::spawn);
+    let store = args.db.as_deref().map(Store::open).transpose()?.map(StoreWriter::spawn);
+    if let Some(store) = &store {
+        let drop_after = Duration::from_secs(args.drop_after);
+        tokio::spawn(watch_blocks(args.ws_url.clone(), store.clone(), drop_after));
+    }
+    // stdout may be NDJSON, so status lines go to stderr then
+    let ndjson = sink.is_some();
+    let status = move |line: String| if ndjson { eprintln!("{line}") } else { println!("{line}") };
 
-    let ws = WsConnect::new(rpc_url);
-    let provider = ProviderBuilder::new().on_ws(ws).await?;
+    let backend = match args.backend {
+        Backend::Auto => {
+            let provider = ProviderBuilder::new().on_ws(WsConnect::new(&args.ws_url)).await?;
+            let client = provider.get_client_version().await?;
+            let backend = Backend::detect(&client);
+            status(format!("{client}: tracing with the {backend:?} backend"));
+            backend
+        }
+        backend => backend,
+    };
+    let tracer = Arc::new(Tracer::new(backend, args.trace_types.clone())?);
 
-    let sub = provider.subscribe_full_pending_transactions().await?;
+    let url = args.ws_url.clone();
+    let subscribe = move || {
+        let url = url.clone();
+        async move {
+            let provider = ProviderBuilder::new().on_ws(WsConnect::new(url)).await?;
+            let sub = provider.subscribe_full_pending_transactions().await?;
+            // each tx is traced on the connection it came from
+            Ok::<_, eyre::Report>(sub.into_stream().map(move |tx| (provider.clone(), tx)))
+        }
+    };
+    let backoff = Backoff {
+        idle_timeout: Some(Duration::from_secs(args.idle_timeout)),
+        ..Default::default()
+    };
+    let mut stream = Box::pin(resilient(subscribe, backoff));
 
-    // Wait and take the next 3 transactions.
-    //let mut stream = sub.into_stream();
-    let mut stream = sub.into_stream().take(1);
+    let config = PipelineConfig {
+        concurrency: args.concurrency,
+        queue: args.queue,
+        timeout: Duration::from_secs(args.trace_timeout),
+        drop_policy: args.drop_policy,
+    };
+    let pretty = args.pretty;
+    let (pipeline_abis, pipeline_sink) = (abis.clone(), sink.clone());
+    let pipeline = Pipeline::spawn(config, move |job: Job| {
+        let (tracer, abis, sink) = (tracer.clone(), pipeline_abis.clone(), pipeline_sink.clone());
+        async move {
+            let traced = tracer.trace(&job.provider, &job.tx).await?;
+            match sink {
+                Some(sink) => {
+                    for record in trace_records(&job.tx, &traced, tracer.backend, &abis) {
+                        sink.emit(record);
+                    }
+                }
+                None => println!("{}", render_trace(&job.tx, &traced, &abis, pretty)),
+            }
+            Ok(())
+        }
+    });
 
-    println!("Awaiting pending transactions...");
+    status(format!("Awaiting pending transactions from {}...", args.ws_url));
 
-    let handle = tokio::spawn(async move {
-        while let Some(tx) = stream.next().await {
-            //println!("{:#?}", tx);
+    // re-announced while still in the recent window, e.g. after a re-broadcast
+    let mut duplicates = 0u64;
+    let mut filtered = 0u64;
+    let mut seen = Seen::new(10_000);
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
 
-	    //let tx = TransactionRequest::default().from(tx.from).to(tx.transact_to).input(calldata.into());
+    let interrupted = loop {
+        let event = tokio::select! {
+            _ = &mut shutdown => break true,
+            event = stream.next() => event,
+        };
+        let (provider, tx) = match event {
+            Some(Event::Item(item)) => item,
+            Some(Event::Disconnected { reason }) => {
+                eprintln!("disconnected: {reason}; pending txs are missed until reconnected");
+                continue;
+            }
+            Some(Event::Retrying { attempt, delay, error }) => {
+                eprintln!("subscribing failed: {error}; retry {attempt} in {delay:?}");
+                continue;
+            }
+            Some(Event::Reconnected { downtime, attempts }) => {
+                eprintln!("reconnected after {downtime:?} and {attempts} attempts");
+                continue;
+            }
+            None => break false,
+        };
 
-            //TransactionRequest::default().with_from(tx.from).with_to(tx.transact_to).with_data(tx.data.0);
+        if !seen.insert(tx.tx_hash()) {
+            duplicates += 1;
+            continue;
+        }
+        if let Some(store) = &store {
+            store.send(StoreOp::Pending(stored_tx(&tx, &args.ws_url)));
+        }
+        if filter.as_ref().is_some_and(|filter| !filter.matches(&tx_fields(&tx))) {
+            filtered += 1;
+            continue;
+        }
 
+        if let Some(sink) = &sink {
+            sink.emit(Record::PendingTx(pending_tx_record(&tx, &abis)));
+        }
+        pipeline.submit(Job { provider, tx });
 
-	    let trace_type = [TraceType::Trace];
-            let result = provider.trace_call(&tx.into(), &trace_type).await;
-            println!("{:?}", result.unwrap().trace);
-      }
-    });
+        if args.limit.is_some_and(|limit| pipeline.counts().submitted >= limit) {
+            break false;
+        }
+    };
 
-    handle.await?;
+    // on Ctrl-C don't wait for the queue, only for what was already printed
+    let counts = pipeline.shutdown(!interrupted).await;
+    status(format!(
+        "processed: {}, failed: {} ({} timed out), skipped: {} ({} filtered, {} duplicates, {} dropped), cancelled: {}",
+        counts.completed,
+        counts.failed + counts.timed_out,
+        counts.timed_out,
+        filtered + duplicates + counts.dropped,
+        filtered,
+        duplicates,
+        counts.dropped,
+        counts.cancelled
+    ));
+    if let Some(sink) = &sink {
+        let errors = sink.flush().await;
+        if errors > 0 {
+            status(format!("{errors} records couldn't be written"));
+        }
+    }
+    if let Some(store) = &store {
+        let recorded = store.counts().await?;
+        status(format!(
+            "recorded: {} included, {} pending, {} replaced, {} dropped, {} failed writes",
+            recorded.included,
+            recorded.pending,
+            recorded.replaced,
+            recorded.dropped,
+            store.errors()
+        ));
+    }
 
     Ok(())
 }
-
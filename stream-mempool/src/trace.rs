@@ -0,0 +1,212 @@
+//! Tracing a pending tx on whichever backend the node serves, into stream-core's common model.
+
+use alloy::{
+    primitives::Bytes,
+    providers::{ext::TraceApi, Provider, RootProvider},
+    pubsub::PubSubFrontend,
+    rpc::types::{
+        trace::parity::{
+            Action, CallType, ChangedType, Delta, StateDiff as ParityStateDiff, TraceOutput,
+            TraceType, TransactionTrace, VmTrace,
+        },
+        BlockNumberOrTag, Transaction, TransactionRequest,
+    },
+};
+use clap::ValueEnum;
+use eyre::{bail, Result};
+use stream_core::{
+    trace::{AccountDiff, CallKind},
+    Backend, CallNode, GethTracer, StateDiff, TraceResult,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TraceKind {
+    /// The call tree: `trace` on parity, `callTracer` on geth.
+    Trace,
+    /// Balance, nonce, code and storage changes: `stateDiff` on parity, `prestateTracer` in
+    /// diff mode on geth.
+    StateDiff,
+    /// Every executed instruction; parity only.
+    VmTrace,
+    /// Selectors called, with calldata sizes: `4byteTracer` on geth, counted from the call
+    /// tree on parity.
+    #[value(name = "4byte")]
+    FourByte,
+}
+
+/// What to ask the node for each tx, decided once at startup.
+#[derive(Debug, Clone)]
+pub struct Tracer {
+    pub backend: Backend,
+    kinds: Vec<TraceKind>,
+}
+
+/// A traced tx; the VM trace has no counterpart on geth, so it stays in parity's shape.
+pub struct Traced {
+    pub result: TraceResult,
+    pub vm_trace: Option<VmTrace>,
+}
+
+impl Tracer {
+    /// `backend` is parity or geth, [`Backend::Auto`] resolved already.
+    pub fn new(backend: Backend, kinds: Vec<TraceKind>) -> Result<Self> {
+        if backend == Backend::Geth && kinds.contains(&TraceKind::VmTrace) {
+            bail!("vm-trace needs the parity backend, geth has no equivalent tracer");
+        }
+        Ok(Self { backend, kinds })
+    }
+
+    pub async fn trace(
+        &self,
+        provider: &RootProvider<PubSubFrontend>,
+        tx: &Transaction,
+    ) -> Result<Traced> {
+        let request: TransactionRequest = tx.clone().into();
+        match self.backend {
+            Backend::Geth => self.trace_geth(provider, request).await,
+            _ => self.trace_parity(provider, request).await,
+        }
+    }
+
+    async fn trace_parity(
+        &self,
+        provider: &RootProvider<PubSubFrontend>,
+        request: TransactionRequest,
+    ) -> Result<Traced> {
+        let mut types = Vec::new();
+        for kind in &self.kinds {
+            let trace_type = match kind {
+                TraceKind::Trace | TraceKind::FourByte => TraceType::Trace,
+                TraceKind::StateDiff => TraceType::StateDiff,
+                TraceKind::VmTrace => TraceType::VmTrace,
+            };
+            if !types.contains(&trace_type) {
+                types.push(trace_type);
+            }
+        }
+
+        let results = provider.trace_call(&request, &types).await?;
+        let calls = parity_calls(&results.trace)?;
+        let result = TraceResult {
+            selectors: self
+                .kinds
+                .contains(&TraceKind::FourByte)
+                .then(|| calls.as_ref().map(CallNode::selectors).unwrap_or_default()),
+            calls: calls.filter(|_| self.kinds.contains(&TraceKind::Trace)),
+            state_diff: results.state_diff.as_ref().map(parity_state_diff),
+        };
+        Ok(Traced { result, vm_trace: results.vm_trace })
+    }
+
+    async fn trace_geth(
+        &self,
+        provider: &RootProvider<PubSubFrontend>,
+        request: TransactionRequest,
+    ) -> Result<Traced> {
+        let mut result = TraceResult::default();
+        for kind in &self.kinds {
+            let tracer = match kind {
+                TraceKind::Trace => GethTracer::Call,
+                TraceKind::StateDiff => GethTracer::PrestateDiff,
+                TraceKind::FourByte => GethTracer::FourByte,
+                TraceKind::VmTrace => unreachable!("rejected in Tracer::new"),
+            };
+            // one call per tracer, geth runs a single tracer at a time
+            let params = (request.clone(), BlockNumberOrTag::Latest, tracer.options());
+            let value: serde_json::Value =
+                provider.raw_request("debug_traceCall".into(), params).await?;
+            tracer.normalize(&value, &mut result)?;
+        }
+        Ok(Traced { result, vm_trace: None })
+    }
+}
+
+/// The flat parity traces as a tree; `None` if the trace type wasn't requested.
+fn parity_calls(traces: &[TransactionTrace]) -> Result<Option<CallNode>> {
+    if traces.is_empty() {
+        return Ok(None);
+    }
+
+    let flat = traces.iter().filter_map(|trace| {
+        let (output, gas_used, created) = match &trace.result {
+            Some(TraceOutput::Call(call)) => (call.output.clone(), call.gas_used, None),
+            Some(TraceOutput::Create(create)) => {
+                (create.code.clone(), create.gas_used, Some(create.address))
+            }
+            None => (Bytes::new(), 0, None),
+        };
+        let node = match &trace.action {
+            Action::Call(call) => CallNode {
+                kind: match call.call_type {
+                    CallType::StaticCall => CallKind::StaticCall,
+                    CallType::DelegateCall => CallKind::DelegateCall,
+                    CallType::CallCode => CallKind::CallCode,
+                    _ => CallKind::Call,
+                },
+                from: call.from,
+                to: Some(call.to),
+                value: call.value,
+                input: call.input.clone(),
+                output,
+                gas_used,
+                error: trace.error.clone(),
+                calls: Vec::new(),
+            },
+            Action::Create(create) => CallNode {
+                kind: CallKind::Create,
+                from: create.from,
+                to: created,
+                value: create.value,
+                input: create.init.clone(),
+                output,
+                gas_used,
+                error: trace.error.clone(),
+                calls: Vec::new(),
+            },
+            Action::Selfdestruct(destruct) => CallNode {
+                kind: CallKind::SelfDestruct,
+                from: destruct.address,
+                to: Some(destruct.refund_address),
+                value: destruct.balance,
+                input: Bytes::new(),
+                output,
+                gas_used,
+                error: trace.error.clone(),
+                calls: Vec::new(),
+            },
+            Action::Reward(_) => return None,
+        };
+        Some((trace.trace_address.clone(), node))
+    });
+
+    CallNode::tree(flat).map(Some)
+}
+
+fn parity_state_diff(diff: &ParityStateDiff) -> StateDiff {
+    diff.0
+        .iter()
+        .map(|(address, account)| {
+            let account = AccountDiff {
+                balance: delta(&account.balance),
+                nonce: delta(&account.nonce).map(|(from, to)| (from.to(), to.to())),
+                code: delta(&account.code),
+                storage: account
+                    .storage
+                    .iter()
+                    .filter_map(|(slot, value)| Some((*slot, delta(value)?)))
+                    .collect(),
+            };
+            (*address, account)
+        })
+        .collect()
+}
+
+/// `(before, after)`, with whatever didn't exist on one side as zero.
+fn delta<T: Clone + Default>(delta: &Delta<T>) -> Option<(T, T)> {
+    match delta {
+        Delta::Unchanged => None,
+        Delta::Added(to) => Some((T::default(), to.clone())),
+        Delta::Removed(from) => Some((from.clone(), T::default())),
+        Delta::Changed(ChangedType { from, to }) => Some((from.clone(), to.clone())),
+    }
+}
@@ -1,55 +1,211 @@
-//! Example of subscribing and listening for pending transactions in the public mempool by
-//! `WebSocket` subscription.
+//! Example of subscribing to new blocks by `WebSocket` subscription and tracing each one.
+//!
+//! If the node restarts, the stream subscribes again and backfills the blocks it missed from
+//! the last block number it saw.
+//!
+//! Calls in the traces are named by their selector, from the signature database at
+//! `--signatures` on top of the built-in signatures.
+//!
+//! `--format ndjson` writes a `block` line per block and a `call` line per call or create in
+//! its traces instead, in the schema of stream-core's `sink` module.
 
-// use alloy::providers::{Provider, ProviderBuilder, WsConnect};
-use alloy_provider::{network::AnyNetwork, Provider, ProviderBuilder, RootProvider};
+use std::{path::PathBuf, time::Duration};
+
+use alloy_provider::{Provider, ProviderBuilder};
 use alloy_rpc_client::WsConnect;
+use alloy_rpc_types::BlockNumberOrTag;
+use clap::Parser;
 use eyre::Result;
 use futures_util::StreamExt;
-use alloy_rpc_types::{Block, BlockId, BlockNumberOrTag};
-
-use alloy_rpc_types_trace::{
-    filter::TraceFilter,
-    parity::{LocalizedTransactionTrace, TraceResults, TraceResultsWithTransactionHash, TraceType},
+use stream_core::{
+    primitives::{Address, FixedBytes, B256, U256},
+    resilient,
+    sink::{BlockRecord, CallRecord},
+    Backoff, Event, OutputArgs, Record, SignatureDb, SinkWriter,
 };
 
 use alloy_provider::ext::TraceApi;
-use alloy_rpc_types_trace::parity::Action;
+use alloy_rpc_types_trace::parity::{Action, LocalizedTransactionTrace, TraceOutput};
+
+const WS_URL: &str = "ws://10.0.0.227:8546";
+const SIGNATURES: &str = "signatures.txt";
+
+#[derive(Parser, Debug)]
+struct Args {
+    /// WebSocket endpoint of the node.
+    #[arg(long, default_value = WS_URL)]
+    ws_url: String,
+    /// Signature database to name calls with, on top of the built-in signatures.
+    #[arg(long, default_value = SIGNATURES)]
+    signatures: PathBuf,
+    #[command(flatten)]
+    output: OutputArgs,
+}
+
+/// The `call` line of a block trace; parity traces are flat, so the depth is the length of the
+/// trace address. Rewards and selfdestructs have none.
+fn call_record(
+    number: u64,
+    trace: &LocalizedTransactionTrace,
+    signatures: &SignatureDb,
+) -> Option<CallRecord> {
+    // this alloy is on an older alloy-primitives than stream-core, so values are copied over
+    let address = |address: &[u8]| Address::from_slice(address);
+    let gas_used = match &trace.trace.result {
+        Some(TraceOutput::Call(call)) => call.gas_used,
+        Some(TraceOutput::Create(create)) => create.gas_used,
+        None => 0,
+    };
+    let (call_type, from, to, value, selector) = match &trace.trace.action {
+        Action::Call(call) => {
+            let selector = call.input.get(..4).map(FixedBytes::<4>::from_slice);
+            let call_type = format!("{:?}", call.call_type).to_lowercase();
+            (
+                call_type,
+                address(call.from.as_slice()),
+                Some(address(call.to.as_slice())),
+                call.value,
+                selector,
+            )
+        }
+        Action::Create(create) => {
+            let to = match &trace.trace.result {
+                Some(TraceOutput::Create(created)) => Some(address(created.address.as_slice())),
+                _ => None,
+            };
+            ("create".to_string(), address(create.from.as_slice()), to, create.value, None)
+        }
+        _ => return None,
+    };
+
+    Some(CallRecord {
+        tx_hash: trace.transaction_hash.map(|hash| B256::from_slice(hash.as_slice())),
+        block: Some(number),
+        depth: trace.trace.trace_address.len(),
+        call_type,
+        from,
+        to,
+        value: U256::from_limbs(*value.as_limbs()),
+        function: selector.and_then(|selector| signatures.function(selector.0).first().cloned()),
+        selector,
+        gas_used,
+        error: trace.trace.error.clone(),
+    })
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let ws_url = "ws://10.0.0.227:8546";
+    let args = Args::parse();
+    let signatures = SignatureDb::open(&args.signatures)?;
+    let sink = args.output.sink()?.map(SinkWriter::spawn);
 
-    let ws = WsConnect::new(ws_url);
-    let provider = ProviderBuilder::new().on_ws(ws).await?;
+    let url = args.ws_url.clone();
+    let subscribe = move || {
+        let url = url.clone();
+        async move {
+            let provider = ProviderBuilder::new().on_ws(WsConnect::new(url)).await?;
+            let subscription = provider.subscribe_blocks().await?;
+            // each block is traced on the connection it came from
+            Ok::<_, eyre::Report>(
+                subscription.into_stream().map(move |block| (provider.clone(), block)),
+            )
+        }
+    };
+    // a block every 12 seconds, so a minute of silence means the node is gone
+    let backoff = Backoff { idle_timeout: Some(Duration::from_secs(60)), ..Default::default() };
+    let mut stream = Box::pin(resilient(subscribe, backoff));
 
+    let mut last_seen: Option<u64> = None;
 
-    let subscription = provider.subscribe_blocks().await?;
-    let mut stream = subscription.into_stream();
+    while let Some(event) = stream.next().await {
+        let (provider, block) = match event {
+            Event::Item(item) => item,
+            Event::Disconnected { reason } => {
+                match last_seen {
+                    Some(last) => eprintln!("disconnected: {reason}; will backfill after {last}"),
+                    None => eprintln!("disconnected: {reason}"),
+                }
+                continue;
+            }
+            Event::Retrying { attempt, delay, error } => {
+                eprintln!("subscribing failed: {error}; retry {attempt} in {delay:?}");
+                continue;
+            }
+            Event::Reconnected { downtime, attempts } => {
+                eprintln!("reconnected after {downtime:?} and {attempts} attempts");
+                continue;
+            }
+        };
 
-    while let Some(block) = stream.next().await {
-        println!(
-            "Received block number: {}",
-            block.header.number.expect("Failed to get block number")
-        );
+        let Some(number) = block.header.number else {
+            eprintln!("skipping a block without a number");
+            continue;
+        };
 
-        let traces = provider.trace_block(BlockNumberOrTag::Number(block.header.number.expect("REASON"))).await?;
-        // TransportResult<Vec<LocalizedTransactionTrace>>
+        // blocks that went by while disconnected, oldest first; a lower number is a reorg
+        let first = match last_seen {
+            Some(last) if number > last + 1 => {
+                eprintln!("backfilling blocks {}..={}", last + 1, number - 1);
+                last + 1
+            }
+            _ => number,
+        };
+        last_seen = Some(last_seen.map_or(number, |last| last.max(number)));
+
+        for number in first..=number {
+            if sink.is_none() {
+                println!("Received block number: {}", number);
+            }
 
-        for trace in traces {
-            //println!("Trace: {:?}", trace);
-            match trace.trace.action {
-                Action::Call(tx) => {
+            let traces = match provider.trace_block(BlockNumberOrTag::Number(number)).await {
+                Ok(traces) => traces,
+                Err(err) => {
+                    eprintln!("failed to trace block {number}: {err}");
+                    continue;
+                }
+            };
+
+            if let Some(sink) = &sink {
+                // only the subscribed block came with its header
+                let hash = block.header.hash.filter(|_| block.header.number == Some(number));
+                let hash = hash.map(|hash| B256::from_slice(hash.as_slice()));
+                sink.emit(Record::Block(BlockRecord { number, hash, traces: traces.len() }));
+                for trace in &traces {
+                    if let Some(call) = call_record(number, trace, &signatures) {
+                        sink.emit(Record::Call(call));
+                    }
+                }
+                continue;
+            }
+
+            for trace in traces {
+                //println!("Trace: {:?}", trace);
+                if let Action::Call(tx) = trace.trace.action {
                     // if tx.input < 4 then return
                     if tx.input.0.len() < 4 {
                         continue;
                     }
-                },
-                _ => {}
+                    let selector: [u8; 4] = tx.input[..4].try_into().unwrap();
+                    match signatures.function(selector) {
+                        [] => {
+                            let hex: String = selector.iter().map(|b| format!("{b:02x}")).collect();
+                            println!("  {}: 0x{hex}", tx.to);
+                        }
+                        [signature] => println!("  {}: {signature}", tx.to),
+                        [signature, others @ ..] => {
+                            println!("  {}: {signature} (or {} colliding)", tx.to, others.len())
+                        }
+                    }
+                }
             }
         }
-
     }
 
+    if let Some(sink) = &sink {
+        let errors = sink.flush().await;
+        if errors > 0 {
+            eprintln!("{errors} records couldn't be written");
+        }
+    }
     Ok(())
-}
\ No newline at end of file
+}
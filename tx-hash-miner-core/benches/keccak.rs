@@ -0,0 +1,40 @@
+//! alloy's one-at-a-time vs. lockstep Keccak-256 over signed-tx sized inputs.
+//!
+//!     cargo bench --bench keccak
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use miner_core::keccak::{self, LANES};
+
+fn inputs(len: usize) -> Vec<Vec<u8>> {
+    (0..1024u32).map(|i| (0..len as u32).map(|j| (i ^ j.wrapping_mul(31)) as u8).collect()).collect()
+}
+
+fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("keccak256");
+
+    // a plain transfer, a setName call, and one spilling into a second block
+    for len in [110, 180, 300] {
+        let inputs = inputs(len);
+        group.throughput(Throughput::Elements(inputs.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("alloy", len), &inputs, |b, inputs| {
+            b.iter(|| inputs.iter().map(alloy::primitives::keccak256).collect::<Vec<_>>())
+        });
+        group.bench_with_input(BenchmarkId::new("x4", len), &inputs, |b, inputs| {
+            b.iter(|| keccak::keccak256_batch(inputs))
+        });
+        group.bench_with_input(BenchmarkId::new("x4-portable", len), &inputs, |b, inputs| {
+            b.iter(|| {
+                inputs
+                    .chunks_exact(LANES)
+                    .flat_map(|c| keccak::keccak256_x4_portable(std::array::from_fn(|i| &c[i][..])))
+                    .collect::<Vec<_>>()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,307 @@
+//! Mining from inside another async service: one call from a tx template to a signed tx, with
+//! the CPU work kept off the runtime's worker threads.
+
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    consensus::{BlobTransactionSidecar, TxEip1559, TxEip4844, TxEip7702, TxEnvelope},
+    primitives::{Address, B256, U256},
+};
+use eyre::{bail, Result};
+
+use crate::{
+    blob,
+    engine::{self, CostCapExceeded, Mined, PriorityFeeCapReached},
+    tx::{MinableTx, Parameter, TxType},
+    MinerSigner, Options, Progress, Strategy, Target, TxTemplate,
+};
+
+/// Called every `MineRequest::progress_interval` while mining.
+pub type ProgressCallback = Box<dyn FnMut(ProgressReport) + Send>;
+
+/// Attempts so far, and how fast they went since the previous report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressReport {
+    pub attempts: u64,
+    pub per_sec: f64,
+}
+
+/// Everything one mining run needs.
+pub struct MineRequest {
+    /// Has to be complete; `TxTemplate::fill` gets the missing fields from a node.
+    pub template: TxTemplate,
+    pub strategy: Strategy,
+    pub target: Target,
+    pub signer: MinerSigner,
+    pub options: Options,
+    /// `Progress::cancel` on this stops the run; so does dropping the future.
+    pub progress: Arc<Progress>,
+    pub on_progress: Option<ProgressCallback>,
+    pub progress_interval: Duration,
+}
+
+impl MineRequest {
+    pub fn new(
+        template: TxTemplate,
+        strategy: Strategy,
+        target: Target,
+        signer: MinerSigner,
+    ) -> Self {
+        Self {
+            template,
+            strategy,
+            target,
+            signer,
+            options: Options::default(),
+            progress: Arc::new(Progress::default()),
+            on_progress: None,
+            progress_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// A signed tx whose hash meets the target, ready to broadcast.
+#[derive(Debug, Clone)]
+pub struct MinedTx {
+    /// Blob txs come with their sidecar attached.
+    pub envelope: TxEnvelope,
+    pub hash: B256,
+    pub from: Address,
+    pub attempts: u64,
+    /// The value of the mined field that produced the hash.
+    pub parameter: Parameter,
+    /// The most the tx can spend, fees and value together.
+    pub cost: U256,
+}
+
+#[derive(Debug)]
+pub enum MineError {
+    /// A missing field, or a blob file that can't be read.
+    Template(eyre::Report),
+    /// The strategy mines a field the tx type doesn't have.
+    UnsupportedStrategy { strategy: Strategy, tx_type: TxType },
+    /// Every attempt left could spend more than `Options::max_cost`.
+    CostCap { cap: U256, cost: U256 },
+    /// The priority fee strategy raised the fee to the max fee per gas without a match.
+    PriorityFeeCap { max_fee_per_gas: u128 },
+    /// Cancelled through `Progress::cancel` or by dropping the future.
+    Cancelled { attempts: u64 },
+    /// Signing failed, e.g. an external signer went away.
+    Signer(eyre::Report),
+}
+
+impl fmt::Display for MineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Template(err) => write!(f, "bad tx template: {err}"),
+            Self::UnsupportedStrategy { strategy, tx_type } => {
+                write!(f, "the {strategy:?} strategy has nothing to mine in a {tx_type:?} tx")
+            }
+            Self::CostCap { cap, cost } => {
+                write!(f, "{}", CostCapExceeded { cap: *cap, cost: *cost })
+            }
+            Self::PriorityFeeCap { max_fee_per_gas } => {
+                write!(f, "{}", PriorityFeeCapReached { max_fee_per_gas: *max_fee_per_gas })
+            }
+            Self::Cancelled { attempts } => write!(f, "mining cancelled after {attempts} attempts"),
+            Self::Signer(err) => write!(f, "signing failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for MineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Template(err) | Self::Signer(err) => Some(&**err),
+            _ => None,
+        }
+    }
+}
+
+/// Mines the tx described by `request.template`.
+///
+/// Local signers run on the blocking pool, so the caller's runtime keeps serving other tasks;
+/// external signers are network bound and stay on the runtime.
+pub async fn mine_tx(request: MineRequest) -> Result<MinedTx, MineError> {
+    let MineRequest {
+        template,
+        strategy,
+        target,
+        signer,
+        options,
+        progress,
+        on_progress,
+        progress_interval,
+    } = request;
+
+    let base = BaseTx::build(&template).map_err(MineError::Template)?;
+    if !strategy.supports(base.tx_type()) {
+        return Err(MineError::UnsupportedStrategy { strategy, tx_type: base.tx_type() });
+    }
+
+    let reporter =
+        on_progress.map(|callback| spawn_callback(progress.clone(), progress_interval, callback));
+    let mut guard = CancelOnDrop { progress: Some(progress.clone()), reporter };
+
+    let mined = base.mine(strategy, target, signer, options, progress).await;
+
+    guard.progress = None;
+    mined
+}
+
+/// The tx to mine; a blob tx's sidecar stays aside until the hash is found, it isn't hashed.
+pub(crate) enum BaseTx {
+    Eip1559(TxEip1559),
+    Eip4844(TxEip4844, BlobTransactionSidecar),
+    Eip7702(TxEip7702),
+}
+
+impl BaseTx {
+    pub(crate) fn build(template: &TxTemplate) -> Result<Self> {
+        match (&template.blob_file, &template.authorization_list) {
+            (None, None) => Ok(Self::Eip1559(template.build()?)),
+            (Some(path), None) => {
+                let sidecar = blob::sidecar_from_file(path)?;
+                let tx = template.build_eip4844(blob::versioned_hashes(&sidecar))?;
+                Ok(Self::Eip4844(tx, sidecar))
+            }
+            (None, Some(_)) => Ok(Self::Eip7702(template.build_eip7702()?)),
+            (Some(_), Some(_)) => bail!("a tx can't carry both blobs and authorizations"),
+        }
+    }
+
+    pub(crate) fn tx_type(&self) -> TxType {
+        match self {
+            Self::Eip1559(_) => TxType::Eip1559,
+            Self::Eip4844(..) => TxType::Eip4844,
+            Self::Eip7702(_) => TxType::Eip7702,
+        }
+    }
+
+    pub(crate) fn chain_id(&self) -> u64 {
+        match self {
+            Self::Eip1559(tx) => tx.chain_id,
+            Self::Eip4844(tx, _) => tx.chain_id,
+            Self::Eip7702(tx) => tx.chain_id,
+        }
+    }
+
+    pub(crate) fn max_cost(&self) -> U256 {
+        match self {
+            Self::Eip1559(tx) => tx.max_cost(),
+            Self::Eip4844(tx, _) => tx.max_cost(),
+            Self::Eip7702(tx) => tx.max_cost(),
+        }
+    }
+
+    pub(crate) async fn mine(
+        self,
+        strategy: Strategy,
+        target: Target,
+        signer: MinerSigner,
+        options: Options,
+        progress: Arc<Progress>,
+    ) -> Result<MinedTx, MineError> {
+        match self {
+            Self::Eip1559(tx) => {
+                let mined = run_engine(tx, strategy, target, &signer, options, &progress).await?;
+                finish(&mined, mined.envelope(), &signer, strategy)
+            }
+            Self::Eip4844(tx, sidecar) => {
+                let mined = run_engine(tx, strategy, target, &signer, options, &progress).await?;
+                let envelope =
+                    blob::envelope(mined.tx.clone(), sidecar).map_err(MineError::Template)?;
+                finish(&mined, envelope, &signer, strategy)
+            }
+            Self::Eip7702(tx) => {
+                let mined = run_engine(tx, strategy, target, &signer, options, &progress).await?;
+                finish(&mined, mined.envelope(), &signer, strategy)
+            }
+        }
+    }
+}
+
+fn finish<T: MinableTx>(
+    mined: &Mined<T>,
+    envelope: TxEnvelope,
+    signer: &MinerSigner,
+    strategy: Strategy,
+) -> Result<MinedTx, MineError> {
+    let tx = mined.tx.tx();
+    let parameter = tx
+        .parameter(strategy)
+        .ok_or(MineError::UnsupportedStrategy { strategy, tx_type: T::TX_TYPE })?;
+
+    Ok(MinedTx {
+        envelope,
+        hash: mined.hash(),
+        from: signer.address(),
+        attempts: mined.attempts,
+        parameter,
+        cost: tx.max_cost(),
+    })
+}
+
+/// `engine::mine`, with its errors sorted into `MineError`.
+async fn run_engine<T: MinableTx>(
+    base: T,
+    strategy: Strategy,
+    target: Target,
+    signer: &MinerSigner,
+    options: Options,
+    progress: &Arc<Progress>,
+) -> Result<Mined<T>, MineError> {
+    let start = progress.attempts();
+    let mined = engine::mine(&base, strategy, &target, signer, &options, progress).await;
+
+    mined.map_err(|err| {
+        if let Some(cap) = err.downcast_ref::<CostCapExceeded>() {
+            MineError::CostCap { cap: cap.cap, cost: cap.cost }
+        } else if let Some(cap) = err.downcast_ref::<PriorityFeeCapReached>() {
+            MineError::PriorityFeeCap { max_fee_per_gas: cap.max_fee_per_gas }
+        } else if progress.is_cancelled() {
+            MineError::Cancelled { attempts: progress.attempts() - start }
+        } else {
+            MineError::Signer(err)
+        }
+    })
+}
+
+fn spawn_callback(
+    progress: Arc<Progress>,
+    interval: Duration,
+    mut callback: ProgressCallback,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let (mut last, mut since) = (progress.attempts(), Instant::now());
+        loop {
+            tokio::time::sleep(interval).await;
+            let attempts = progress.attempts();
+            let per_sec = (attempts - last) as f64 / since.elapsed().as_secs_f64();
+            callback(ProgressReport { attempts, per_sec });
+            (last, since) = (attempts, Instant::now());
+        }
+    })
+}
+
+/// Stops the progress reporter once `mine_tx` is done, and the search threads too if its future
+/// is dropped before they finish.
+struct CancelOnDrop {
+    progress: Option<Arc<Progress>>,
+    reporter: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if let Some(progress) = &self.progress {
+            progress.cancel();
+        }
+        if let Some(reporter) = &self.reporter {
+            reporter.abort();
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    consensus::{TxEip1559, TxEip4844, TxEip7702},
+    primitives::{Address, Bytes, TxKind, B256, U256},
+    signers::local::PrivateKeySigner,
+    transports::http::reqwest::Url,
+};
+use clap::{Args, ValueEnum};
+use eyre::{bail, Result};
+use serde::Serialize;
+
+use crate::{
+    delegation,
+    engine::{self, Options, Progress},
+    tx::{MinableTx, TxType},
+    ExternalSigner, MinerSigner, Strategy, Target,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignerBackend {
+    /// Random in-process key; keystore and mnemonic signers sign the same way.
+    Local,
+    /// `--external-signer`, one round trip per attempt.
+    External,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct BenchArgs {
+    #[arg(long, value_delimiter = ',', default_value = "fee,priority,gas,data")]
+    pub strategies: Vec<Strategy>,
+    #[arg(long, value_delimiter = ',', default_value = "eip1559")]
+    pub tx_types: Vec<TxType>,
+    #[arg(long, value_delimiter = ',', default_value = "local")]
+    pub signers: Vec<SignerBackend>,
+    /// Thread counts to compare; defaults to 1 and all cores.
+    #[arg(long, value_delimiter = ',')]
+    pub threads: Vec<usize>,
+    /// Seconds to run before sampling.
+    #[arg(long, default_value_t = 1.0)]
+    pub warmup: f64,
+    #[arg(long, default_value_t = 5)]
+    pub samples: usize,
+    /// Seconds per sample.
+    #[arg(long, default_value_t = 1.0)]
+    pub sample_secs: f64,
+    /// Print JSON instead of a table.
+    #[arg(long)]
+    pub json: bool,
+    /// Needed for the `external` signer backend.
+    #[arg(long, requires = "from")]
+    pub external_signer: Option<Url>,
+    #[arg(long, requires = "external_signer")]
+    pub from: Option<Address>,
+}
+
+/// Attempts/sec of one configuration, over `samples` samples.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchResult {
+    pub strategy: Strategy,
+    pub tx_type: TxType,
+    pub signer: SignerBackend,
+    pub threads: usize,
+    pub samples: usize,
+    pub mean: f64,
+    pub stddev: f64,
+    pub min: f64,
+    pub median: f64,
+    pub max: f64,
+}
+
+impl BenchResult {
+    /// Statistics over one rate per sample.
+    pub fn new(
+        strategy: Strategy,
+        tx_type: TxType,
+        signer: SignerBackend,
+        threads: usize,
+        mut rates: Vec<f64>,
+    ) -> Self {
+        rates.sort_by(|a, b| a.total_cmp(b));
+
+        let n = rates.len().max(1) as f64;
+        let mean = rates.iter().sum::<f64>() / n;
+        let variance = rates.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n;
+        let median = match rates.len() {
+            0 => 0.0,
+            len if len % 2 == 0 => (rates[len / 2 - 1] + rates[len / 2]) / 2.0,
+            len => rates[len / 2],
+        };
+
+        Self {
+            strategy,
+            tx_type,
+            signer,
+            threads,
+            samples: rates.len(),
+            mean,
+            stddev: variance.sqrt(),
+            min: rates.first().copied().unwrap_or_default(),
+            median,
+            max: rates.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Attempts/sec of a sample that made `attempts` attempts in `elapsed`.
+pub fn rate(attempts: u64, elapsed: Duration) -> f64 {
+    attempts as f64 / elapsed.as_secs_f64()
+}
+
+/// The tx every configuration starts from; shaped like the `setName("poop")` call.
+fn bench_tx() -> TxEip1559 {
+    TxEip1559 {
+        chain_id: 1,
+        nonce: 0,
+        gas_limit: 100_000,
+        max_fee_per_gas: 20_000_000_000,
+        max_priority_fee_per_gas: 1_000_000_000,
+        to: TxKind::Call(Address::repeat_byte(0x11)),
+        value: U256::ZERO,
+        access_list: Default::default(),
+        input: Bytes::from(vec![0xab; 100]),
+    }
+}
+
+/// The same call carrying one blob; only the versioned hash is hashed, so no sidecar needed.
+fn bench_blob_tx() -> TxEip4844 {
+    let tx = bench_tx();
+    let mut blob_hash = B256::repeat_byte(0x22);
+    blob_hash[0] = 0x01;
+
+    TxEip4844 {
+        chain_id: tx.chain_id,
+        nonce: tx.nonce,
+        gas_limit: tx.gas_limit,
+        max_fee_per_gas: tx.max_fee_per_gas,
+        max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+        to: Address::repeat_byte(0x11),
+        value: tx.value,
+        access_list: tx.access_list,
+        blob_versioned_hashes: vec![blob_hash],
+        max_fee_per_blob_gas: 1_000_000_000,
+        input: tx.input,
+    }
+}
+
+/// The same call installing one delegation; the authorization is only hashed, never checked.
+fn bench_set_code_tx() -> Result<TxEip7702> {
+    let tx = bench_tx();
+    let authority = PrivateKeySigner::random();
+    let authorization =
+        delegation::sign_authorization(&authority, tx.chain_id, Address::repeat_byte(0x33), 0)?;
+
+    Ok(TxEip7702 {
+        chain_id: tx.chain_id,
+        nonce: tx.nonce,
+        gas_limit: tx.gas_limit,
+        max_fee_per_gas: tx.max_fee_per_gas,
+        max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+        to: Address::repeat_byte(0x11),
+        value: tx.value,
+        access_list: tx.access_list,
+        authorization_list: vec![authorization],
+        input: tx.input,
+    })
+}
+
+/// Runs every combination without a node and returns attempts/sec for each.
+pub async fn run(args: &BenchArgs) -> Result<Vec<BenchResult>> {
+    let threads = if args.threads.is_empty() {
+        let all = Options::default().threads;
+        if all > 1 {
+            vec![1, all]
+        } else {
+            vec![1]
+        }
+    } else {
+        args.threads.clone()
+    };
+
+    // all 64 nibbles have to match, so no run ends on its own
+    let target: Target = "f".repeat(64).parse()?;
+    let local = MinerSigner::Local(PrivateKeySigner::random());
+
+    let mut results = Vec::new();
+    for &backend in &args.signers {
+        let signer = match backend {
+            SignerBackend::Local => local.clone(),
+            SignerBackend::External => match (&args.external_signer, args.from) {
+                (Some(url), Some(from)) => {
+                    MinerSigner::External(ExternalSigner::new(url.clone(), from))
+                }
+                _ => bail!("the external signer backend needs --external-signer and --from"),
+            },
+        };
+
+        for &tx_type in &args.tx_types {
+            for &strategy in args.strategies.iter().filter(|s| s.supports(tx_type)) {
+                for &threads in &threads {
+                    let rates = sample(args, &signer, tx_type, strategy, &target, threads).await?;
+                    results.push(BenchResult::new(strategy, tx_type, backend, threads, rates));
+                }
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+async fn sample(
+    args: &BenchArgs,
+    signer: &MinerSigner,
+    tx_type: TxType,
+    strategy: Strategy,
+    target: &Target,
+    threads: usize,
+) -> Result<Vec<f64>> {
+    let progress = Arc::new(Progress::default());
+    let options = Options { threads, max_cost: None };
+
+    let run = {
+        let (signer, target, progress) = (signer.clone(), target.clone(), progress.clone());
+        tokio::spawn(async move {
+            match tx_type {
+                TxType::Eip1559 => {
+                    mine(bench_tx(), strategy, &target, &signer, &options, &progress).await
+                }
+                TxType::Eip4844 => {
+                    mine(bench_blob_tx(), strategy, &target, &signer, &options, &progress).await
+                }
+                TxType::Eip7702 => {
+                    let tx = bench_set_code_tx()?;
+                    mine(tx, strategy, &target, &signer, &options, &progress).await
+                }
+            }
+        })
+    };
+
+    tokio::time::sleep(Duration::from_secs_f64(args.warmup)).await;
+
+    let mut rates = Vec::with_capacity(args.samples);
+    for _ in 0..args.samples {
+        let before = progress.attempts();
+        let started = Instant::now();
+        tokio::time::sleep(Duration::from_secs_f64(args.sample_secs)).await;
+        rates.push(rate(progress.attempts() - before, started.elapsed()));
+    }
+
+    // the target can't be met, so a run that ended on its own failed
+    if run.is_finished() {
+        run.await??;
+        return Ok(rates);
+    }
+
+    progress.cancel();
+    let _ = run.await?;
+
+    Ok(rates)
+}
+
+/// `engine::mine` with the result dropped, so every tx type's run has the same type.
+async fn mine<T: MinableTx>(
+    base: T,
+    strategy: Strategy,
+    target: &Target,
+    signer: &MinerSigner,
+    options: &Options,
+    progress: &Arc<Progress>,
+) -> Result<()> {
+    engine::mine(&base, strategy, target, signer, options, progress).await.map(|_| ())
+}
+
+pub fn print_table(results: &[BenchResult]) {
+    println!(
+        "{:<9} {:<8} {:<8} {:>7} {:>12} {:>10} {:>12} {:>12} {:>12}",
+        "strategy",
+        "tx type",
+        "signer",
+        "threads",
+        "mean/s",
+        "stddev",
+        "min/s",
+        "median/s",
+        "max/s"
+    );
+    for r in results {
+        println!(
+            "{:<9} {:<8} {:<8} {:>7} {:>12.0} {:>10.0} {:>12.0} {:>12.0} {:>12.0}",
+            format!("{:?}", r.strategy).to_lowercase(),
+            format!("{:?}", r.tx_type).to_lowercase(),
+            format!("{:?}", r.signer).to_lowercase(),
+            r.threads,
+            r.mean,
+            r.stddev,
+            r.min,
+            r.median,
+            r.max
+        );
+    }
+}
@@ -0,0 +1,40 @@
+//! EIP-4844 sidecars. The tx hash only covers the versioned hashes, so the engine mines the
+//! bare tx and the sidecar is attached afterwards, for the network wrapper nodes expect.
+
+use std::path::Path;
+
+use alloy::{
+    consensus::{
+        BlobTransactionSidecar, SidecarBuilder, Signed, SimpleCoder, TxEip4844,
+        TxEip4844WithSidecar, TxEnvelope,
+    },
+    primitives::B256,
+};
+use eyre::{ensure, Result, WrapErr};
+
+/// Packs the file into as many blobs as it needs and computes their KZG commitments and proofs.
+pub fn sidecar_from_file(path: &Path) -> Result<BlobTransactionSidecar> {
+    let data =
+        std::fs::read(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    ensure!(!data.is_empty(), "blob file {} is empty", path.display());
+
+    SidecarBuilder::<SimpleCoder>::from_slice(&data)
+        .build()
+        .wrap_err_with(|| format!("failed to build a blob sidecar from {}", path.display()))
+}
+
+pub fn versioned_hashes(sidecar: &BlobTransactionSidecar) -> Vec<B256> {
+    sidecar.versioned_hashes().collect()
+}
+
+/// The mined tx in the network wrapper, ready for `eth_sendRawTransaction`.
+pub fn envelope(signed: Signed<TxEip4844>, sidecar: BlobTransactionSidecar) -> Result<TxEnvelope> {
+    let (tx, signature, hash) = signed.into_parts();
+    ensure!(
+        tx.blob_versioned_hashes == versioned_hashes(&sidecar),
+        "sidecar doesn't match the tx's blob versioned hashes"
+    );
+
+    let tx = TxEip4844WithSidecar::from_tx_and_sidecar(tx, sidecar);
+    Ok(TxEnvelope::Eip4844(Signed::new_unchecked(tx.into(), signature, hash)))
+}
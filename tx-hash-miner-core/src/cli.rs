@@ -0,0 +1,410 @@
+use std::{path::PathBuf, sync::Arc};
+
+use alloy::{
+    primitives::{Address, Bytes, B256, U256},
+    providers::{Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+    transports::http::reqwest::Url,
+};
+use clap::{Args, Parser, Subcommand};
+use eyre::{bail, ensure, eyre, Result, WrapErr};
+
+use crate::{
+    api::BaseTx,
+    bench::{self, BenchArgs},
+    broadcast, delegation,
+    engine::{self, Options, Progress},
+    guard::{self, GuardArgs},
+    relay::{self, Relay, RelayArgs},
+    replace, MinerSigner, SignedTxFile, SignerArgs, Strategy, Target, TxTemplate, VerifyArgs,
+};
+
+#[derive(Parser, Debug)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Fetch the nonce and fees from the node, then mine.
+    Mine(MineArgs),
+    /// Mine without a node; every tx field has to come from flags or a template.
+    Offline(OfflineArgs),
+    /// Broadcast tx files written by `offline`; with `--relay` they go out as one bundle.
+    Broadcast(BroadcastArgs),
+    /// Measure attempts/sec per strategy, tx type, signer and thread count, without a node.
+    Bench(BenchArgs),
+    /// Decode a raw signed tx and check its sender, hash and target without trusting the miner.
+    Verify(VerifyArgs),
+    /// Mine a replacement for a stuck tx: same nonce, fees bumped enough for nodes to take it.
+    Replace(ReplaceArgs),
+}
+
+/// Tx fields, layered over the miner defaults.
+#[derive(Args, Debug, Clone)]
+pub struct TxArgs {
+    /// JSON tx template; flags override its fields.
+    #[arg(long)]
+    pub template: Option<PathBuf>,
+    #[arg(long)]
+    pub chain_id: Option<u64>,
+    #[arg(long)]
+    pub nonce: Option<u64>,
+    #[arg(long)]
+    pub to: Option<Address>,
+    #[arg(long)]
+    pub value: Option<U256>,
+    #[arg(long)]
+    pub gas_limit: Option<u64>,
+    #[arg(long)]
+    pub max_fee_per_gas: Option<u128>,
+    #[arg(long)]
+    pub max_priority_fee_per_gas: Option<u128>,
+    #[arg(long)]
+    pub input: Option<Bytes>,
+    #[arg(long)]
+    pub max_fee_per_blob_gas: Option<u128>,
+    /// Send this file's contents in blobs, as an EIP-4844 tx.
+    #[arg(long)]
+    pub blob_file: Option<PathBuf>,
+    /// Delegate the authority's account to this contract, as an EIP-7702 set-code tx.
+    #[arg(long, requires = "authority_key")]
+    pub delegate: Option<Address>,
+    /// Key of the account to delegate; may be the sender's own.
+    #[arg(long, requires = "delegate")]
+    pub authority_key: Option<String>,
+    /// Nonce the authorization is signed over; fetched from the node when mining online.
+    #[arg(long, requires = "delegate")]
+    pub authority_nonce: Option<u64>,
+    /// Hex prefix the tx hash has to start with.
+    #[arg(long, default_value = "dead")]
+    pub target: Target,
+    /// Signing threads; defaults to all cores.
+    #[arg(long)]
+    pub threads: Option<usize>,
+}
+
+impl TxArgs {
+    pub fn template(&self, defaults: TxTemplate) -> Result<TxTemplate> {
+        let mut template = defaults;
+        if let Some(path) = &self.template {
+            template = template.merge(TxTemplate::from_file(path)?);
+        }
+
+        Ok(template.merge(TxTemplate {
+            chain_id: self.chain_id,
+            nonce: self.nonce,
+            to: self.to,
+            value: self.value,
+            gas_limit: self.gas_limit,
+            max_fee_per_gas: self.max_fee_per_gas,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+            input: self.input.clone(),
+            max_fee_per_blob_gas: self.max_fee_per_blob_gas,
+            blob_file: self.blob_file.clone(),
+            authorization_list: None,
+        }))
+    }
+
+    pub fn authority(&self) -> Result<Option<PrivateKeySigner>> {
+        match &self.authority_key {
+            Some(key) => Ok(Some(key.trim().parse().wrap_err("authority key is not valid hex")?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Signs `--delegate` with `authority` and adds it to the template's authorization list.
+    ///
+    /// `account_nonce` is the authority's nonce on chain, when there is a node to ask.
+    pub fn authorize(
+        &self,
+        template: &mut TxTemplate,
+        authority: &PrivateKeySigner,
+        sender: Address,
+        account_nonce: Option<u64>,
+    ) -> Result<()> {
+        let delegate = self.delegate.ok_or_else(|| eyre!("--authority-key needs --delegate"))?;
+        let chain_id =
+            template.chain_id.ok_or_else(|| eyre!("tx template is missing `chainId`"))?;
+        let tx_nonce = template.nonce.ok_or_else(|| eyre!("tx template is missing `nonce`"))?;
+
+        let nonce = match self.authority_nonce {
+            Some(nonce) => nonce,
+            None => {
+                match delegation::authority_nonce(
+                    authority.address(),
+                    sender,
+                    tx_nonce,
+                    account_nonce,
+                ) {
+                    Some(nonce) => nonce,
+                    None => bail!("offline mining needs --authority-nonce"),
+                }
+            }
+        };
+
+        let authorization = delegation::sign_authorization(authority, chain_id, delegate, nonce)?;
+        template.authorization_list.get_or_insert_with(Vec::new).push(authorization);
+        Ok(())
+    }
+
+    pub fn options(&self, guard: &GuardArgs) -> Options {
+        guard.options(self.threads)
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct MineArgs {
+    #[arg(long)]
+    pub rpc_url: Option<Url>,
+    #[command(flatten)]
+    pub tx: TxArgs,
+    #[command(flatten)]
+    pub signer: SignerArgs,
+    #[command(flatten)]
+    pub guard: GuardArgs,
+    #[command(flatten)]
+    pub relay: RelayArgs,
+    /// Broadcast the mined tx, or bundle it with `--relay`, and wait for it to be included.
+    #[arg(long)]
+    pub send: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct OfflineArgs {
+    #[command(flatten)]
+    pub tx: TxArgs,
+    #[command(flatten)]
+    pub signer: SignerArgs,
+    #[command(flatten)]
+    pub guard: GuardArgs,
+    /// Where to write the signed tx.
+    #[arg(long, default_value = "mined-tx.json")]
+    pub out: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct BroadcastArgs {
+    #[arg(long)]
+    pub rpc_url: Option<Url>,
+    /// Tx file written by `offline`; repeat to send several, in order.
+    #[arg(long, default_value = "mined-tx.json")]
+    pub file: Vec<PathBuf>,
+    #[command(flatten)]
+    pub guard: GuardArgs,
+    #[command(flatten)]
+    pub relay: RelayArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct ReplaceArgs {
+    #[arg(long)]
+    pub rpc_url: Option<Url>,
+    /// Hash of the pending tx to replace.
+    #[arg(long)]
+    pub hash: B256,
+    /// Replace it with a 0-value transfer to yourself instead of speeding up the same call.
+    #[arg(long)]
+    pub cancel: bool,
+    /// Hex prefix the replacement's hash has to start with.
+    #[arg(long, default_value = "dead")]
+    pub target: Target,
+    /// Signing threads; defaults to all cores.
+    #[arg(long)]
+    pub threads: Option<usize>,
+    #[command(flatten)]
+    pub signer: SignerArgs,
+    #[command(flatten)]
+    pub guard: GuardArgs,
+    /// Broadcast the replacement and wait for it to be included.
+    #[arg(long)]
+    pub send: bool,
+    /// Where to write the signed replacement when not sending it.
+    #[arg(long, default_value = "mined-tx.json")]
+    pub out: PathBuf,
+}
+
+/// What a miner binary fixes: its strategy and the values it used to hardcode.
+pub struct Miner {
+    pub strategy: Strategy,
+    pub rpc_url: &'static str,
+    pub template: TxTemplate,
+}
+
+impl Miner {
+    pub async fn run(self) -> Result<()> {
+        match Cli::parse().command {
+            Command::Mine(args) => self.mine(args).await,
+            Command::Offline(args) => self.offline(args).await,
+            Command::Broadcast(args) => {
+                let files = args.file.iter().map(|path| SignedTxFile::read(path));
+                let files = files.collect::<Result<Vec<_>>>()?;
+                let relay = args.relay.relay()?;
+                let rpc_url = self.rpc_url(args.rpc_url)?;
+                send(&files, rpc_url, &args.guard, relay.as_ref(), args.relay.relay_blocks).await
+            }
+            Command::Bench(args) => {
+                let results = bench::run(&args).await?;
+                if args.json {
+                    println!("{}", serde_json::to_string_pretty(&results)?);
+                } else {
+                    bench::print_table(&results);
+                }
+                Ok(())
+            }
+            Command::Verify(args) => args.run(),
+            Command::Replace(args) => self.replace(args).await,
+        }
+    }
+
+    async fn mine(&self, args: MineArgs) -> Result<()> {
+        let signer = args.signer.signer()?;
+        let rpc_url = self.rpc_url(args.rpc_url)?;
+        let provider = ProviderBuilder::new().on_http(rpc_url.clone());
+
+        let mut template = args.tx.template(self.template.clone())?;
+        template.fill(&provider, signer.address()).await?;
+        if let Some(authority) = args.tx.authority()? {
+            let account_nonce = provider.get_transaction_count(authority.address()).await?;
+            args.tx.authorize(&mut template, &authority, signer.address(), Some(account_nonce))?;
+        }
+
+        let base = BaseTx::build(&template)?;
+        guard::check_chain_id(&provider, base.chain_id()).await?;
+        // a bad relay key should fail before mining too
+        let relay = args.relay.relay()?;
+        if args.send {
+            // fail before mining rather than after
+            args.guard.check_broadcast(base.chain_id(), base.max_cost())?;
+        }
+
+        let options = args.tx.options(&args.guard);
+        let file = self.mine_file(base, &args.tx.target, &signer, &options).await?;
+
+        if args.send {
+            send(&[file], rpc_url, &args.guard, relay.as_ref(), args.relay.relay_blocks).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn offline(&self, args: OfflineArgs) -> Result<()> {
+        let signer = args.signer.signer()?;
+
+        let mut template = args.tx.template(self.template.clone())?;
+        if let Some(authority) = args.tx.authority()? {
+            args.tx.authorize(&mut template, &authority, signer.address(), None)?;
+        }
+        let options = args.tx.options(&args.guard);
+        let file =
+            self.mine_file(BaseTx::build(&template)?, &args.tx.target, &signer, &options).await?;
+
+        file.write(&args.out)?;
+        println!("Wrote signed transaction to {}", args.out.display());
+
+        Ok(())
+    }
+
+    async fn replace(&self, args: ReplaceArgs) -> Result<()> {
+        let signer = args.signer.signer()?;
+        let rpc_url = self.rpc_url(args.rpc_url)?;
+        let provider = ProviderBuilder::new().on_http(rpc_url.clone());
+
+        let pending = replace::fetch_pending(&provider, args.hash).await?;
+        ensure!(
+            pending.from == signer.address(),
+            "tx {} was sent by {}, not by the signer {}",
+            pending.hash,
+            pending.from,
+            signer.address()
+        );
+        if args.cancel && self.strategy == Strategy::Data {
+            bail!("a cancel has no calldata to mine; use a fee, priority or gas miner");
+        }
+
+        let fees = provider.estimate_eip1559_fees(None).await?;
+        let current = (fees.max_fee_per_gas, fees.max_priority_fee_per_gas);
+        let base = BaseTx::build(&pending.replacement(args.cancel, Some(current)))?;
+        guard::check_chain_id(&provider, base.chain_id()).await?;
+        if args.send {
+            args.guard.check_broadcast(base.chain_id(), base.max_cost())?;
+        }
+
+        let (max_fee, priority_fee) = pending.min_fees();
+        println!(
+            "Replacing {} (nonce {}): needs max fee per gas >= {}, priority fee per gas >= {}",
+            pending.hash, pending.nonce, max_fee, priority_fee
+        );
+
+        let options = args.guard.options(args.threads);
+        let file = self.mine_file(base, &args.target, &signer, &options).await?;
+
+        if args.send {
+            send(&[file], rpc_url, &args.guard, None, 0).await
+        } else {
+            file.write(&args.out)?;
+            println!("Wrote signed transaction to {}", args.out.display());
+            Ok(())
+        }
+    }
+
+    /// Mines `base` and wraps the result for broadcasting.
+    async fn mine_file(
+        &self,
+        base: BaseTx,
+        target: &Target,
+        signer: &MinerSigner,
+        options: &Options,
+    ) -> Result<SignedTxFile> {
+        let progress = Arc::new(Progress::default());
+        let reporter = engine::spawn_reporter(progress.clone());
+        let mined = base
+            .mine(self.strategy, target.clone(), signer.clone(), options.clone(), progress)
+            .await;
+        reporter.abort();
+        let mined = mined?;
+
+        println!("Found a transaction hash starting with prefix: {:?}", mined.hash);
+        println!("{}", mined.parameter);
+        println!("attempts: {}", mined.attempts);
+
+        Ok(SignedTxFile::from_envelope(
+            &mined.envelope,
+            mined.attempts,
+            mined.from,
+            self.strategy,
+            target,
+        ))
+    }
+
+    fn rpc_url(&self, url: Option<Url>) -> Result<Url> {
+        match url {
+            Some(url) => Ok(url),
+            None => Ok(self.rpc_url.parse()?),
+        }
+    }
+}
+
+/// Broadcasts `files` one after another, or submits them as a single bundle to the relay.
+async fn send(
+    files: &[SignedTxFile],
+    rpc_url: Url,
+    guard: &GuardArgs,
+    relay: Option<&Relay>,
+    blocks: u64,
+) -> Result<()> {
+    if let Some(relay) = relay {
+        let inclusion = relay::submit(files, rpc_url, relay, blocks, guard).await?;
+        for hash in inclusion.hashes {
+            println!("Included transaction: {} in block {}", hash, inclusion.block_number);
+        }
+        return Ok(());
+    }
+
+    for file in files {
+        let hash = broadcast(file, rpc_url.clone(), guard).await?;
+        println!("Sent transaction: {}", hash);
+    }
+    Ok(())
+}
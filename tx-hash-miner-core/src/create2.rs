@@ -0,0 +1,117 @@
+//! Searching for a CREATE2 salt whose contract address matches a prefix.
+//!
+//! `address = keccak256(0xff ++ deployer ++ salt ++ keccak256(init_code))[12..]`; only the salt
+//! changes between attempts, so the rest of the preimage is laid out once per thread.
+
+use std::path::Path;
+
+use alloy::primitives::{address, keccak256, Address, Bytes, B256};
+use eyre::{bail, ensure, Result, WrapErr};
+
+use crate::{
+    engine::{self, Progress},
+    keccak::{self, LANES},
+    Target,
+};
+
+/// The deterministic deployment proxy used by forge for `new C{salt: s}()`.
+pub const CREATE2_DEPLOYER: Address = address!("4e59b44847b379578588920cA78FbF26c0B4956C");
+
+/// Leading salt bytes a caller can fix; the last 8 are the mined counter.
+pub const MAX_SALT_PREFIX: usize = 24;
+
+/// `prefix`, zero padding, then `step` in the last 8 bytes.
+pub fn salt(prefix: &[u8], step: u64) -> B256 {
+    let mut salt = B256::ZERO;
+    salt[..prefix.len()].copy_from_slice(prefix);
+    salt[MAX_SALT_PREFIX..].copy_from_slice(&step.to_be_bytes());
+    salt
+}
+
+/// The init code in a file: hex bytecode, or a forge artifact JSON whose `bytecode.object` is
+/// used.
+pub fn read_init_code(path: &Path) -> Result<Bytes> {
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    let contents = contents.trim();
+
+    let hex = if contents.starts_with('{') {
+        let artifact: serde_json::Value = serde_json::from_str(contents)?;
+        match artifact["bytecode"]["object"].as_str() {
+            Some(code) => code.to_string(),
+            None => bail!("{} has no bytecode.object", path.display()),
+        }
+    } else {
+        contents.to_string()
+    };
+
+    let code: Bytes = hex.parse().wrap_err("init code is not hex")?;
+    ensure!(!code.is_empty(), "init code is empty");
+    Ok(code)
+}
+
+/// What CREATE2 hashes in place of the init code itself.
+pub fn init_code_hash(init_code: &[u8]) -> B256 {
+    keccak256(init_code)
+}
+
+/// CREATE2 addresses of one deployer and init code, `LANES` salts at a time.
+pub struct Create2Hasher {
+    preimages: [[u8; 85]; LANES],
+}
+
+impl Create2Hasher {
+    pub fn new(deployer: Address, init_code_hash: B256) -> Self {
+        let mut preimage = [0u8; 85];
+        preimage[0] = 0xff;
+        preimage[1..21].copy_from_slice(deployer.as_slice());
+        preimage[53..].copy_from_slice(init_code_hash.as_slice());
+        Self { preimages: [preimage; LANES] }
+    }
+
+    pub fn addresses(&mut self, salts: &[B256; LANES]) -> [Address; LANES] {
+        for (preimage, salt) in self.preimages.iter_mut().zip(salts) {
+            preimage[21..53].copy_from_slice(salt.as_slice());
+        }
+        let hashes = keccak::keccak256_x4(std::array::from_fn(|i| &self.preimages[i][..]));
+        hashes.map(|hash| Address::from_slice(&hash[12..]))
+    }
+}
+
+/// Mines salts starting with `salt_prefix` on `threads` threads until the address `deployer`
+/// creates with one of them starts with `target`.
+pub fn search(
+    deployer: Address,
+    init_code_hash: B256,
+    salt_prefix: &[u8],
+    target: &Target,
+    threads: usize,
+    progress: &Progress,
+) -> Result<((B256, Address), u64)> {
+    ensure!(
+        salt_prefix.len() <= MAX_SALT_PREFIX,
+        "--salt-prefix is {} bytes, at most {MAX_SALT_PREFIX} fit",
+        salt_prefix.len()
+    );
+    ensure!(target.len() <= 40, "target `{target}` is longer than an address");
+
+    engine::search(threads, progress, |lane| {
+        let mut hasher = Create2Hasher::new(deployer, init_code_hash);
+        let mut step = lane.offset;
+        while lane.running() {
+            let salts: [B256; LANES] =
+                std::array::from_fn(|i| salt(salt_prefix, step + i as u64 * lane.stride));
+            step += LANES as u64 * lane.stride;
+
+            let addresses = hasher.addresses(&salts);
+            lane.attempted(LANES as u64);
+
+            for (salt, address) in salts.into_iter().zip(addresses) {
+                if target.matches(address) {
+                    return Ok(Some((salt, address)));
+                }
+            }
+        }
+        Ok(None)
+    })
+}
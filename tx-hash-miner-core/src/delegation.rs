@@ -0,0 +1,44 @@
+//! EIP-7702 authorizations. Each one is signed by its authority over the chain id, the delegate
+//! and the authority's nonce only, so the miner can change every other field of the outer tx
+//! without invalidating them.
+
+use alloy::{
+    eips::eip7702::{Authorization, SignedAuthorization},
+    primitives::{Address, U256},
+    signers::{local::PrivateKeySigner, SignerSync},
+};
+use eyre::{Result, WrapErr};
+
+/// Authorizes `delegate` as the code of `authority`'s account on `chain_id`.
+pub fn sign_authorization(
+    authority: &PrivateKeySigner,
+    chain_id: u64,
+    delegate: Address,
+    nonce: u64,
+) -> Result<SignedAuthorization> {
+    let authorization = Authorization { chain_id: U256::from(chain_id), address: delegate, nonce };
+    let signature = authority
+        .sign_hash_sync(&authorization.signature_hash())
+        .wrap_err("failed to sign the authorization")?;
+    Ok(authorization.into_signed(signature))
+}
+
+/// The nonce the authorization has to carry. The sender's nonce is bumped before the
+/// authorization list is processed, so a sender authorizing for itself signs one ahead.
+pub fn authority_nonce(
+    authority: Address,
+    sender: Address,
+    tx_nonce: u64,
+    account_nonce: Option<u64>,
+) -> Option<u64> {
+    if authority == sender {
+        Some(tx_nonce + 1)
+    } else {
+        account_nonce
+    }
+}
+
+/// Code of an account delegated to `delegate`: the `0xef0100` marker, then the address.
+pub fn delegation_code(delegate: Address) -> Vec<u8> {
+    [&[0xef, 0x01, 0x00][..], delegate.as_slice()].concat()
+}
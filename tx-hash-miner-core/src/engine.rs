@@ -0,0 +1,336 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use alloy::{
+    consensus::{Signed, TxEip1559, TxEnvelope},
+    primitives::{B256, U256},
+    signers::local::PrivateKeySigner,
+};
+use eyre::{ensure, eyre, Result};
+
+use crate::{
+    keccak,
+    signer::{sign_unhashed, ExternalSigner},
+    tx::MinableTx,
+    MinerSigner, Strategy, Target,
+};
+
+/// A signed tx whose hash meets the target.
+#[derive(Debug, Clone)]
+pub struct Mined<T = TxEip1559> {
+    pub tx: Signed<T>,
+    pub attempts: u64,
+}
+
+impl<T: MinableTx> Mined<T> {
+    pub fn hash(&self) -> B256 {
+        *self.tx.hash()
+    }
+
+    pub fn envelope(&self) -> TxEnvelope {
+        T::into_envelope(self.tx.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Signing threads for local signers; external signers always sign one attempt at a time.
+    pub threads: usize,
+    /// Stop with an error once an attempt could spend more than this many wei.
+    pub max_cost: Option<U256>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Self { threads, max_cost: None }
+    }
+}
+
+/// Attempt counter shared with whoever is watching the run; also the way to cancel it.
+#[derive(Debug, Default)]
+pub struct Progress {
+    attempts: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    pub fn attempts(&self) -> u64 {
+        self.attempts.load(Ordering::Relaxed)
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn attempt(&self) {
+        self.attempts.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Signs variations of `base` until the hash meets `target`.
+///
+/// Only needs the signer, so it works the same with or without a node. A local signer mines on
+/// tokio's blocking pool, so the runtime awaiting this keeps running.
+pub async fn mine<T: MinableTx>(
+    base: &T,
+    strategy: Strategy,
+    target: &Target,
+    signer: &MinerSigner,
+    options: &Options,
+    progress: &Arc<Progress>,
+) -> Result<Mined<T>> {
+    ensure!(
+        strategy.supports(T::TX_TYPE),
+        "the {strategy:?} strategy has nothing to mine in a {:?} tx",
+        T::TX_TYPE
+    );
+
+    match signer {
+        MinerSigner::Local(signer) => {
+            let (base, target, signer, options, lane_progress) =
+                (base.clone(), target.clone(), signer.clone(), options.clone(), progress.clone());
+            let run = tokio::task::spawn_blocking(move || {
+                mine_local(&base, strategy, &target, &signer, &options, &lane_progress)
+            });
+            match run.await {
+                Ok(mined) => mined,
+                Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+                // the runtime is shutting down
+                Err(_) => {
+                    progress.cancel();
+                    Err(eyre!("mining cancelled after {} attempts", progress.attempts()))
+                }
+            }
+        }
+        MinerSigner::External(signer) => {
+            mine_external(base, strategy, target, signer, options, progress).await
+        }
+    }
+}
+
+/// Mining stopped because the next attempt could spend more than `Options::max_cost`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostCapExceeded {
+    pub cap: U256,
+    pub cost: U256,
+}
+
+impl fmt::Display for CostCapExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ran past the --max-cost cap of {} wei ({} wei) without a match",
+            self.cap, self.cost
+        )
+    }
+}
+
+impl std::error::Error for CostCapExceeded {}
+
+/// Mining stopped because the priority fee reached the max fee per gas, which it can't pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityFeeCapReached {
+    pub max_fee_per_gas: u128,
+}
+
+impl fmt::Display for PriorityFeeCapReached {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "raised the priority fee to the max fee per gas of {} wei without a match",
+            self.max_fee_per_gas
+        )
+    }
+}
+
+impl std::error::Error for PriorityFeeCapReached {}
+
+fn check_cost<T: MinableTx>(tx: &T, max_cost: Option<U256>) -> Result<()> {
+    if let Some(cap) = max_cost {
+        let cost = tx.max_cost();
+        if cost > cap {
+            return Err(CostCapExceeded { cap, cost }.into());
+        }
+    }
+    Ok(())
+}
+
+/// One search thread's share of the steps: `offset, offset + stride, offset + 2 * stride, ...`.
+pub struct Lane<'a> {
+    pub offset: u64,
+    pub stride: u64,
+    done: &'a AtomicBool,
+    progress: &'a Progress,
+}
+
+impl Lane<'_> {
+    /// False once any lane found a match or the run was cancelled.
+    pub fn running(&self) -> bool {
+        !self.done.load(Ordering::Relaxed) && !self.progress.is_cancelled()
+    }
+
+    /// Counts `n` finished attempts.
+    pub fn attempted(&self, n: u64) {
+        self.progress.attempts.fetch_add(n, Ordering::Relaxed);
+    }
+}
+
+/// The parallel part of every miner: runs `work` on `threads` threads, each with its own
+/// `Lane`, until one of them returns a match.
+///
+/// `work` returns `Ok(None)` once its lane stops running. An error only ends its own lane, so
+/// the search fails only if no lane finds anything. Returns the match and the attempt count.
+pub fn search<T, F>(threads: usize, progress: &Progress, work: F) -> Result<(T, u64)>
+where
+    T: Send,
+    F: Fn(Lane<'_>) -> Result<Option<T>> + Sync,
+{
+    let threads = threads.max(1) as u64;
+    let start = progress.attempts();
+    let done = AtomicBool::new(false);
+    let found = Mutex::new(None);
+    let error = Mutex::new(None);
+
+    thread::scope(|scope| {
+        for offset in 0..threads {
+            let (work, done, found, error) = (&work, &done, &found, &error);
+            scope.spawn(move || {
+                let lane = Lane { offset, stride: threads, done, progress };
+                match work(lane) {
+                    Ok(Some(value)) => {
+                        done.store(true, Ordering::Relaxed);
+                        found.lock().unwrap().get_or_insert(value);
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        error.lock().unwrap().get_or_insert(err);
+                    }
+                }
+            });
+        }
+    });
+
+    let attempts = progress.attempts() - start;
+    match (found.into_inner().unwrap(), error.into_inner().unwrap()) {
+        (Some(value), _) => Ok((value, attempts)),
+        (None, Some(err)) => Err(err),
+        (None, None) => Err(eyre!("mining cancelled after {attempts} attempts")),
+    }
+}
+
+/// Prints attempts/sec to stderr every few seconds until aborted.
+pub fn spawn_reporter(progress: Arc<Progress>) -> tokio::task::JoinHandle<()> {
+    const INTERVAL: Duration = Duration::from_secs(5);
+
+    tokio::spawn(async move {
+        let mut last = progress.attempts();
+        loop {
+            tokio::time::sleep(INTERVAL).await;
+            let attempts = progress.attempts();
+            eprintln!("attempts: {} ({}/s)", attempts, (attempts - last) / INTERVAL.as_secs());
+            last = attempts;
+        }
+    })
+}
+
+/// Each thread signs `keccak::LANES` candidates, then hashes them together.
+fn mine_local<T: MinableTx>(
+    base: &T,
+    strategy: Strategy,
+    target: &Target,
+    signer: &PrivateKeySigner,
+    options: &Options,
+    progress: &Progress,
+) -> Result<Mined<T>> {
+    let (tx, attempts) = search(options.threads, progress, |lane| {
+        let mut tx = base.clone();
+        let mut step = lane.offset;
+        let mut batch = Vec::with_capacity(keccak::LANES);
+        let mut encoded = Vec::with_capacity(keccak::LANES);
+
+        while lane.running() {
+            batch.clear();
+            encoded.clear();
+
+            let mut failed = None;
+            while batch.len() < keccak::LANES {
+                let applied = strategy.apply(base, &mut tx, step);
+                step += lane.stride;
+
+                // fees and gas only grow with the step, so this lane is finished; other lanes
+                // may still be under the cap for a few more steps
+                let signature = match applied
+                    .and_then(|_| check_cost(&tx, options.max_cost))
+                    .and_then(|_| sign_unhashed(signer, &tx))
+                {
+                    Ok(signature) => signature,
+                    Err(err) => {
+                        failed = Some(err);
+                        break;
+                    }
+                };
+
+                let mut buf = Vec::new();
+                tx.encode_signed(&signature, &mut buf);
+                encoded.push(buf);
+                batch.push((tx.clone(), signature));
+            }
+
+            let hashes = keccak::keccak256_batch(&encoded);
+            lane.attempted(hashes.len() as u64);
+            for ((tx, signature), hash) in batch.drain(..).zip(hashes) {
+                if target.matches(hash) {
+                    return Ok(Some(Signed::new_unchecked(tx, signature, hash)));
+                }
+            }
+
+            if let Some(err) = failed {
+                return Err(err);
+            }
+        }
+
+        Ok(None)
+    })?;
+
+    Ok(Mined { tx, attempts })
+}
+
+async fn mine_external<T: MinableTx>(
+    base: &T,
+    strategy: Strategy,
+    target: &Target,
+    signer: &ExternalSigner,
+    options: &Options,
+    progress: &Progress,
+) -> Result<Mined<T>> {
+    let mut tx = base.clone();
+    let mut step = 0u64;
+
+    while !progress.is_cancelled() {
+        strategy.apply(base, &mut tx, step)?;
+        check_cost(&tx, options.max_cost)?;
+
+        let signed = signer.sign(&tx).await?;
+        progress.attempt();
+
+        if target.matches(signed.hash()) {
+            return Ok(Mined { tx: signed, attempts: step + 1 });
+        }
+
+        step += 1;
+    }
+
+    Err(eyre!("mining cancelled after {step} attempts"))
+}
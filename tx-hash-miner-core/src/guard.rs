@@ -0,0 +1,77 @@
+use alloy::{primitives::U256, providers::Provider, transports::Transport};
+use clap::Args;
+use eyre::{bail, ensure, Result};
+
+use crate::Options;
+
+/// Chains where a mistaken broadcast costs real money.
+pub const PRODUCTION_CHAINS: &[(u64, &str)] = &[
+    (1, "Ethereum mainnet"),
+    (10, "OP mainnet"),
+    (56, "BNB Smart Chain"),
+    (100, "Gnosis"),
+    (137, "Polygon"),
+    (324, "zkSync Era"),
+    (8453, "Base"),
+    (42161, "Arbitrum One"),
+    (43114, "Avalanche C-Chain"),
+    (59144, "Linea"),
+];
+
+pub fn production_chain(chain_id: u64) -> Option<&'static str> {
+    PRODUCTION_CHAINS.iter().find(|(id, _)| *id == chain_id).map(|(_, name)| *name)
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct GuardArgs {
+    /// Required to broadcast on a production chain such as mainnet.
+    #[arg(long)]
+    pub confirm_production: bool,
+    /// Refuse to mine or broadcast a tx that could spend more than this many wei.
+    #[arg(long)]
+    pub max_cost: Option<U256>,
+}
+
+impl GuardArgs {
+    pub fn check_cost(&self, cost: U256) -> Result<()> {
+        if let Some(cap) = self.max_cost {
+            ensure!(
+                cost <= cap,
+                "tx could spend {cost} wei, above the --max-cost cap of {cap} wei"
+            );
+        }
+        Ok(())
+    }
+
+    pub fn check_broadcast(&self, chain_id: u64, cost: U256) -> Result<()> {
+        if let Some(name) = production_chain(chain_id) {
+            ensure!(
+                self.confirm_production,
+                "refusing to broadcast on {name} (chain id {chain_id}) without --confirm-production"
+            );
+        }
+        self.check_cost(cost)
+    }
+
+    /// Mining options under this guard's cost cap, on `threads` threads or all cores.
+    pub fn options(&self, threads: Option<usize>) -> Options {
+        let mut options = Options { max_cost: self.max_cost, ..Default::default() };
+        if let Some(threads) = threads {
+            options.threads = threads;
+        }
+        options
+    }
+}
+
+/// Fails unless the node is on `chain_id`.
+pub async fn check_chain_id<P, T>(provider: &P, chain_id: u64) -> Result<()>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    let node_chain_id = provider.get_chain_id().await?;
+    if node_chain_id != chain_id {
+        bail!("tx is for chain id {chain_id} but the node reports {node_chain_id}");
+    }
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! Keccak-256 over several inputs at once.
+//!
+//! `keccak256_x4` runs four Keccak-f[1600] states in lockstep. Every step is written over
+//! `[u64; LANES]` arrays so the compiler lowers it to whatever vector instructions it is
+//! allowed to use, without nightly `std::simd`. Four lanes only pay off with 256-bit
+//! registers, so on x86_64 the lockstep permutation is compiled a second time with AVX2
+//! enabled and picked at runtime; everywhere else, and for inputs that can't share a
+//! permutation schedule, alloy's `keccak256` hashes them one at a time.
+
+use alloy::primitives::B256;
+
+/// One input at a time; the fallback for `keccak256_x4`.
+pub use alloy::primitives::keccak256;
+
+/// Inputs hashed per `keccak256_x4` call.
+pub const LANES: usize = 4;
+
+const RATE: usize = 136;
+
+const RC: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+const RHO: [u32; 24] =
+    [1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44];
+
+const PI: [usize; 24] =
+    [10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1];
+
+type Lanes = [u64; LANES];
+
+#[inline(always)]
+fn xor(a: Lanes, b: Lanes) -> Lanes {
+    std::array::from_fn(|i| a[i] ^ b[i])
+}
+
+#[inline(always)]
+fn andn(a: Lanes, b: Lanes) -> Lanes {
+    std::array::from_fn(|i| !a[i] & b[i])
+}
+
+#[inline(always)]
+fn rotl(a: Lanes, n: u32) -> Lanes {
+    std::array::from_fn(|i| a[i].rotate_left(n))
+}
+
+/// `keccak_f1600` on `LANES` states; lane `i` of every word belongs to state `i`.
+#[inline(always)]
+#[allow(unused_assignments)]
+fn keccak_f1600_x4(a: &mut [Lanes; 25]) {
+    for rc in RC {
+        let mut c = [[0u64; LANES]; 5];
+        for x in 0..5 {
+            c[x] = xor(xor(xor(a[x], a[x + 5]), xor(a[x + 10], a[x + 15])), a[x + 20]);
+        }
+        for x in 0..5 {
+            let d = xor(c[(x + 4) % 5], rotl(c[(x + 1) % 5], 1));
+            for y in 0..5 {
+                a[x + 5 * y] = xor(a[x + 5 * y], d);
+            }
+        }
+
+        // unrolled so every rotation is by a constant
+        let mut last = a[1];
+        macro_rules! rho_pi {
+            ($($i:literal)*) => {$(
+                let tmp = a[PI[$i]];
+                a[PI[$i]] = rotl(last, RHO[$i]);
+                last = tmp;
+            )*};
+        }
+        rho_pi!(0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23);
+
+        for y in 0..5 {
+            let row = [a[5 * y], a[5 * y + 1], a[5 * y + 2], a[5 * y + 3], a[5 * y + 4]];
+            for x in 0..5 {
+                a[5 * y + x] = xor(row[x], andn(row[(x + 1) % 5], row[(x + 2) % 5]));
+            }
+        }
+
+        a[0] = xor(a[0], [rc; LANES]);
+    }
+}
+
+/// Number of rate-sized blocks `len` bytes take once padded.
+fn blocks(len: usize) -> usize {
+    len / RATE + 1
+}
+
+/// The `block`-th padded block of `input`, as little-endian words.
+fn block_words(input: &[u8], block: usize) -> [u64; RATE / 8] {
+    let start = block * RATE;
+    let mut buf = [0u8; RATE];
+
+    if start + RATE <= input.len() {
+        buf.copy_from_slice(&input[start..start + RATE]);
+    } else {
+        let tail = &input[start..];
+        buf[..tail.len()].copy_from_slice(tail);
+        buf[tail.len()] ^= 0x01;
+        buf[RATE - 1] ^= 0x80;
+    }
+
+    std::array::from_fn(|i| u64::from_le_bytes(buf[i * 8..i * 8 + 8].try_into().unwrap()))
+}
+
+fn squeeze(words: [u64; 4]) -> B256 {
+    let mut out = [0u8; 32];
+    for (chunk, word) in out.chunks_exact_mut(8).zip(words) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    B256::from(out)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn keccak_f1600_x4_avx2(a: &mut [Lanes; 25]) {
+    keccak_f1600_x4(a)
+}
+
+/// Whether `keccak256_x4` actually runs its lanes in lockstep on this CPU.
+pub fn lockstep_available() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        std::is_x86_feature_detected!("avx2")
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        false
+    }
+}
+
+fn lockstep(inputs: [&[u8]; LANES], permute: impl Fn(&mut [Lanes; 25])) -> [B256; LANES] {
+    let n = blocks(inputs[0].len());
+    if inputs.iter().any(|input| blocks(input.len()) != n) {
+        return inputs.map(keccak256);
+    }
+
+    let mut state = [[0u64; LANES]; 25];
+    for block in 0..n {
+        let words = inputs.map(|input| block_words(input, block));
+        for (i, s) in state.iter_mut().take(RATE / 8).enumerate() {
+            *s = xor(*s, std::array::from_fn(|lane| words[lane][i]));
+        }
+        permute(&mut state);
+    }
+
+    std::array::from_fn(|lane| squeeze(std::array::from_fn(|i| state[i][lane])))
+}
+
+/// Hashes four inputs, in lockstep where the CPU makes that faster than one at a time.
+pub fn keccak256_x4(inputs: [&[u8]; LANES]) -> [B256; LANES] {
+    #[cfg(target_arch = "x86_64")]
+    if lockstep_available() {
+        // SAFETY: AVX2 support was checked just above
+        return lockstep(inputs, |state| unsafe { keccak_f1600_x4_avx2(state) });
+    }
+
+    inputs.map(keccak256)
+}
+
+/// Always runs the lanes in lockstep, compiled for the baseline target. Slower than
+/// `keccak256_x4` on most CPUs; here so tests and benchmarks can reach the portable path.
+pub fn keccak256_x4_portable(inputs: [&[u8]; LANES]) -> [B256; LANES] {
+    lockstep(inputs, keccak_f1600_x4)
+}
+
+/// Hashes `inputs` `LANES` at a time, with alloy's `keccak256` for the leftovers.
+pub fn keccak256_batch<T: AsRef<[u8]>>(inputs: &[T]) -> Vec<B256> {
+    let mut out = Vec::with_capacity(inputs.len());
+
+    let mut chunks = inputs.chunks_exact(LANES);
+    for chunk in &mut chunks {
+        out.extend(keccak256_x4(std::array::from_fn(|i| chunk[i].as_ref())));
+    }
+    out.extend(chunks.remainder().iter().map(|input| keccak256(input.as_ref())));
+
+    out
+}
@@ -0,0 +1,36 @@
+//! Shared pieces of the tx hash miners: templates, mining strategies, signers, the mining loop
+//! and the offline sign / broadcast split.
+
+pub mod api;
+pub mod bench;
+pub mod blob;
+pub mod cli;
+pub mod create2;
+pub mod delegation;
+pub mod engine;
+pub mod guard;
+pub mod keccak;
+pub mod offline;
+pub mod relay;
+pub mod replace;
+pub mod selector;
+pub mod signer;
+pub mod strategy;
+pub mod target;
+pub mod template;
+pub mod tx;
+pub mod vanity;
+pub mod verify;
+
+pub use api::{mine_tx, MineError, MineRequest, MinedTx};
+pub use cli::Miner;
+pub use engine::{mine, Mined, Options, Progress};
+pub use guard::GuardArgs;
+pub use offline::{broadcast, SignedTxFile};
+pub use relay::{Relay, RelayArgs};
+pub use signer::{ExternalSigner, MinerSigner, SignerArgs};
+pub use strategy::Strategy;
+pub use target::Target;
+pub use template::TxTemplate;
+pub use tx::{MinableTx, Parameter, TxType};
+pub use verify::VerifyArgs;
@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use alloy::{
+    consensus::{Transaction, TxEnvelope},
+    eips::eip2718::{Decodable2718, Encodable2718},
+    primitives::{Address, Bytes, B256},
+    providers::{Provider, ProviderBuilder},
+    transports::http::reqwest::Url,
+};
+use eyre::{ensure, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    guard::{self, GuardArgs},
+    tx::{self, MinableTx},
+    Mined, Strategy, Target,
+};
+
+/// A mined tx written by an offline miner, to be carried over to an online box and broadcast.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedTxFile {
+    /// EIP-2718 encoded signed tx, as accepted by `eth_sendRawTransaction`; blob txs include
+    /// their sidecar.
+    pub raw: Bytes,
+    pub hash: B256,
+    pub from: Address,
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub strategy: Strategy,
+    pub target: String,
+    pub attempts: u64,
+}
+
+impl SignedTxFile {
+    pub fn new<T: MinableTx>(
+        mined: &Mined<T>,
+        from: Address,
+        strategy: Strategy,
+        target: &Target,
+    ) -> Self {
+        Self::from_envelope(&mined.envelope(), mined.attempts, from, strategy, target)
+    }
+
+    /// For a mined tx that was wrapped after mining, like a blob tx with its sidecar.
+    pub fn from_envelope(
+        envelope: &TxEnvelope,
+        attempts: u64,
+        from: Address,
+        strategy: Strategy,
+        target: &Target,
+    ) -> Self {
+        Self {
+            raw: envelope.encoded_2718().into(),
+            hash: *envelope.tx_hash(),
+            from,
+            chain_id: envelope.chain_id().unwrap_or_default(),
+            nonce: envelope.nonce(),
+            strategy,
+            target: target.to_string(),
+            attempts,
+        }
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .wrap_err_with(|| format!("failed to open {}", path.display()))?;
+        serde_json::from_reader(file)
+            .wrap_err_with(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file = std::fs::File::create(path)
+            .wrap_err_with(|| format!("failed to create {}", path.display()))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// Decodes the raw tx and checks it still matches the recorded hash.
+    pub fn decode(&self) -> Result<TxEnvelope> {
+        let envelope = TxEnvelope::decode_2718(&mut self.raw.as_ref())
+            .wrap_err("raw tx is not a valid EIP-2718 envelope")?;
+        ensure!(
+            *envelope.tx_hash() == self.hash,
+            "raw tx hashes to {} but the file says {}",
+            envelope.tx_hash(),
+            self.hash
+        );
+        Ok(envelope)
+    }
+}
+
+/// Sends a signed tx file to the node and waits for the receipt.
+///
+/// Refuses if the node is on another chain than the tx, or if `guard` rejects it.
+pub async fn broadcast(file: &SignedTxFile, rpc_url: Url, guard: &GuardArgs) -> Result<B256> {
+    let (chain_id, cost) = tx::chain_and_cost(&file.decode()?)?;
+    ensure!(
+        chain_id == file.chain_id,
+        "raw tx is for chain id {}, the file says {}",
+        chain_id,
+        file.chain_id
+    );
+
+    let provider = ProviderBuilder::new().on_http(rpc_url);
+    guard::check_chain_id(&provider, chain_id).await?;
+    guard.check_broadcast(chain_id, cost)?;
+
+    let receipt = provider.send_raw_transaction(&file.raw).await?.get_receipt().await?;
+
+    Ok(receipt.transaction_hash)
+}
@@ -0,0 +1,214 @@
+//! Submitting mined txs to a Flashbots-style private relay instead of the public mempool, where
+//! a vanity tx can be copied or held back by whoever sees it first.
+
+use std::time::Duration;
+
+use alloy::{
+    hex,
+    primitives::{keccak256, Bytes, B256, U256},
+    providers::{Provider, ProviderBuilder},
+    signers::{local::PrivateKeySigner, SignerSync},
+    transports::http::reqwest::{Client, Url},
+};
+use clap::Args;
+use eyre::{bail, ensure, eyre, Result, WrapErr};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    guard::{self, GuardArgs},
+    tx, SignedTxFile,
+};
+
+#[derive(Args, Debug, Clone)]
+pub struct RelayArgs {
+    /// Send through this relay's `eth_sendBundle` instead of the public mempool,
+    /// e.g. https://relay.flashbots.net.
+    #[arg(long)]
+    pub relay: Option<Url>,
+    /// Key that signs relay requests. Only an identity for the relay's reputation system, it
+    /// should hold no funds. Falls back to `RELAY_IDENTITY_KEY`, then to a throwaway key.
+    #[arg(long, requires = "relay")]
+    pub relay_identity_key: Option<String>,
+    /// Number of blocks after the current head the bundle is submitted for.
+    #[arg(long, default_value_t = 5)]
+    pub relay_blocks: u64,
+}
+
+impl RelayArgs {
+    /// `None` unless `--relay` is set.
+    pub fn relay(&self) -> Result<Option<Relay>> {
+        let Some(url) = &self.relay else {
+            return Ok(None);
+        };
+
+        let identity = match self
+            .relay_identity_key
+            .clone()
+            .or_else(|| std::env::var("RELAY_IDENTITY_KEY").ok())
+        {
+            Some(key) => key.trim().parse().wrap_err("relay identity key is not valid hex")?,
+            None => {
+                let key = PrivateKeySigner::random();
+                eprintln!("no relay identity key set, signing relay requests as {}", key.address());
+                key
+            }
+        };
+
+        Ok(Some(Relay::new(url.clone(), identity)))
+    }
+}
+
+/// A relay client; every request carries an `X-Flashbots-Signature` from `identity`.
+#[derive(Debug, Clone)]
+pub struct Relay {
+    url: Url,
+    identity: PrivateKeySigner,
+    client: Client,
+    /// How often the node is asked whether the bundle landed.
+    pub poll_interval: Duration,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SendBundleResult {
+    bundle_hash: B256,
+}
+
+/// Where a submitted bundle ended up.
+#[derive(Debug, Clone)]
+pub struct Inclusion {
+    pub block_number: u64,
+    pub hashes: Vec<B256>,
+}
+
+impl Relay {
+    pub fn new(url: Url, identity: PrivateKeySigner) -> Self {
+        Self { url, identity, client: Client::new(), poll_interval: Duration::from_secs(2) }
+    }
+
+    /// Submits `txs`, in order and all or nothing, for inclusion in `block_number`.
+    pub async fn send_bundle(&self, txs: &[Bytes], block_number: u64) -> Result<B256> {
+        let params = json!([{ "txs": txs, "blockNumber": format!("{block_number:#x}") }]);
+        let result: SendBundleResult = self.call("eth_sendBundle", params).await?;
+        Ok(result.bundle_hash)
+    }
+
+    async fn call<T: serde::de::DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let body = serde_json::to_vec(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        }))?;
+
+        // the relay recovers the identity from an EIP-191 signature over the hex body hash
+        let digest = keccak256(&body).to_string();
+        let signature = self.identity.sign_message_sync(digest.as_bytes())?;
+        let header =
+            format!("{}:{}", self.identity.address(), hex::encode_prefixed(signature.as_bytes()));
+
+        let response = self
+            .client
+            .post(self.url.clone())
+            .header("content-type", "application/json")
+            .header("x-flashbots-signature", header)
+            .body(body)
+            .send()
+            .await
+            .wrap_err_with(|| format!("failed to reach relay {}", self.url))?;
+
+        let status = response.status();
+        let response: Value = serde_json::from_slice(&response.bytes().await?)
+            .wrap_err_with(|| format!("relay answered {status} with invalid JSON"))?;
+        if let Some(error) = response.get("error") {
+            bail!("relay rejected {method}: {error}");
+        }
+        ensure!(status.is_success(), "relay answered {status}");
+
+        let result =
+            response.get("result").cloned().ok_or_else(|| eyre!("relay sent no result"))?;
+        serde_json::from_value(result).wrap_err_with(|| format!("unexpected {method} result"))
+    }
+}
+
+/// Submits the txs as one bundle for each of the next `blocks` blocks, then follows the chain
+/// until the bundle lands or the last targeted block has passed.
+///
+/// Every tx goes through the same chain id and `guard` checks as a public broadcast.
+pub async fn submit(
+    files: &[SignedTxFile],
+    rpc_url: Url,
+    relay: &Relay,
+    blocks: u64,
+    guard: &GuardArgs,
+) -> Result<Inclusion> {
+    ensure!(!files.is_empty(), "no txs to submit");
+    ensure!(blocks > 0, "--relay-blocks has to be at least 1");
+
+    let chain_id = files[0].chain_id;
+    let mut cost = U256::ZERO;
+    for file in files {
+        let (tx_chain_id, tx_cost) = tx::chain_and_cost(&file.decode()?)?;
+        ensure!(
+            tx_chain_id == chain_id && file.chain_id == chain_id,
+            "every tx in a bundle has to be for the same chain, {} isn't",
+            file.hash
+        );
+        cost += tx_cost;
+    }
+
+    let provider = ProviderBuilder::new().on_http(rpc_url);
+    guard::check_chain_id(&provider, chain_id).await?;
+    guard.check_broadcast(chain_id, cost)?;
+
+    let raw: Vec<Bytes> = files.iter().map(|file| file.raw.clone()).collect();
+    let hashes: Vec<B256> = files.iter().map(|file| file.hash).collect();
+
+    let head = provider.get_block_number().await?;
+    let (first, last) = (head + 1, head + blocks);
+    for block in first..=last {
+        let bundle_hash = relay.send_bundle(&raw, block).await?;
+        println!("Submitted bundle {} for block {}", bundle_hash, block);
+    }
+
+    let mut seen = head;
+    loop {
+        // head first, so a tx landing in between is still seen before giving up
+        let current = provider.get_block_number().await?;
+
+        let mut included = Vec::new();
+        for hash in &hashes {
+            if let Some(receipt) = provider.get_transaction_receipt(*hash).await? {
+                included.push(receipt);
+            }
+        }
+
+        if included.len() == hashes.len() {
+            let block_number = included[0].block_number.unwrap_or_default();
+            for receipt in &included {
+                if !receipt.status() {
+                    eprintln!("warning: {} was included but reverted", receipt.transaction_hash);
+                }
+            }
+            return Ok(Inclusion { block_number, hashes });
+        }
+        // a bundle lands whole or not at all, so a partial match means the txs also went
+        // out some other way
+        if !included.is_empty() {
+            bail!("only {} of {} bundle txs were included", included.len(), hashes.len());
+        }
+
+        if current > seen {
+            for block in seen + 1..=current.min(last) {
+                println!("Bundle not in block {}", block);
+            }
+            seen = current;
+        }
+        if current >= last {
+            bail!("bundle was not included in blocks {first}..={last}");
+        }
+
+        tokio::time::sleep(relay.poll_interval).await;
+    }
+}
@@ -0,0 +1,114 @@
+//! Replacing a stuck tx: same sender and nonce, fees bumped past what nodes require to swap it
+//! out of their pool. Mining only ever raises fees, so every attempt stays a valid replacement.
+
+use alloy::{
+    consensus::{Transaction, TxEnvelope, TxType as EnvelopeType},
+    primitives::{Address, Bytes, TxKind, B256, U256},
+    providers::Provider,
+    transports::Transport,
+};
+use eyre::{bail, ensure, eyre, Result, WrapErr};
+
+use crate::TxTemplate;
+
+/// How much, in percent, a replacement has to raise both fees; geth's `--txpool.pricebump`.
+pub const PRICE_BUMP_PERCENT: u128 = 10;
+
+/// The lowest fee a node accepts in place of `fee`, rounded up.
+pub fn bumped(fee: u128) -> u128 {
+    fee + (fee * PRICE_BUMP_PERCENT).div_ceil(100)
+}
+
+/// The fields of a pending tx a replacement needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pending {
+    pub hash: B256,
+    pub from: Address,
+    pub chain_id: u64,
+    pub nonce: u64,
+    pub to: TxKind,
+    pub value: U256,
+    pub gas_limit: u64,
+    pub input: Bytes,
+    /// For a legacy tx, its gas price.
+    pub max_fee_per_gas: u128,
+    /// For a legacy tx, its gas price too; nodes compare both fees against it.
+    pub max_priority_fee_per_gas: u128,
+}
+
+impl Pending {
+    pub fn from_envelope(envelope: &TxEnvelope, from: Address) -> Result<Self> {
+        // blob txs need a 100% bump and their sidecar again, and aren't worth it here
+        ensure!(envelope.tx_type() != EnvelopeType::Eip4844, "can't replace a blob tx");
+
+        let max_fee_per_gas = envelope.max_fee_per_gas();
+        Ok(Self {
+            hash: *envelope.tx_hash(),
+            from,
+            chain_id: envelope.chain_id().ok_or_else(|| eyre!("tx has no chain id"))?,
+            nonce: envelope.nonce(),
+            to: envelope.to(),
+            value: envelope.value(),
+            gas_limit: envelope.gas_limit().try_into().wrap_err("gas limit doesn't fit a u64")?,
+            input: Bytes::copy_from_slice(envelope.input()),
+            max_fee_per_gas,
+            max_priority_fee_per_gas: envelope
+                .max_priority_fee_per_gas()
+                .unwrap_or(max_fee_per_gas),
+        })
+    }
+
+    /// The least each fee of a replacement can be.
+    pub fn min_fees(&self) -> (u128, u128) {
+        (bumped(self.max_fee_per_gas), bumped(self.max_priority_fee_per_gas))
+    }
+
+    /// The replacement before mining: the same call, or with `cancel` a 0-value transfer to
+    /// the sender. Fees are the minimum replacement fees, or `current` network fees if higher.
+    pub fn replacement(&self, cancel: bool, current: Option<(u128, u128)>) -> TxTemplate {
+        let (mut max_fee, mut priority_fee) = self.min_fees();
+        if let Some((current_max_fee, current_priority_fee)) = current {
+            max_fee = max_fee.max(current_max_fee);
+            priority_fee = priority_fee.max(current_priority_fee);
+        }
+        // nodes reject a tip above the fee cap
+        max_fee = max_fee.max(priority_fee);
+
+        let (to, value, gas_limit, input) = if cancel {
+            (Some(self.from), U256::ZERO, 21_000, Bytes::new())
+        } else {
+            (self.to.to().copied(), self.value, self.gas_limit, self.input.clone())
+        };
+
+        TxTemplate {
+            chain_id: Some(self.chain_id),
+            nonce: Some(self.nonce),
+            to,
+            value: Some(value),
+            gas_limit: Some(gas_limit),
+            max_fee_per_gas: Some(max_fee),
+            max_priority_fee_per_gas: Some(priority_fee),
+            input: Some(input),
+            ..Default::default()
+        }
+    }
+}
+
+/// Looks up `hash` on the node and checks it is still waiting to be included.
+pub async fn fetch_pending<P, T>(provider: &P, hash: B256) -> Result<Pending>
+where
+    P: Provider<T>,
+    T: Transport + Clone,
+{
+    let Some(tx) = provider.get_transaction_by_hash(hash).await? else {
+        bail!("the node doesn't know tx {hash}");
+    };
+    if let Some(block) = tx.block_number {
+        bail!("tx {hash} was already included in block {block}");
+    }
+
+    let from = tx.from;
+    let envelope =
+        TxEnvelope::try_from(tx).map_err(|err| eyre!("can't decode tx {hash}: {err}"))?;
+    Pending::from_envelope(&envelope, from)
+}
@@ -0,0 +1,110 @@
+//! Searching for a function name suffix whose 4-byte selector matches a prefix.
+//!
+//! Candidates are `<name>_<suffix><params>`, the suffix counting up in base 36, e.g.
+//! `setName_a7f(string)`.
+
+use std::{collections::HashMap, path::Path};
+
+use alloy::{
+    json_abi::{Function, JsonAbi},
+    primitives::Selector,
+};
+use eyre::{ensure, eyre, Result, WrapErr};
+
+use crate::{
+    engine::{self, Progress},
+    keccak::{self, LANES},
+    Target,
+};
+
+/// Parses `setName(string)` or `function setName(string name)`.
+pub fn parse_function(input: &str) -> Result<Function> {
+    let input = input.trim();
+    let input = if input.starts_with("function ") {
+        input.to_string()
+    } else {
+        format!("function {input}")
+    };
+    Function::parse(&input).map_err(|err| eyre!("bad function `{input}`: {err}"))
+}
+
+/// Selectors a renamed function can't take in the ABI at `path`: its other functions' and its
+/// custom errors', which share the 4-byte space in revert data. `renamed` is left out.
+pub fn taken_selectors(path: &Path, renamed: &str) -> Result<HashMap<Selector, String>> {
+    let contents = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    let json: serde_json::Value = serde_json::from_str(&contents)?;
+
+    // a forge artifact keeps the ABI under `abi`
+    let abi = match json.get("abi") {
+        Some(abi) => abi.clone(),
+        None => json,
+    };
+    let abi: JsonAbi = serde_json::from_value(abi).wrap_err("not a JSON ABI")?;
+
+    let functions = abi.functions().map(|function| (function.selector(), function.signature()));
+    let errors =
+        abi.errors().map(|error| (error.selector(), format!("error {}", error.signature())));
+
+    let mut selectors = HashMap::new();
+    for (selector, signature) in functions.chain(errors) {
+        if signature == renamed {
+            continue;
+        }
+        if let Some(existing) = selectors.insert(selector, signature.clone()) {
+            eprintln!("warning: {existing} and {signature} already share selector {selector}");
+        }
+    }
+    Ok(selectors)
+}
+
+/// `step` in base 36, so every step gives a different, valid identifier suffix.
+pub fn suffix(mut step: u64) -> String {
+    const DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    let mut out = Vec::new();
+    loop {
+        out.push(DIGITS[(step % 36) as usize]);
+        step /= 36;
+        if step == 0 {
+            break;
+        }
+    }
+    out.reverse();
+    String::from_utf8(out).unwrap()
+}
+
+/// Tries suffixes on `threads` threads until the selector of the renamed `function` starts
+/// with `target` and isn't one of `taken`.
+pub fn search(
+    function: &Function,
+    target: &Target,
+    taken: &HashMap<Selector, String>,
+    threads: usize,
+    progress: &Progress,
+) -> Result<((String, Selector), u64)> {
+    ensure!(target.len() <= 8, "target `{target}` is longer than a selector");
+    let signature = function.signature();
+    let (name, params) = signature.split_at(function.name.len());
+
+    engine::search(threads, progress, |lane| {
+        let mut step = lane.offset;
+        while lane.running() {
+            let candidates: [String; LANES] = std::array::from_fn(|i| {
+                format!("{name}_{}{params}", suffix(step + i as u64 * lane.stride))
+            });
+            step += LANES as u64 * lane.stride;
+
+            let hashes = keccak::keccak256_x4(std::array::from_fn(|i| candidates[i].as_bytes()));
+            lane.attempted(LANES as u64);
+
+            for (candidate, hash) in candidates.into_iter().zip(hashes) {
+                let selector = Selector::from_slice(&hash[..4]);
+                if target.matches(selector) && !taken.contains_key(&selector) {
+                    return Ok(Some((candidate, selector)));
+                }
+            }
+        }
+        Ok(None)
+    })
+}
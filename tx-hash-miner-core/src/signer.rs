@@ -0,0 +1,166 @@
+use std::path::PathBuf;
+
+use alloy::{
+    consensus::{Signed, TxEnvelope},
+    eips::eip2718::Decodable2718,
+    network::TxSignerSync,
+    primitives::{Address, Bytes, Signature},
+    rpc::client::{ClientBuilder, RpcClient},
+    signers::local::{coins_bip39::English, MnemonicBuilder, PrivateKeySigner},
+    transports::http::{
+        reqwest::{Client, Url},
+        Http,
+    },
+};
+use clap::{ArgGroup, Args};
+use eyre::{bail, ensure, eyre, Result, WrapErr};
+use serde::Deserialize;
+
+use crate::tx::{recover_signer, MinableTx};
+
+/// Where the signing key comes from. Without any of these the `PK` env var is used.
+#[derive(Args, Debug, Clone)]
+#[command(group(ArgGroup::new("signer").args(["private_key", "keystore", "mnemonic", "external_signer"])))]
+pub struct SignerArgs {
+    /// Raw hex private key.
+    #[arg(long)]
+    pub private_key: Option<String>,
+    /// Encrypted JSON keystore; the password is prompted for unless `--password-file` is set.
+    #[arg(long)]
+    pub keystore: Option<PathBuf>,
+    #[arg(long, requires = "keystore")]
+    pub password_file: Option<PathBuf>,
+    /// BIP-39 mnemonic phrase.
+    #[arg(long)]
+    pub mnemonic: Option<String>,
+    #[arg(long, requires = "mnemonic", default_value = "m/44'/60'/0'/0/0")]
+    pub derivation_path: String,
+    /// JSON-RPC endpoint of an external signer implementing `account_signTransaction` (Clef).
+    #[arg(long, requires = "from")]
+    pub external_signer: Option<Url>,
+    /// Account the external signer should sign with.
+    #[arg(long, requires = "external_signer")]
+    pub from: Option<Address>,
+}
+
+impl SignerArgs {
+    pub fn signer(&self) -> Result<MinerSigner> {
+        if let Some(path) = &self.keystore {
+            let password = match &self.password_file {
+                Some(file) => std::fs::read_to_string(file)
+                    .wrap_err_with(|| format!("failed to read {}", file.display()))?
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string(),
+                None => rpassword::prompt_password("Keystore password: ")?,
+            };
+            let signer = PrivateKeySigner::decrypt_keystore(path, password)
+                .wrap_err_with(|| format!("failed to decrypt keystore {}", path.display()))?;
+            return Ok(MinerSigner::Local(signer));
+        }
+
+        if let Some(phrase) = &self.mnemonic {
+            let signer = MnemonicBuilder::<English>::default()
+                .phrase(phrase.trim())
+                .derivation_path(&self.derivation_path)
+                .wrap_err_with(|| format!("invalid derivation path `{}`", self.derivation_path))?
+                .build()
+                .wrap_err("invalid mnemonic")?;
+            return Ok(MinerSigner::Local(signer));
+        }
+
+        if let Some(url) = &self.external_signer {
+            let from = self.from.ok_or_else(|| eyre!("--external-signer needs --from"))?;
+            return Ok(MinerSigner::External(ExternalSigner::new(url.clone(), from)));
+        }
+
+        let key = match &self.private_key {
+            Some(key) => key.clone(),
+            None => match std::env::var("PK") {
+                Ok(key) => key,
+                Err(_) => bail!(
+                    "no signer configured: pass --private-key, --keystore, --mnemonic or \
+                     --external-signer, or set the PK environment variable"
+                ),
+            },
+        };
+        let signer = key.trim().parse().wrap_err("private key is not valid hex")?;
+        Ok(MinerSigner::Local(signer))
+    }
+}
+
+/// The key used to sign mining attempts.
+#[derive(Debug, Clone)]
+pub enum MinerSigner {
+    /// Raw key, keystore or mnemonic; signs in process.
+    Local(PrivateKeySigner),
+    /// Every attempt is a round trip to the external signer, so expect far fewer attempts/sec.
+    External(ExternalSigner),
+}
+
+impl MinerSigner {
+    pub fn address(&self) -> Address {
+        match self {
+            Self::Local(signer) => signer.address(),
+            Self::External(signer) => signer.from,
+        }
+    }
+
+    pub async fn sign<T: MinableTx>(&self, tx: &T) -> Result<Signed<T>> {
+        match self {
+            Self::Local(signer) => sign_local(signer, tx),
+            Self::External(signer) => signer.sign(tx).await,
+        }
+    }
+}
+
+pub(crate) fn sign_local<T: MinableTx>(signer: &PrivateKeySigner, tx: &T) -> Result<Signed<T>> {
+    let signature = sign_unhashed(signer, tx)?;
+    Ok(tx.clone().into_signed(signature))
+}
+
+/// Just the signature; the engine hashes the signed encoding itself, in batches.
+pub(crate) fn sign_unhashed<T: MinableTx>(signer: &PrivateKeySigner, tx: &T) -> Result<Signature> {
+    let mut tx = tx.clone();
+    Ok(signer.sign_transaction_sync(&mut tx)?)
+}
+
+/// Signs through `account_signTransaction`, as served by Clef.
+#[derive(Debug, Clone)]
+pub struct ExternalSigner {
+    client: RpcClient<Http<Client>>,
+    from: Address,
+}
+
+#[derive(Debug, Deserialize)]
+struct SignTransactionResult {
+    raw: Bytes,
+}
+
+impl ExternalSigner {
+    pub fn new(url: Url, from: Address) -> Self {
+        Self { client: ClientBuilder::default().http(url), from }
+    }
+
+    pub async fn sign<T: MinableTx>(&self, tx: &T) -> Result<Signed<T>> {
+        let request = tx.request().from(self.from);
+        let result: SignTransactionResult = self
+            .client
+            .request("account_signTransaction", (request,))
+            .await
+            .wrap_err("external signer rejected account_signTransaction")?;
+
+        let envelope = TxEnvelope::decode_2718(&mut result.raw.as_ref())
+            .wrap_err("external signer returned an invalid raw tx")?;
+        let signed = T::from_envelope(envelope).wrap_err("external signer changed the tx type")?;
+
+        // the signer is free to rewrite fields, which would sign a different tx than we mined
+        ensure!(signed.tx() == tx, "external signer changed the tx before signing it");
+        ensure!(
+            recover_signer(&signed)? == self.from,
+            "external signer signed with a different account than {}",
+            self.from
+        );
+
+        Ok(signed)
+    }
+}
@@ -0,0 +1,50 @@
+use alloy::primitives::Bytes;
+use clap::ValueEnum;
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::tx::{MinableTx, TxType};
+
+/// Which field of the tx gets bumped on every attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+    /// Increase the max fee per gas, starting at the template value.
+    Fee,
+    /// Increase the priority fee per gas, starting at the template value.
+    Priority,
+    /// Increase the gas limit; unused gas is refunded to the sender.
+    Gas,
+    /// Append a counter to the calldata; solidity ignores bytes past the encoded arguments.
+    Data,
+    /// Increase the max fee per blob gas of a blob tx; only the blob base fee is charged.
+    BlobFee,
+}
+
+impl Strategy {
+    /// Whether `tx_type` has the field this strategy mines.
+    pub fn supports(&self, tx_type: TxType) -> bool {
+        *self != Self::BlobFee || tx_type == TxType::Eip4844
+    }
+
+    /// Sets the mined field of `tx` to its value for attempt `step`, relative to `base`.
+    pub fn apply<T: MinableTx>(&self, base: &T, tx: &mut T, step: u64) -> Result<()> {
+        tx.apply(base, *self, step)
+    }
+
+    /// The value of the mined field, for printing.
+    pub fn describe<T: MinableTx>(&self, tx: &T) -> String {
+        match tx.parameter(*self) {
+            Some(parameter) => parameter.to_string(),
+            None => format!("no {self:?} field in a {:?} tx", T::TX_TYPE),
+        }
+    }
+}
+
+/// `input` with `step` appended as 8 big-endian bytes, the data strategy's calldata.
+pub(crate) fn with_counter(input: &Bytes, step: u64) -> Bytes {
+    let mut out = Vec::with_capacity(input.len() + 8);
+    out.extend_from_slice(input);
+    out.extend_from_slice(&step.to_be_bytes());
+    Bytes::from(out)
+}
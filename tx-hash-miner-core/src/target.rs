@@ -0,0 +1,65 @@
+use std::{fmt, str::FromStr};
+
+use eyre::{bail, Result};
+
+/// A hex prefix the mined hash (or address) has to start with, e.g. `dead` or `0xdead`.
+///
+/// Odd lengths are allowed, the last nibble is compared on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    nibbles: Vec<u8>,
+}
+
+impl Target {
+    pub fn matches(&self, bytes: impl AsRef<[u8]>) -> bool {
+        let bytes = bytes.as_ref();
+        if self.nibbles.len() > bytes.len() * 2 {
+            return false;
+        }
+
+        self.nibbles.iter().enumerate().all(|(i, nibble)| {
+            let byte = bytes[i / 2];
+            let got = if i % 2 == 0 { byte >> 4 } else { byte & 0x0f };
+            got == *nibble
+        })
+    }
+
+    /// Number of nibbles that have to match.
+    pub fn len(&self) -> usize {
+        self.nibbles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nibbles.is_empty()
+    }
+}
+
+impl FromStr for Target {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        if s.len() > 64 {
+            bail!("target `{s}` is longer than a 32 byte hash");
+        }
+
+        let mut nibbles = Vec::with_capacity(s.len());
+        for c in s.chars() {
+            match c.to_digit(16) {
+                Some(d) => nibbles.push(d as u8),
+                None => bail!("target `{s}` is not hex"),
+            }
+        }
+
+        Ok(Self { nibbles })
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for nibble in &self.nibbles {
+            write!(f, "{nibble:x}")?;
+        }
+        Ok(())
+    }
+}
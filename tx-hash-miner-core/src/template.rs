@@ -0,0 +1,170 @@
+use std::path::{Path, PathBuf};
+
+use alloy::{
+    consensus::{TxEip1559, TxEip4844, TxEip7702},
+    eips::eip7702::SignedAuthorization,
+    primitives::{Address, Bytes, TxKind, B256, U256},
+    providers::Provider,
+    transports::Transport,
+};
+use eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+/// The fields of an EIP-1559 tx before mining, or of a blob tx when `blobFile` is set, or of a
+/// set-code tx when `authorizationList` is.
+///
+/// Every field is optional so a template can be layered: miner defaults, then a JSON file,
+/// then CLI flags. Whatever is still missing is fetched from the node, or is an error when
+/// mining offline.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxTemplate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<U256>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<u128>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<u128>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<Bytes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_blob_gas: Option<u128>,
+    /// Data to carry in blobs; makes the tx an EIP-4844 blob tx.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob_file: Option<PathBuf>,
+    /// Signed EIP-7702 authorizations, e.g. from `cast wallet sign-auth`; makes the tx a
+    /// set-code tx.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization_list: Option<Vec<SignedAuthorization>>,
+}
+
+impl TxTemplate {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .wrap_err_with(|| format!("failed to open template {}", path.display()))?;
+        serde_json::from_reader(file)
+            .wrap_err_with(|| format!("failed to parse template {}", path.display()))
+    }
+
+    /// Fields set in `other` win over the ones in `self`.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            chain_id: other.chain_id.or(self.chain_id),
+            nonce: other.nonce.or(self.nonce),
+            to: other.to.or(self.to),
+            value: other.value.or(self.value),
+            gas_limit: other.gas_limit.or(self.gas_limit),
+            max_fee_per_gas: other.max_fee_per_gas.or(self.max_fee_per_gas),
+            max_priority_fee_per_gas: other
+                .max_priority_fee_per_gas
+                .or(self.max_priority_fee_per_gas),
+            input: other.input.or(self.input),
+            max_fee_per_blob_gas: other.max_fee_per_blob_gas.or(self.max_fee_per_blob_gas),
+            blob_file: other.blob_file.or(self.blob_file),
+            authorization_list: other.authorization_list.or(self.authorization_list),
+        }
+    }
+
+    /// Fills the chain id, nonce and fees from the node if they aren't set yet.
+    pub async fn fill<P, T>(&mut self, provider: &P, from: Address) -> Result<()>
+    where
+        P: Provider<T>,
+        T: Transport + Clone,
+    {
+        if self.chain_id.is_none() {
+            self.chain_id = Some(provider.get_chain_id().await?);
+        }
+
+        if self.nonce.is_none() {
+            self.nonce = Some(provider.get_transaction_count(from).await?);
+        }
+
+        if self.max_fee_per_gas.is_none() || self.max_priority_fee_per_gas.is_none() {
+            let eip1559_est = provider.estimate_eip1559_fees(None).await?;
+            self.max_fee_per_gas.get_or_insert(eip1559_est.max_fee_per_gas);
+            self.max_priority_fee_per_gas.get_or_insert(eip1559_est.max_priority_fee_per_gas);
+        }
+
+        if self.blob_file.is_some() && self.max_fee_per_blob_gas.is_none() {
+            // headroom for the blob base fee rising while the tx waits
+            self.max_fee_per_blob_gas = Some(provider.get_blob_base_fee().await? * 2);
+        }
+
+        Ok(())
+    }
+
+    /// Builds the unsigned tx, failing on the first missing field.
+    pub fn build(&self) -> Result<TxEip1559> {
+        let missing = |field: &str| eyre!("tx template is missing `{field}`");
+
+        Ok(TxEip1559 {
+            chain_id: self.chain_id.ok_or_else(|| missing("chainId"))?,
+            nonce: self.nonce.ok_or_else(|| missing("nonce"))?,
+            gas_limit: self.gas_limit.ok_or_else(|| missing("gasLimit"))?.into(),
+            max_fee_per_gas: self.max_fee_per_gas.ok_or_else(|| missing("maxFeePerGas"))?,
+            max_priority_fee_per_gas: self
+                .max_priority_fee_per_gas
+                .ok_or_else(|| missing("maxPriorityFeePerGas"))?,
+            to: self.to.map(TxKind::Call).unwrap_or(TxKind::Create),
+            value: self.value.unwrap_or_default(),
+            access_list: Default::default(),
+            input: self.input.clone().unwrap_or_default(),
+        })
+    }
+
+    /// Builds the unsigned blob tx committing to `blob_versioned_hashes`, failing on the first
+    /// missing field.
+    pub fn build_eip4844(&self, blob_versioned_hashes: Vec<B256>) -> Result<TxEip4844> {
+        let missing = |field: &str| eyre!("tx template is missing `{field}`");
+        let base = self.build()?;
+
+        Ok(TxEip4844 {
+            chain_id: base.chain_id,
+            nonce: base.nonce,
+            gas_limit: base.gas_limit,
+            max_fee_per_gas: base.max_fee_per_gas,
+            max_priority_fee_per_gas: base.max_priority_fee_per_gas,
+            // blob txs can't create contracts
+            to: self.to.ok_or_else(|| missing("to"))?,
+            value: base.value,
+            access_list: base.access_list,
+            blob_versioned_hashes,
+            max_fee_per_blob_gas: self
+                .max_fee_per_blob_gas
+                .ok_or_else(|| missing("maxFeePerBlobGas"))?,
+            input: base.input,
+        })
+    }
+
+    /// Builds the unsigned set-code tx carrying `authorizationList`, failing on the first
+    /// missing field.
+    pub fn build_eip7702(&self) -> Result<TxEip7702> {
+        let missing = |field: &str| eyre!("tx template is missing `{field}`");
+        let base = self.build()?;
+
+        Ok(TxEip7702 {
+            chain_id: base.chain_id,
+            nonce: base.nonce,
+            gas_limit: base.gas_limit,
+            max_fee_per_gas: base.max_fee_per_gas,
+            max_priority_fee_per_gas: base.max_priority_fee_per_gas,
+            // set-code txs can't create contracts
+            to: self.to.ok_or_else(|| missing("to"))?,
+            value: base.value,
+            access_list: base.access_list,
+            authorization_list: self
+                .authorization_list
+                .clone()
+                .ok_or_else(|| missing("authorizationList"))?,
+            input: base.input,
+        })
+    }
+}
@@ -0,0 +1,284 @@
+use std::fmt;
+
+use alloy::{
+    consensus::{
+        SignableTransaction, Signed, TxEip1559, TxEip4844, TxEip4844Variant, TxEip7702, TxEnvelope,
+    },
+    eips::eip4844::DATA_GAS_PER_BLOB,
+    primitives::{Address, Bytes, Signature, U256},
+    rpc::types::TransactionRequest,
+};
+use clap::ValueEnum;
+use eyre::{bail, Result, WrapErr};
+use serde::Serialize;
+
+use crate::{engine::PriorityFeeCapReached, strategy::with_counter, Strategy};
+
+/// Tx types the engine can mine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxType {
+    Eip1559,
+    /// Type 3 blob tx; mined without its sidecar, which doesn't change the hash.
+    Eip4844,
+    /// Type 4 set-code tx; its authorizations are signed separately and left alone.
+    Eip7702,
+}
+
+/// The value of the mined field; the one in a mined tx is what made its hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Parameter {
+    MaxFeePerGas(u128),
+    MaxPriorityFeePerGas(u128),
+    GasLimit(u128),
+    Input(Bytes),
+    MaxFeePerBlobGas(u128),
+}
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MaxFeePerGas(fee) => write!(f, "max fee per gas: {fee}"),
+            Self::MaxPriorityFeePerGas(fee) => write!(f, "priority fee per gas: {fee}"),
+            Self::GasLimit(gas) => write!(f, "gas limit: {gas}"),
+            Self::Input(input) => write!(f, "input: {input}"),
+            Self::MaxFeePerBlobGas(fee) => write!(f, "max fee per blob gas: {fee}"),
+        }
+    }
+}
+
+/// A tx type the engine can mine: how strategies change it and how it is encoded once signed.
+pub trait MinableTx:
+    SignableTransaction<Signature> + Clone + PartialEq + fmt::Debug + Send + Sync + 'static
+{
+    const TX_TYPE: TxType;
+
+    /// Sets the field `strategy` mines to its value for attempt `step`, relative to `base`.
+    /// Fails with `PriorityFeeCapReached` once the priority fee would pass the max fee.
+    fn apply(&mut self, base: &Self, strategy: Strategy, step: u64) -> Result<()>;
+
+    /// The value of the field `strategy` mines, if the tx has it.
+    fn parameter(&self, strategy: Strategy) -> Option<Parameter>;
+
+    /// The most the tx can spend: the gas and blob gas at their max fees, plus the value.
+    fn max_cost(&self) -> U256 {
+        let blob_gas = self
+            .blob_versioned_hashes()
+            .map_or(0, |hashes| hashes.len() as u64 * DATA_GAS_PER_BLOB);
+        U256::from(self.gas_limit()) * U256::from(self.max_fee_per_gas())
+            + U256::from(blob_gas) * U256::from(self.max_fee_per_blob_gas().unwrap_or_default())
+            + self.value()
+    }
+
+    /// EIP-2718 encoding of the signed tx, the bytes its hash is taken over.
+    fn encode_signed(&self, signature: &Signature, out: &mut Vec<u8>);
+
+    fn into_envelope(signed: Signed<Self>) -> TxEnvelope;
+
+    /// The inverse of `into_envelope`, for txs coming back from an external signer.
+    fn from_envelope(envelope: TxEnvelope) -> Result<Signed<Self>>;
+
+    /// The tx as an `account_signTransaction` request.
+    fn request(&self) -> TransactionRequest;
+}
+
+/// `priority_fee` raised by `step`, up to `max_fee_per_gas`; nodes reject a tx whose priority
+/// fee is above its max fee.
+fn raised_priority_fee(priority_fee: u128, max_fee_per_gas: u128, step: u64) -> Result<u128> {
+    let fee = priority_fee + step as u128;
+    if fee > max_fee_per_gas {
+        return Err(PriorityFeeCapReached { max_fee_per_gas }.into());
+    }
+    Ok(fee)
+}
+
+/// The account that signed `signed`.
+pub fn recover_signer<T: SignableTransaction<Signature>>(signed: &Signed<T>) -> Result<Address> {
+    signed
+        .signature()
+        .recover_address_from_prehash(&signed.tx().signature_hash())
+        .wrap_err("signature does not recover")
+}
+
+impl MinableTx for TxEip1559 {
+    const TX_TYPE: TxType = TxType::Eip1559;
+
+    fn apply(&mut self, base: &Self, strategy: Strategy, step: u64) -> Result<()> {
+        match strategy {
+            Strategy::Fee => self.max_fee_per_gas = base.max_fee_per_gas + step as u128,
+            Strategy::Priority => {
+                self.max_priority_fee_per_gas =
+                    raised_priority_fee(base.max_priority_fee_per_gas, base.max_fee_per_gas, step)?
+            }
+            Strategy::Gas => self.gas_limit = base.gas_limit + step as u128,
+            Strategy::Data => self.input = with_counter(&base.input, step),
+            // rejected by `Strategy::supports` before mining starts
+            Strategy::BlobFee => {}
+        }
+        Ok(())
+    }
+
+    fn parameter(&self, strategy: Strategy) -> Option<Parameter> {
+        match strategy {
+            Strategy::Fee => Some(Parameter::MaxFeePerGas(self.max_fee_per_gas)),
+            Strategy::Priority => {
+                Some(Parameter::MaxPriorityFeePerGas(self.max_priority_fee_per_gas))
+            }
+            Strategy::Gas => Some(Parameter::GasLimit(self.gas_limit)),
+            Strategy::Data => Some(Parameter::Input(self.input.clone())),
+            Strategy::BlobFee => None,
+        }
+    }
+
+    fn encode_signed(&self, signature: &Signature, out: &mut Vec<u8>) {
+        out.reserve(self.encoded_len_with_signature(signature, false));
+        self.encode_with_signature(signature, out, false);
+    }
+
+    fn into_envelope(signed: Signed<Self>) -> TxEnvelope {
+        signed.into()
+    }
+
+    fn from_envelope(envelope: TxEnvelope) -> Result<Signed<Self>> {
+        match envelope {
+            TxEnvelope::Eip1559(signed) => Ok(signed),
+            other => bail!("expected an EIP-1559 tx, got {:?}", other.tx_type()),
+        }
+    }
+
+    fn request(&self) -> TransactionRequest {
+        self.clone().into()
+    }
+}
+
+impl MinableTx for TxEip4844 {
+    const TX_TYPE: TxType = TxType::Eip4844;
+
+    fn apply(&mut self, base: &Self, strategy: Strategy, step: u64) -> Result<()> {
+        // the blob versioned hashes are never touched, they commit to the sidecar
+        match strategy {
+            Strategy::Fee => self.max_fee_per_gas = base.max_fee_per_gas + step as u128,
+            Strategy::Priority => {
+                self.max_priority_fee_per_gas =
+                    raised_priority_fee(base.max_priority_fee_per_gas, base.max_fee_per_gas, step)?
+            }
+            Strategy::Gas => self.gas_limit = base.gas_limit + step as u128,
+            Strategy::Data => self.input = with_counter(&base.input, step),
+            Strategy::BlobFee => {
+                self.max_fee_per_blob_gas = base.max_fee_per_blob_gas + step as u128
+            }
+        }
+        Ok(())
+    }
+
+    fn parameter(&self, strategy: Strategy) -> Option<Parameter> {
+        match strategy {
+            Strategy::Fee => Some(Parameter::MaxFeePerGas(self.max_fee_per_gas)),
+            Strategy::Priority => {
+                Some(Parameter::MaxPriorityFeePerGas(self.max_priority_fee_per_gas))
+            }
+            Strategy::Gas => Some(Parameter::GasLimit(self.gas_limit)),
+            Strategy::Data => Some(Parameter::Input(self.input.clone())),
+            Strategy::BlobFee => Some(Parameter::MaxFeePerBlobGas(self.max_fee_per_blob_gas)),
+        }
+    }
+
+    fn encode_signed(&self, signature: &Signature, out: &mut Vec<u8>) {
+        out.reserve(self.encoded_len_with_signature(signature, false));
+        self.encode_with_signature(signature, out, false);
+    }
+
+    /// Without a sidecar; `blob::envelope` attaches one for broadcasting.
+    fn into_envelope(signed: Signed<Self>) -> TxEnvelope {
+        let (tx, signature, hash) = signed.into_parts();
+        TxEnvelope::Eip4844(Signed::new_unchecked(tx.into(), signature, hash))
+    }
+
+    fn from_envelope(envelope: TxEnvelope) -> Result<Signed<Self>> {
+        match envelope {
+            TxEnvelope::Eip4844(signed) => {
+                let (tx, signature, hash) = signed.into_parts();
+                let tx = match tx {
+                    TxEip4844Variant::TxEip4844(tx) => tx,
+                    TxEip4844Variant::TxEip4844WithSidecar(tx) => tx.tx,
+                };
+                Ok(Signed::new_unchecked(tx, signature, hash))
+            }
+            other => bail!("expected an EIP-4844 tx, got {:?}", other.tx_type()),
+        }
+    }
+
+    fn request(&self) -> TransactionRequest {
+        self.clone().into()
+    }
+}
+
+impl MinableTx for TxEip7702 {
+    const TX_TYPE: TxType = TxType::Eip7702;
+
+    fn apply(&mut self, base: &Self, strategy: Strategy, step: u64) -> Result<()> {
+        // only outer fields, the authorization list is signed by the authorities
+        match strategy {
+            Strategy::Fee => self.max_fee_per_gas = base.max_fee_per_gas + step as u128,
+            Strategy::Priority => {
+                self.max_priority_fee_per_gas =
+                    raised_priority_fee(base.max_priority_fee_per_gas, base.max_fee_per_gas, step)?
+            }
+            Strategy::Gas => self.gas_limit = base.gas_limit + step as u128,
+            Strategy::Data => self.input = with_counter(&base.input, step),
+            // rejected by `Strategy::supports` before mining starts
+            Strategy::BlobFee => {}
+        }
+        Ok(())
+    }
+
+    fn parameter(&self, strategy: Strategy) -> Option<Parameter> {
+        match strategy {
+            Strategy::Fee => Some(Parameter::MaxFeePerGas(self.max_fee_per_gas)),
+            Strategy::Priority => {
+                Some(Parameter::MaxPriorityFeePerGas(self.max_priority_fee_per_gas))
+            }
+            Strategy::Gas => Some(Parameter::GasLimit(self.gas_limit)),
+            Strategy::Data => Some(Parameter::Input(self.input.clone())),
+            Strategy::BlobFee => None,
+        }
+    }
+
+    fn encode_signed(&self, signature: &Signature, out: &mut Vec<u8>) {
+        out.reserve(self.encoded_len_with_signature(signature, false));
+        self.encode_with_signature(signature, out, false);
+    }
+
+    fn into_envelope(signed: Signed<Self>) -> TxEnvelope {
+        signed.into()
+    }
+
+    fn from_envelope(envelope: TxEnvelope) -> Result<Signed<Self>> {
+        match envelope {
+            TxEnvelope::Eip7702(signed) => Ok(signed),
+            other => bail!("expected an EIP-7702 tx, got {:?}", other.tx_type()),
+        }
+    }
+
+    fn request(&self) -> TransactionRequest {
+        self.clone().into()
+    }
+}
+
+/// Chain id and maximum cost of a signed tx about to be broadcast.
+///
+/// Blob txs have to carry their sidecar; nodes only accept them in the network wrapper.
+pub fn chain_and_cost(envelope: &TxEnvelope) -> Result<(u64, U256)> {
+    match envelope {
+        TxEnvelope::Eip1559(signed) => Ok((signed.tx().chain_id, signed.tx().max_cost())),
+        TxEnvelope::Eip4844(signed) => match signed.tx() {
+            TxEip4844Variant::TxEip4844WithSidecar(tx) => Ok((tx.tx.chain_id, tx.tx.max_cost())),
+            TxEip4844Variant::TxEip4844(_) => {
+                bail!("blob tx {} has no sidecar, nodes won't accept it", signed.hash())
+            }
+        },
+        TxEnvelope::Eip7702(signed) => Ok((signed.tx().chain_id, signed.tx().max_cost())),
+        other => bail!("can't broadcast a {:?} tx", other.tx_type()),
+    }
+}
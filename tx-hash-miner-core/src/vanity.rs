@@ -0,0 +1,87 @@
+//! Searching for a private key whose address matches a pattern.
+//!
+//! Every candidate key is 32 fresh bytes from the operating system's CSPRNG (`OsRng`). There is
+//! no seed, no counter and no key stepping, so finding one key tells an attacker nothing about
+//! any other key this tool generated. This is the property Profanity lacked: it seeded its
+//! search from a 32-bit value and stepped keys from there, so every vanity key it produced could
+//! be recovered by brute-forcing the seed.
+
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use alloy::{primitives::Address, signers::local::PrivateKeySigner};
+use eyre::{ensure, Result, WrapErr};
+use rand::rngs::OsRng;
+
+use crate::{
+    engine::{self, Progress},
+    Target,
+};
+
+/// An address prefix. All-lowercase patterns match either case; a pattern with an uppercase
+/// letter has to match the EIP-55 checksummed address exactly, e.g. `DeAd`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressPattern {
+    target: Target,
+    checksum: Option<String>,
+}
+
+impl AddressPattern {
+    pub fn matches(&self, address: &Address) -> bool {
+        // nibbles first; the checksum costs another keccak
+        self.target.matches(address)
+            && self.checksum.as_ref().is_none_or(|pattern| {
+                address.to_checksum(None)[2..].starts_with(pattern.as_str())
+            })
+    }
+}
+
+impl FromStr for AddressPattern {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        let target: Target = s.parse()?;
+        ensure!(target.len() <= 40, "pattern `{s}` is longer than an address");
+
+        let checksum = s.chars().any(|c| c.is_ascii_uppercase()).then(|| s.to_string());
+        Ok(Self { target, checksum })
+    }
+}
+
+/// A key straight from the OS CSPRNG, independent of every key before it.
+pub fn random_key() -> PrivateKeySigner {
+    PrivateKeySigner::random_with(&mut OsRng)
+}
+
+/// Draws keys on `threads` threads until one's address matches `pattern`.
+pub fn search(
+    pattern: &AddressPattern,
+    threads: usize,
+    progress: &Progress,
+) -> Result<(PrivateKeySigner, u64)> {
+    engine::search(threads, progress, |lane| {
+        while lane.running() {
+            let key = random_key();
+            lane.attempted(1);
+            if pattern.matches(&key.address()) {
+                return Ok(Some(key));
+            }
+        }
+        Ok(None)
+    })
+}
+
+/// Encrypts `key` into `dir`, named after its address; the key itself is never printed.
+pub fn write_keystore(key: &PrivateKeySigner, dir: &Path, password: &str) -> Result<PathBuf> {
+    ensure!(!password.is_empty(), "refusing to write a keystore with an empty password");
+    std::fs::create_dir_all(dir).wrap_err_with(|| format!("failed to create {}", dir.display()))?;
+
+    let name = key.address().to_string();
+    PrivateKeySigner::encrypt_keystore(dir, &mut OsRng, key.to_bytes(), password, Some(&name))
+        .wrap_err("failed to write keystore")?;
+
+    Ok(dir.join(name))
+}
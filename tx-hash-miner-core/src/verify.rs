@@ -0,0 +1,398 @@
+//! Checking a mined tx from its raw bytes alone, so nobody has to trust the miner's output.
+
+use std::path::PathBuf;
+
+use alloy::{
+    consensus::{Transaction, TxEip1559, TxEip4844, TxEip4844Variant, TxEip7702, TxEnvelope},
+    dyn_abi::{DynSolValue, JsonAbiExt},
+    eips::eip2718::Decodable2718,
+    json_abi::Function,
+    primitives::{keccak256, Address, Bytes, TxKind, B256},
+};
+use clap::{ArgGroup, Args};
+use eyre::{bail, ensure, eyre, Result, WrapErr};
+
+use crate::{
+    tx::{recover_signer, MinableTx, TxType},
+    SignedTxFile, Strategy, Target,
+};
+
+#[derive(Args, Debug, Clone)]
+#[command(group(ArgGroup::new("tx").required(true).args(["raw", "file"])))]
+pub struct VerifyArgs {
+    /// Raw signed tx as hex, as sent with `eth_sendRawTransaction`.
+    #[arg(long)]
+    pub raw: Option<Bytes>,
+    /// File holding the raw tx as hex, or a tx file written by `offline`.
+    #[arg(long)]
+    pub file: Option<PathBuf>,
+    /// Hex prefix the hash has to start with; defaults to the one recorded in a tx file.
+    #[arg(long)]
+    pub target: Option<Target>,
+    /// Function to decode the calldata as, e.g. `setName(string)`.
+    #[arg(long)]
+    pub function: Option<String>,
+}
+
+/// What `verify` found out about a raw tx.
+#[derive(Debug, Clone)]
+pub struct Verification {
+    pub tx: VerifiedTx,
+    /// keccak256 of the signed tx, computed here rather than read from anywhere. The raw bytes
+    /// of a blob tx also carry its sidecar, which the hash leaves out.
+    pub hash: B256,
+    pub from: Address,
+    pub target: Option<Target>,
+    /// The field that most likely carried the mining counter, and why.
+    pub mined_field: Option<(Strategy, String)>,
+    pub calldata: Option<Calldata>,
+    /// Why the calldata couldn't be decoded as the given function; it's only shown as raw hex
+    /// then, and the rest of the tx is checked as usual.
+    pub undecoded: Option<String>,
+}
+
+impl Verification {
+    pub fn matches_target(&self) -> bool {
+        self.target.as_ref().is_none_or(|target| target.matches(self.hash))
+    }
+}
+
+/// The decoded tx, without its signature or blob sidecar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifiedTx {
+    Eip1559(TxEip1559),
+    Eip4844(TxEip4844),
+    Eip7702(TxEip7702),
+}
+
+impl VerifiedTx {
+    pub fn tx_type(&self) -> TxType {
+        match self {
+            Self::Eip1559(_) => TxType::Eip1559,
+            Self::Eip4844(_) => TxType::Eip4844,
+            Self::Eip7702(_) => TxType::Eip7702,
+        }
+    }
+
+    /// The fields every type has.
+    pub fn fields(&self) -> &dyn Transaction {
+        match self {
+            Self::Eip1559(tx) => tx,
+            Self::Eip4844(tx) => tx,
+            Self::Eip7702(tx) => tx,
+        }
+    }
+
+    fn describe(&self, strategy: Strategy) -> String {
+        match self {
+            Self::Eip1559(tx) => strategy.describe(tx),
+            Self::Eip4844(tx) => strategy.describe(tx),
+            Self::Eip7702(tx) => strategy.describe(tx),
+        }
+    }
+}
+
+/// Calldata decoded against a function signature.
+#[derive(Debug, Clone)]
+pub struct Calldata {
+    pub signature: String,
+    pub args: Vec<DynSolValue>,
+    /// Bytes past the canonical encoding of `args`; solidity never reads them.
+    pub trailing: Bytes,
+    /// Offsets into the arguments where the calldata has something else than the canonical
+    /// zero padding; the decoded values ignore these bytes too.
+    pub padding: Vec<usize>,
+}
+
+impl VerifyArgs {
+    pub fn run(&self) -> Result<()> {
+        let (raw, recorded) = self.load()?;
+        let target = match (&self.target, &recorded) {
+            (Some(target), _) => Some(target.clone()),
+            (None, Some(file)) => Some(file.target.parse()?),
+            (None, None) => None,
+        };
+        let function = match &self.function {
+            Some(function) => Some(parse_function(function)?),
+            None => None,
+        };
+
+        let verification = verify(&raw, target, recorded.as_ref(), function.as_ref())?;
+        print(&verification);
+
+        ensure!(verification.matches_target(), "hash does not start with the target");
+        Ok(())
+    }
+
+    fn load(&self) -> Result<(Bytes, Option<SignedTxFile>)> {
+        if let Some(raw) = &self.raw {
+            return Ok((raw.clone(), None));
+        }
+
+        let path = self.file.as_ref().ok_or_else(|| eyre!("no tx given"))?;
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        let contents = contents.trim();
+
+        if contents.starts_with('{') {
+            let file: SignedTxFile = serde_json::from_str(contents)
+                .wrap_err_with(|| format!("failed to parse {}", path.display()))?;
+            return Ok((file.raw.clone(), Some(file)));
+        }
+        let raw = contents.parse().wrap_err_with(|| format!("{} is not hex", path.display()))?;
+        Ok((raw, None))
+    }
+}
+
+/// Accepts `setName(string)` as well as `function setName(string s)`.
+pub fn parse_function(function: &str) -> Result<Function> {
+    let function = function.trim();
+    let function = if function.starts_with("function ") {
+        function.to_string()
+    } else {
+        format!("function {function}")
+    };
+    Function::parse(&function).map_err(|err| eyre!("bad function `{function}`: {err}"))
+}
+
+/// Decodes `raw`, recovers the sender and recomputes the hash.
+///
+/// With `recorded`, the tx file written next to the raw tx, every field it claims is checked
+/// against the raw tx, and its strategy names the mined field. Otherwise the mined field is a
+/// guess from which field looks least like a value someone would pick by hand.
+pub fn verify(
+    raw: &Bytes,
+    target: Option<Target>,
+    recorded: Option<&SignedTxFile>,
+    function: Option<&Function>,
+) -> Result<Verification> {
+    let envelope = TxEnvelope::decode_2718(&mut raw.as_ref())
+        .wrap_err("raw tx is not a valid EIP-2718 envelope")?;
+    let (tx, hash, from) = match &envelope {
+        TxEnvelope::Eip1559(_) => {
+            let (tx, hash, from) = open::<TxEip1559>(envelope, raw)?;
+            (VerifiedTx::Eip1559(tx), hash, from)
+        }
+        TxEnvelope::Eip4844(signed) => {
+            if let TxEip4844Variant::TxEip4844WithSidecar(tx) = signed.tx() {
+                ensure!(
+                    tx.sidecar.versioned_hashes().eq(tx.tx.blob_versioned_hashes.iter().copied()),
+                    "sidecar doesn't match the tx's blob versioned hashes"
+                );
+            }
+            let (tx, hash, from) = open::<TxEip4844>(envelope, raw)?;
+            (VerifiedTx::Eip4844(tx), hash, from)
+        }
+        TxEnvelope::Eip7702(_) => {
+            let (tx, hash, from) = open::<TxEip7702>(envelope, raw)?;
+            (VerifiedTx::Eip7702(tx), hash, from)
+        }
+        other => bail!("can't verify a {:?} tx", other.tx_type()),
+    };
+    let fields = tx.fields();
+
+    if let Some(file) = recorded {
+        ensure!(
+            file.hash == hash,
+            "tx file says hash {} but the raw tx hashes to {hash}",
+            file.hash
+        );
+        ensure!(
+            file.from == from,
+            "tx file says sender {} but the signature recovers {from}",
+            file.from
+        );
+        ensure!(
+            Some(file.chain_id) == fields.chain_id() && file.nonce == fields.nonce(),
+            "tx file chain id / nonce don't match the raw tx"
+        );
+    }
+
+    let (calldata, undecoded) = match function.map(|f| decode_calldata(f, fields.input())) {
+        Some(Ok(calldata)) => (Some(calldata), None),
+        Some(Err(err)) => (None, Some(format!("{err:#}"))),
+        None => (None, None),
+    };
+
+    let mined_field = match recorded {
+        Some(file) => Some((file.strategy, "recorded in the tx file".to_string())),
+        None => guess_mined_field(&tx, calldata.as_ref()),
+    };
+
+    Ok(Verification { tx, hash, from, target, mined_field, calldata, undecoded })
+}
+
+/// The tx without its signature and sidecar, its hash and its sender. The hash is taken over
+/// the tx re-encoded without a sidecar, which has to give back `raw` for every other tx.
+fn open<T: MinableTx>(envelope: TxEnvelope, raw: &[u8]) -> Result<(T, B256, Address)> {
+    let sidecar = matches!(
+        &envelope,
+        TxEnvelope::Eip4844(signed)
+            if matches!(signed.tx(), TxEip4844Variant::TxEip4844WithSidecar(_))
+    );
+    let signed = T::from_envelope(envelope)?;
+
+    let mut encoded = Vec::new();
+    signed.tx().encode_signed(signed.signature(), &mut encoded);
+    ensure!(sidecar || encoded == raw, "raw tx isn't canonically encoded");
+    let hash = keccak256(&encoded);
+
+    let from = recover_signer(&signed)?;
+    Ok((signed.strip_signature(), hash, from))
+}
+
+fn decode_calldata(function: &Function, input: &[u8]) -> Result<Calldata> {
+    ensure!(input.len() >= 4, "calldata is too short for a selector");
+    ensure!(
+        input[..4] == function.selector()[..],
+        "calldata selector 0x{} isn't {} ({})",
+        alloy::hex::encode(&input[..4]),
+        function.signature(),
+        function.selector()
+    );
+
+    let args = function
+        .abi_decode_input(&input[4..], false)
+        .wrap_err_with(|| format!("calldata doesn't decode as {}", function.signature()))?;
+
+    // the decoded values re-encode canonically, so whatever differs is padding and whatever is
+    // left over was appended after the arguments
+    let canonical = DynSolValue::Tuple(args.clone()).abi_encode_params();
+    let encoded = &input[4..];
+    ensure!(encoded.len() >= canonical.len(), "calldata is shorter than its own arguments");
+
+    let padding = (0..canonical.len()).filter(|&i| encoded[i] != canonical[i]).collect();
+    let trailing = Bytes::copy_from_slice(&encoded[canonical.len()..]);
+
+    Ok(Calldata { signature: function.signature(), args, trailing, padding })
+}
+
+fn guess_mined_field(tx: &VerifiedTx, calldata: Option<&Calldata>) -> Option<(Strategy, String)> {
+    let fields = tx.fields();
+    let input = fields.input();
+    if let Some(calldata) = calldata {
+        if !calldata.padding.is_empty() {
+            return Some((
+                Strategy::Data,
+                format!("{} non-zero padding bytes", calldata.padding.len()),
+            ));
+        }
+        if !calldata.trailing.is_empty() {
+            return Some((
+                Strategy::Data,
+                format!("{} bytes follow the arguments", calldata.trailing.len()),
+            ));
+        }
+    } else if input.len() > 4 && !(input.len() - 4).is_multiple_of(32) {
+        return Some((Strategy::Data, "calldata isn't a whole number of ABI words".to_string()));
+    }
+
+    if !fields.gas_limit().is_multiple_of(1_000) {
+        return Some((Strategy::Gas, "gas limit isn't a round number".to_string()));
+    }
+    if !fields.max_priority_fee_per_gas().unwrap_or_default().is_multiple_of(1_000_000) {
+        return Some((Strategy::Priority, "priority fee isn't a round number".to_string()));
+    }
+    if !fields.max_fee_per_gas().is_multiple_of(1_000_000) {
+        return Some((Strategy::Fee, "max fee isn't a round number".to_string()));
+    }
+    if !fields.max_fee_per_blob_gas().unwrap_or_default().is_multiple_of(1_000_000) {
+        return Some((Strategy::BlobFee, "max fee per blob gas isn't a round number".to_string()));
+    }
+    None
+}
+
+fn print(verification: &Verification) {
+    let tx = verification.tx.fields();
+    println!("hash: {}", verification.hash);
+    println!("type: {:?}", verification.tx.tx_type());
+    println!("from: {}", verification.from);
+    println!("chain id: {}, nonce: {}", tx.chain_id().unwrap_or_default(), tx.nonce());
+    match tx.to() {
+        TxKind::Call(to) => println!("to: {}", to),
+        TxKind::Create => println!("to: none, contract creation"),
+    }
+    println!("value: {}", tx.value());
+    println!("gas limit: {}", tx.gas_limit());
+    println!("max fee per gas: {}", tx.max_fee_per_gas());
+    println!("priority fee per gas: {}", tx.max_priority_fee_per_gas().unwrap_or_default());
+    if let VerifiedTx::Eip4844(blob_tx) = &verification.tx {
+        println!("max fee per blob gas: {}", blob_tx.max_fee_per_blob_gas);
+        for hash in &blob_tx.blob_versioned_hashes {
+            println!("blob versioned hash: {}", hash);
+        }
+    }
+    if let VerifiedTx::Eip7702(set_code_tx) = &verification.tx {
+        for authorization in &set_code_tx.authorization_list {
+            let authority = match authorization.recover_authority() {
+                Ok(authority) => authority.to_string(),
+                Err(_) => "unrecoverable".to_string(),
+            };
+            println!(
+                "authorization: {} delegates to {} on chain id {}, nonce {}",
+                authority, authorization.address, authorization.chain_id, authorization.nonce
+            );
+        }
+    }
+    println!("input: {}", Bytes::copy_from_slice(tx.input()));
+
+    if let Some(calldata) = &verification.calldata {
+        println!("decoded as {}:", calldata.signature);
+        for (i, arg) in calldata.args.iter().enumerate() {
+            println!("  [{}] {}", i, format_value(arg));
+        }
+        if calldata.trailing.is_empty() {
+            println!("trailing bytes: none");
+        } else {
+            println!("trailing bytes: {}", calldata.trailing);
+        }
+        if !calldata.padding.is_empty() {
+            println!("non-zero padding at argument offsets: {:?}", calldata.padding);
+        }
+    }
+    if let Some(reason) = &verification.undecoded {
+        println!("not decoded: {}", reason);
+    }
+
+    match &verification.mined_field {
+        Some((strategy, reason)) => {
+            println!("mined field: {:?} ({})", strategy, reason);
+            println!("{}", verification.tx.describe(*strategy));
+            let input = tx.input();
+            if *strategy == Strategy::Data && input.len() >= 8 {
+                // the data strategy appends its counter as the last 8 bytes
+                let counter = u64::from_be_bytes(input[input.len() - 8..].try_into().unwrap());
+                println!("counter: {}", counter);
+            }
+        }
+        None => println!("mined field: unknown, every field looks hand-picked"),
+    }
+
+    match &verification.target {
+        Some(target) if verification.matches_target() => println!("target {}: ok", target),
+        Some(target) => println!("target {}: MISMATCH", target),
+        None => println!("target: not checked"),
+    }
+}
+
+fn format_value(value: &DynSolValue) -> String {
+    match value {
+        DynSolValue::Bool(b) => b.to_string(),
+        DynSolValue::Int(i, _) => i.to_string(),
+        DynSolValue::Uint(u, _) => u.to_string(),
+        DynSolValue::FixedBytes(word, size) => format!("0x{}", alloy::hex::encode(&word[..*size])),
+        DynSolValue::Address(address) => address.to_string(),
+        DynSolValue::Function(function) => function.to_string(),
+        DynSolValue::Bytes(bytes) => format!("0x{}", alloy::hex::encode(bytes)),
+        DynSolValue::String(s) => format!("{s:?}"),
+        DynSolValue::Array(values)
+        | DynSolValue::FixedArray(values)
+        | DynSolValue::Tuple(values) => {
+            let values: Vec<_> = values.iter().map(format_value).collect();
+            format!("[{}]", values.join(", "))
+        }
+        // `CustomStruct` only exists with alloy's eip712 feature
+        #[allow(unreachable_patterns)]
+        other => format!("{other:?}"),
+    }
+}
@@ -0,0 +1,142 @@
+//! Every strategy end to end against a local anvil node: mine a `setName` call to
+//! `DynamicExample`, broadcast it and check the contract saw the name. Needs anvil and forge,
+//! so these only run with `cargo test -- --ignored`.
+
+use std::{path::Path, process::Command, sync::OnceLock};
+
+use alloy::{
+    eips::eip2718::Encodable2718,
+    network::{EthereumWallet, TransactionBuilder},
+    node_bindings::{Anvil, AnvilInstance},
+    primitives::{Address, Bytes, U256},
+    providers::{Provider, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+    sol,
+    sol_types::SolCall,
+};
+use miner_core::{mine_tx, MineRequest, MinerSigner, Strategy, Target, TxTemplate};
+
+sol! {
+    function setName(string calldata s) public;
+    event Named(string s);
+}
+
+const NAME: &str = "poop";
+
+/// Builds `DynamicExample` with forge, once for all tests, and returns its creation code.
+fn dynamic_example() -> Bytes {
+    static CODE: OnceLock<Bytes> = OnceLock::new();
+    CODE.get_or_init(build_dynamic_example).clone()
+}
+
+/// Builds into a temp dir, leaving the example's own directory alone.
+fn build_dynamic_example() -> Bytes {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../tx-hash-miner-data/dynamic-example");
+    let build = std::env::temp_dir().join(format!("dynamic-example-{}", std::process::id()));
+    let (out, cache) = (build.join("out"), build.join("cache"));
+    let output = Command::new("forge")
+        .arg("build")
+        .arg("--root")
+        .arg(&root)
+        .arg("--out")
+        .arg(&out)
+        .arg("--cache-path")
+        .arg(&cache)
+        .output()
+        .expect("failed to run forge");
+    assert!(
+        output.status.success(),
+        "forge build failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let artifact = out.join("DynamicExample.sol/DynamicExample.json");
+    let artifact: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(artifact).unwrap()).unwrap();
+    std::fs::remove_dir_all(build).unwrap();
+    artifact["bytecode"]["object"].as_str().unwrap().parse().unwrap()
+}
+
+/// Anvil with `DynamicExample` deployed by its first dev account, whose key is returned too.
+async fn deployed() -> (AnvilInstance, PrivateKeySigner, Address) {
+    let code = dynamic_example();
+    let anvil = Anvil::new().try_spawn().expect("failed to spawn anvil");
+
+    let key = PrivateKeySigner::from(anvil.keys()[0].clone());
+    let provider = ProviderBuilder::new()
+        .with_recommended_fillers()
+        .wallet(EthereumWallet::from(key.clone()))
+        .on_http(anvil.endpoint_url());
+    let deploy = TransactionRequest::default().with_deploy_code(code);
+    let receipt = provider.send_transaction(deploy).await.unwrap().get_receipt().await.unwrap();
+    let contract = receipt.contract_address.unwrap();
+
+    (anvil, key, contract)
+}
+
+/// The storage word of a solidity `string` shorter than 32 bytes: the bytes, then `len * 2`.
+fn short_string_slot(s: &str) -> U256 {
+    let mut word = [0u8; 32];
+    word[..s.len()].copy_from_slice(s.as_bytes());
+    word[31] = (s.len() * 2) as u8;
+    U256::from_be_bytes(word)
+}
+
+async fn mine_and_send(strategy: Strategy) {
+    let (anvil, key, contract) = deployed().await;
+    let provider = ProviderBuilder::new().on_http(anvil.endpoint_url());
+
+    let mut template = TxTemplate {
+        to: Some(contract),
+        value: Some(U256::ZERO),
+        gas_limit: Some(100_000),
+        input: Some(setNameCall { s: NAME.to_string() }.abi_encode().into()),
+        ..Default::default()
+    };
+    template.fill(&provider, key.address()).await.unwrap();
+
+    let target: Target = "00".parse().unwrap();
+    let request =
+        MineRequest::new(template, strategy, target.clone(), MinerSigner::Local(key.clone()));
+    let mined = mine_tx(request).await.unwrap();
+
+    let pending = provider.send_raw_transaction(&mined.envelope.encoded_2718()).await.unwrap();
+    let receipt = pending.get_receipt().await.unwrap();
+
+    assert!(receipt.status(), "{strategy:?} tx reverted");
+    assert_eq!(receipt.transaction_hash, mined.hash);
+    assert!(target.matches(receipt.transaction_hash));
+
+    let stored = provider.get_storage_at(contract, U256::ZERO).await.unwrap();
+    assert_eq!(stored, short_string_slot(NAME), "{strategy:?} stored the wrong name");
+
+    let logs = receipt.inner.logs();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].address(), contract);
+    assert_eq!(logs[0].log_decode::<Named>().unwrap().inner.data.s, NAME);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and forge"]
+async fn fee_strategy() {
+    mine_and_send(Strategy::Fee).await;
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and forge"]
+async fn priority_strategy() {
+    mine_and_send(Strategy::Priority).await;
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and forge"]
+async fn gas_strategy() {
+    mine_and_send(Strategy::Gas).await;
+}
+
+#[tokio::test]
+#[ignore = "needs anvil and forge"]
+async fn data_strategy() {
+    mine_and_send(Strategy::Data).await;
+}
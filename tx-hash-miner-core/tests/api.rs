@@ -0,0 +1,128 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use alloy::{
+    primitives::{address, U256},
+    signers::local::PrivateKeySigner,
+};
+use miner_core::{
+    api::ProgressReport, mine_tx, MineError, MineRequest, MinerSigner, Parameter, Strategy,
+    TxTemplate,
+};
+
+fn template() -> TxTemplate {
+    TxTemplate {
+        chain_id: Some(31337),
+        nonce: Some(0),
+        to: Some(address!("328eBc7bb2ca4Bf4216863042a960E3C64Ed4c10")),
+        gas_limit: Some(21_000),
+        max_fee_per_gas: Some(2_000_000_000),
+        max_priority_fee_per_gas: Some(1),
+        ..Default::default()
+    }
+}
+
+fn new_request(target: &str) -> MineRequest {
+    let signer = MinerSigner::Local(PrivateKeySigner::random());
+    MineRequest::new(template(), Strategy::Gas, target.parse().unwrap(), signer)
+}
+
+#[tokio::test]
+async fn returns_the_mined_tx() {
+    let request = new_request("00");
+    let target = request.target.clone();
+    let from = request.signer.address();
+
+    let mined = mine_tx(request).await.unwrap();
+
+    assert!(target.matches(mined.hash));
+    assert_eq!(*mined.envelope.tx_hash(), mined.hash);
+    assert_eq!(mined.from, from);
+    let Parameter::GasLimit(gas) = mined.parameter else { panic!("{:?}", mined.parameter) };
+    assert!(gas >= 21_000);
+    assert_eq!(mined.cost, U256::from(gas) * U256::from(2_000_000_000u64));
+}
+
+// one worker thread: if mining ran on it, the sleep below would never wake up to cancel it
+#[tokio::test(flavor = "current_thread")]
+async fn cancels_without_blocking_the_runtime() {
+    // a target nobody will hit
+    let mut request = new_request("0000000000000000");
+    let reports = Arc::new(Mutex::new(Vec::<ProgressReport>::new()));
+    request.progress_interval = Duration::from_millis(5);
+    request.on_progress = Some(Box::new({
+        let reports = reports.clone();
+        move |report| reports.lock().unwrap().push(report)
+    }));
+    let progress = request.progress.clone();
+    let run = tokio::spawn(mine_tx(request));
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    progress.cancel();
+
+    match run.await.unwrap() {
+        Err(MineError::Cancelled { attempts }) => assert!(attempts > 0),
+        other => panic!("{other:?}"),
+    }
+    let reports = reports.lock().unwrap();
+    assert!(!reports.is_empty());
+    assert!(reports.windows(2).all(|pair| pair[0].attempts <= pair[1].attempts));
+}
+
+#[tokio::test]
+async fn dropping_the_future_stops_mining() {
+    let mut request = new_request("0000000000000000");
+    let reports = Arc::new(Mutex::new(0usize));
+    request.progress_interval = Duration::from_millis(5);
+    request.on_progress = Some(Box::new({
+        let reports = reports.clone();
+        move |_| *reports.lock().unwrap() += 1
+    }));
+    let progress = request.progress.clone();
+
+    let timed_out = tokio::time::timeout(Duration::from_millis(50), mine_tx(request)).await;
+    assert!(timed_out.is_err());
+    assert!(progress.is_cancelled());
+
+    // the search threads see the flag within a batch
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let attempts = progress.attempts();
+    let reported = *reports.lock().unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(progress.attempts(), attempts);
+    assert!(reported > 0);
+    assert_eq!(*reports.lock().unwrap(), reported, "the callback still fires");
+}
+
+#[tokio::test]
+async fn reports_typed_errors() {
+    let mut request = new_request("0");
+    request.template.nonce = None;
+    assert!(matches!(mine_tx(request).await, Err(MineError::Template(_))));
+
+    let mut request = new_request("0");
+    request.strategy = Strategy::BlobFee;
+    assert!(matches!(mine_tx(request).await, Err(MineError::UnsupportedStrategy { .. })));
+
+    let mut request = new_request("0000000000000000");
+    let cap = U256::from(21_010u64 * 2_000_000_000);
+    request.options.max_cost = Some(cap);
+    match mine_tx(request).await {
+        Err(MineError::CostCap { cap: reported, cost }) => {
+            assert_eq!(reported, cap);
+            assert!(cost > cap);
+        }
+        other => panic!("{other:?}"),
+    }
+
+    // 10 attempts take the priority fee from 1 wei to the max fee
+    let mut request = new_request("0000000000000000");
+    request.strategy = Strategy::Priority;
+    request.template.max_fee_per_gas = Some(10);
+    match mine_tx(request).await {
+        Err(MineError::PriorityFeeCap { max_fee_per_gas }) => assert_eq!(max_fee_per_gas, 10),
+        other => panic!("{other:?}"),
+    }
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use miner_core::{
+    bench::{self, BenchResult, SignerBackend},
+    tx::TxType,
+    Strategy,
+};
+
+fn bench_result(rates: Vec<f64>) -> BenchResult {
+    BenchResult::new(Strategy::Fee, TxType::Eip1559, SignerBackend::Local, 4, rates)
+}
+
+#[test]
+fn rates_are_attempts_per_second() {
+    assert_eq!(bench::rate(1_000, Duration::from_millis(500)), 2_000.0);
+    assert_eq!(bench::rate(3, Duration::from_secs(2)), 1.5);
+    assert_eq!(bench::rate(0, Duration::from_secs(1)), 0.0);
+}
+
+#[test]
+fn summarizes_samples() {
+    // four half-second samples
+    let rates = [4_000, 1_000, 3_000, 2_000]
+        .map(|attempts| bench::rate(attempts, Duration::from_millis(500)))
+        .to_vec();
+
+    let result = bench_result(rates);
+    assert_eq!(result.samples, 4);
+    assert_eq!(result.threads, 4);
+    assert_eq!(result.min, 2_000.0);
+    assert_eq!(result.max, 8_000.0);
+    assert_eq!(result.mean, 5_000.0);
+    assert_eq!(result.median, 5_000.0);
+    // population deviation: sqrt((3000² + 1000² + 1000² + 3000²) / 4)
+    assert!((result.stddev - 5_000_000f64.sqrt()).abs() < 1e-9, "{}", result.stddev);
+}
+
+#[test]
+fn takes_the_middle_sample_of_an_odd_count() {
+    let result = bench_result(vec![9.0, 1.0, 5.0]);
+    assert_eq!(result.median, 5.0);
+    assert_eq!(result.mean, 5.0);
+    assert_eq!((result.min, result.max), (1.0, 9.0));
+
+    let result = bench_result(vec![7.0]);
+    assert_eq!((result.mean, result.median, result.stddev), (7.0, 7.0, 0.0));
+}
+
+#[test]
+fn handles_no_samples() {
+    let result = bench_result(Vec::new());
+    assert_eq!(result.samples, 0);
+    assert_eq!(
+        (result.mean, result.stddev, result.min, result.median, result.max),
+        (0.0, 0.0, 0.0, 0.0, 0.0)
+    );
+}
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use alloy::{
+    consensus::{BlobTransactionSidecar, TxEip1559, TxEip4844, TxEip4844Variant, TxEnvelope},
+    eips::eip2718::{Decodable2718, Encodable2718},
+    primitives::{address, keccak256, Bytes, TxKind, B256, U256},
+    signers::local::PrivateKeySigner,
+};
+use miner_core::{
+    blob, engine, tx, MinableTx, MinerSigner, Options, SignedTxFile, Strategy, Target,
+};
+
+fn blob_tx(blob_versioned_hashes: Vec<B256>) -> TxEip4844 {
+    TxEip4844 {
+        chain_id: 31337,
+        nonce: 0,
+        gas_limit: 21_000,
+        max_fee_per_gas: 2_000_000_000,
+        max_priority_fee_per_gas: 1,
+        to: address!("328eBc7bb2ca4Bf4216863042a960E3C64Ed4c10"),
+        value: U256::ZERO,
+        access_list: Default::default(),
+        blob_versioned_hashes,
+        max_fee_per_blob_gas: 1_000_000_000,
+        input: Bytes::new(),
+    }
+}
+
+fn sidecar() -> BlobTransactionSidecar {
+    let path = std::env::temp_dir().join(format!("blob-test-{}.bin", std::process::id()));
+    std::fs::write(&path, b"rollup batch goes here").unwrap();
+    let sidecar = blob::sidecar_from_file(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    sidecar
+}
+
+#[tokio::test]
+async fn mines_blob_fee_without_touching_blobs() {
+    let sidecar = sidecar();
+    let base = blob_tx(blob::versioned_hashes(&sidecar));
+    let target: Target = "0".parse().unwrap();
+    let key = PrivateKeySigner::random();
+
+    let mined = engine::mine(
+        &base,
+        Strategy::BlobFee,
+        &target,
+        &MinerSigner::Local(key.clone()),
+        &Options::default(),
+        &Arc::default(),
+    )
+    .await
+    .unwrap();
+
+    let tx = mined.tx.tx();
+    assert!(target.matches(mined.hash()));
+    assert_eq!(tx.blob_versioned_hashes, base.blob_versioned_hashes);
+    assert!(tx.max_fee_per_blob_gas >= base.max_fee_per_blob_gas);
+    assert_eq!(TxEip4844 { max_fee_per_blob_gas: base.max_fee_per_blob_gas, ..tx.clone() }, base);
+
+    // the network wrapper carries the sidecar but hashes like the bare tx
+    let envelope = blob::envelope(mined.tx.clone(), sidecar).unwrap();
+    let raw = envelope.encoded_2718();
+    assert_eq!(*envelope.tx_hash(), mined.hash());
+    assert_eq!(keccak256(mined.envelope().encoded_2718()), mined.hash());
+
+    let decoded = TxEnvelope::decode_2718(&mut raw.as_slice()).unwrap();
+    let TxEnvelope::Eip4844(signed) = &decoded else { panic!("expected a blob tx") };
+    assert!(matches!(signed.tx(), TxEip4844Variant::TxEip4844WithSidecar(_)));
+    assert_eq!(*decoded.tx_hash(), mined.hash());
+
+    let file = SignedTxFile::from_envelope(
+        &envelope,
+        mined.attempts,
+        key.address(),
+        Strategy::BlobFee,
+        &target,
+    );
+    assert_eq!(tx::chain_and_cost(&file.decode().unwrap()).unwrap(), (31337, tx.max_cost()));
+}
+
+#[tokio::test]
+async fn mines_other_strategies_on_blob_txs() {
+    let base = blob_tx(blob::versioned_hashes(&sidecar()));
+    let target: Target = "0".parse().unwrap();
+    let signer = MinerSigner::Local(PrivateKeySigner::random());
+
+    for strategy in [Strategy::Fee, Strategy::Priority, Strategy::Gas, Strategy::Data] {
+        let mined =
+            engine::mine(&base, strategy, &target, &signer, &Options::default(), &Arc::default())
+                .await
+                .unwrap();
+        assert_eq!(keccak256(mined.envelope().encoded_2718()), mined.hash());
+        assert_eq!(mined.tx.tx().blob_versioned_hashes, base.blob_versioned_hashes);
+    }
+}
+
+#[tokio::test]
+async fn refuses_blob_tx_without_sidecar() {
+    let signer = MinerSigner::Local(PrivateKeySigner::random());
+    let signed = signer.sign(&blob_tx(blob::versioned_hashes(&sidecar()))).await.unwrap();
+
+    let err = tx::chain_and_cost(&TxEip4844::into_envelope(signed)).unwrap_err();
+    assert!(err.to_string().contains("no sidecar"), "{err}");
+}
+
+#[tokio::test]
+async fn blob_fee_strategy_needs_blob_tx() {
+    let tx = TxEip1559 {
+        chain_id: 31337,
+        to: TxKind::Call(address!("328eBc7bb2ca4Bf4216863042a960E3C64Ed4c10")),
+        gas_limit: 21_000,
+        ..Default::default()
+    };
+    let signer = MinerSigner::Local(PrivateKeySigner::random());
+
+    let err = engine::mine(
+        &tx,
+        Strategy::BlobFee,
+        &"0".parse().unwrap(),
+        &signer,
+        &Options::default(),
+        &Arc::default(),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("nothing to mine"), "{err}");
+}
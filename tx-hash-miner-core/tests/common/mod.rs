@@ -0,0 +1,65 @@
+use std::{collections::HashMap, sync::Arc};
+
+use alloy::transports::http::reqwest::Url;
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+/// Minimal JSON-over-HTTP server standing in for a signer, relay or node. `handle` gets the
+/// request headers, names lowercased, and the body, and returns the JSON response body.
+pub async fn spawn_http<F>(handle: F) -> Url
+where
+    F: Fn(&HashMap<String, String>, &[u8]) -> Value + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap()).parse().unwrap();
+    let handle = Arc::new(handle);
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(serve(stream, handle.clone()));
+        }
+    });
+
+    url
+}
+
+async fn serve<F>(stream: TcpStream, handle: Arc<F>)
+where
+    F: Fn(&HashMap<String, String>, &[u8]) -> Value,
+{
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+
+    loop {
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap() == 0 {
+                return;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+
+        let len = headers.get("content-length").map_or(0, |len| len.parse().unwrap());
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body).await.unwrap();
+
+        let response = handle(&headers, &body).to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+            response.len(),
+            response
+        );
+        write.write_all(response.as_bytes()).await.unwrap();
+    }
+}
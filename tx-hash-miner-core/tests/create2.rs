@@ -0,0 +1,110 @@
+use std::fs;
+
+use alloy::primitives::{address, b256, bytes, Address, B256};
+use miner_core::{
+    create2::{self, Create2Hasher, CREATE2_DEPLOYER},
+    keccak::LANES,
+    Progress, Target,
+};
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("{name}-{}", std::process::id()))
+}
+
+#[test]
+fn salt_is_prefix_then_counter() {
+    let salt = create2::salt(&[0xaa, 0xbb], 0x0102);
+    assert_eq!(salt, b256!("aabb000000000000000000000000000000000000000000000000000000000102"));
+    assert_eq!(create2::salt(&[], 0), B256::ZERO);
+    assert_eq!(create2::salt(&[0xff; 24], u64::MAX), B256::repeat_byte(0xff));
+}
+
+#[test]
+fn matches_eip1014_examples() {
+    // examples 0, 1 and 5 of EIP-1014
+    let cases = [
+        (
+            Address::ZERO,
+            B256::ZERO,
+            bytes!("00"),
+            address!("4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38"),
+        ),
+        (
+            address!("deadbeef00000000000000000000000000000000"),
+            B256::ZERO,
+            bytes!("00"),
+            address!("B928f69Bb1D91Cd65274e3c79d8986362984fDA3"),
+        ),
+        (
+            address!("00000000000000000000000000000000deadbeef"),
+            b256!("00000000000000000000000000000000000000000000000000000000cafebabe"),
+            bytes!("deadbeef"),
+            address!("60f3f640a8508fC6a86d45DF051962668E1e8AC7"),
+        ),
+    ];
+
+    for (deployer, salt, init_code, expected) in cases {
+        let hash = create2::init_code_hash(&init_code);
+        assert_eq!(deployer.create2(salt, hash), expected);
+        let addresses = Create2Hasher::new(deployer, hash).addresses(&[salt; LANES]);
+        assert_eq!(addresses, [expected; LANES]);
+    }
+}
+
+#[test]
+fn hashes_every_lane_like_alloy() {
+    let hash = create2::init_code_hash(&bytes!("6080604052348015600e575f80fd5b50"));
+    let mut hasher = Create2Hasher::new(CREATE2_DEPLOYER, hash);
+
+    for batch in 0..3u64 {
+        let salts: [B256; LANES] =
+            std::array::from_fn(|i| create2::salt(&[0x42], batch * 4 + i as u64));
+        let addresses = hasher.addresses(&salts);
+        for (salt, address) in salts.into_iter().zip(addresses) {
+            assert_eq!(address, CREATE2_DEPLOYER.create2(salt, hash));
+        }
+    }
+}
+
+#[test]
+fn finds_a_matching_salt() {
+    let hash = create2::init_code_hash(&bytes!("00"));
+    let target: Target = "0ff".parse().unwrap();
+
+    let ((salt, address), attempts) =
+        create2::search(CREATE2_DEPLOYER, hash, &[0x42], &target, 2, &Progress::default()).unwrap();
+
+    assert!(attempts > 0);
+    assert!(address.to_string().to_lowercase().starts_with("0x0ff"), "{address}");
+    assert_eq!(address, CREATE2_DEPLOYER.create2(salt, hash));
+    assert_eq!(salt[0], 0x42);
+}
+
+#[test]
+fn rejects_impossible_searches() {
+    let (hash, progress) = (B256::ZERO, Progress::default());
+    let target: Target = "0".parse().unwrap();
+    assert!(create2::search(CREATE2_DEPLOYER, hash, &[0; 25], &target, 1, &progress).is_err());
+
+    let target: Target = "0".repeat(41).parse().unwrap();
+    assert!(create2::search(CREATE2_DEPLOYER, hash, &[], &target, 1, &progress).is_err());
+}
+
+#[test]
+fn reads_init_code_files() {
+    let path = temp_path("create2-init-code");
+
+    fs::write(&path, "0x6080\n").unwrap();
+    assert_eq!(create2::read_init_code(&path).unwrap(), bytes!("6080"));
+
+    fs::write(&path, r#"{ "bytecode": { "object": "0x60806040" } }"#).unwrap();
+    assert_eq!(create2::read_init_code(&path).unwrap(), bytes!("60806040"));
+
+    fs::write(&path, r#"{ "abi": [] }"#).unwrap();
+    assert!(create2::read_init_code(&path).is_err());
+
+    fs::write(&path, "0x").unwrap();
+    assert!(create2::read_init_code(&path).is_err());
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use alloy::{
+    consensus::TxEip7702,
+    node_bindings::Anvil,
+    primitives::{address, keccak256, Address, Bytes, U256},
+    providers::{Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+};
+use miner_core::{
+    delegation, engine, tx::recover_signer, GuardArgs, MinerSigner, Options, SignedTxFile,
+    Strategy, Target,
+};
+
+const DELEGATE: Address = address!("63c0c19a282a1B52b07dD5a65b58948A07DAE32B");
+
+fn set_code_tx(chain_id: u64, nonce: u64, authority: &PrivateKeySigner) -> TxEip7702 {
+    let authorization = delegation::sign_authorization(authority, chain_id, DELEGATE, 0).unwrap();
+    TxEip7702 {
+        chain_id,
+        nonce,
+        gas_limit: 100_000,
+        max_fee_per_gas: 2_000_000_000,
+        max_priority_fee_per_gas: 1,
+        to: address!("328eBc7bb2ca4Bf4216863042a960E3C64Ed4c10"),
+        value: U256::ZERO,
+        access_list: Default::default(),
+        authorization_list: vec![authorization],
+        input: Bytes::new(),
+    }
+}
+
+#[tokio::test]
+async fn mining_leaves_authorizations_valid() {
+    let authority = PrivateKeySigner::random();
+    let base = set_code_tx(31337, 0, &authority);
+    let target: Target = "0".parse().unwrap();
+    let key = PrivateKeySigner::random();
+
+    for strategy in [Strategy::Fee, Strategy::Priority, Strategy::Gas, Strategy::Data] {
+        let mined = engine::mine(
+            &base,
+            strategy,
+            &target,
+            &MinerSigner::Local(key.clone()),
+            &Options::default(),
+            &Arc::default(),
+        )
+        .await
+        .unwrap();
+
+        let tx = mined.tx.tx();
+        assert!(target.matches(mined.hash()));
+        assert_eq!(tx.authorization_list, base.authorization_list);
+        let authorization = &tx.authorization_list[0];
+        assert_eq!(authorization.recover_authority().unwrap(), authority.address());
+
+        let mut raw = Vec::new();
+        tx.encode_with_signature(mined.tx.signature(), &mut raw, false);
+        assert_eq!(keccak256(&raw), mined.hash());
+        assert_eq!(recover_signer(&mined.tx).unwrap(), key.address());
+    }
+}
+
+#[tokio::test]
+async fn blob_fee_has_nothing_to_mine() {
+    let base = set_code_tx(31337, 0, &PrivateKeySigner::random());
+    let err = engine::mine(
+        &base,
+        Strategy::BlobFee,
+        &"0".parse().unwrap(),
+        &MinerSigner::Local(PrivateKeySigner::random()),
+        &Options::default(),
+        &Arc::default(),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("nothing to mine"), "{err}");
+}
+
+#[test]
+fn sender_authorizing_itself_signs_next_nonce() {
+    let sender = Address::repeat_byte(0x11);
+    assert_eq!(delegation::authority_nonce(sender, sender, 7, Some(7)), Some(8));
+    assert_eq!(delegation::authority_nonce(Address::ZERO, sender, 7, Some(3)), Some(3));
+    assert_eq!(delegation::authority_nonce(Address::ZERO, sender, 7, None), None);
+}
+
+#[tokio::test]
+#[ignore = "needs anvil with prague support"]
+async fn delegation_is_installed_on_anvil() {
+    let anvil = Anvil::new()
+        .args(["--hardfork", "prague"])
+        .try_spawn()
+        .expect("failed to spawn anvil with prague support");
+    let provider = ProviderBuilder::new().on_http(anvil.endpoint_url());
+    let sender = PrivateKeySigner::from(anvil.keys()[0].clone());
+    let authority = PrivateKeySigner::random();
+
+    let nonce = provider.get_transaction_count(sender.address()).await.unwrap();
+    let base = set_code_tx(anvil.chain_id(), nonce, &authority);
+    let target: Target = "0".parse().unwrap();
+
+    let mined = engine::mine(
+        &base,
+        Strategy::Gas,
+        &target,
+        &MinerSigner::Local(sender.clone()),
+        &Options::default(),
+        &Arc::default(),
+    )
+    .await
+    .unwrap();
+    let file = SignedTxFile::new(&mined, sender.address(), Strategy::Gas, &target);
+
+    let hash =
+        miner_core::broadcast(&file, anvil.endpoint_url(), &GuardArgs::default()).await.unwrap();
+    assert_eq!(hash, mined.hash());
+
+    let code = provider.get_code_at(authority.address()).await.unwrap();
+    assert_eq!(code.to_vec(), delegation::delegation_code(DELEGATE));
+}
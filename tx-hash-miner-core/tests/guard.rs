@@ -0,0 +1,143 @@
+use std::sync::{Arc, Mutex};
+
+use alloy::{
+    consensus::{SignableTransaction, TxEip1559, TxEnvelope},
+    network::TxSignerSync,
+    primitives::{address, Bytes, TxKind, U256},
+    providers::ProviderBuilder,
+    signers::local::PrivateKeySigner,
+    transports::http::reqwest::Url,
+};
+use miner_core::{
+    broadcast,
+    guard::{self, production_chain},
+    GuardArgs, Options, SignedTxFile, Strategy,
+};
+use serde_json::{json, Value};
+
+mod common;
+
+/// 21000 gas at 2 gwei.
+const COST: u64 = 42_000_000_000_000;
+
+fn signed_file(chain_id: u64) -> SignedTxFile {
+    let key = PrivateKeySigner::random();
+    let mut tx = TxEip1559 {
+        chain_id,
+        nonce: 0,
+        gas_limit: 21_000,
+        max_fee_per_gas: 2_000_000_000,
+        max_priority_fee_per_gas: 1,
+        to: TxKind::Call(address!("328eBc7bb2ca4Bf4216863042a960E3C64Ed4c10")),
+        value: U256::ZERO,
+        access_list: Default::default(),
+        input: Bytes::new(),
+    };
+    let signature = key.sign_transaction_sync(&mut tx).unwrap();
+    let envelope = TxEnvelope::from(tx.into_signed(signature));
+    SignedTxFile::from_envelope(&envelope, 1, key.address(), Strategy::Gas, &"0".parse().unwrap())
+}
+
+/// Stand-in node on `chain_id`, recording the methods it was called with. It fails every
+/// `eth_sendRawTransaction`, so a broadcast the guard let through ends with that error.
+async fn spawn_node(chain_id: u64) -> (Url, Arc<Mutex<Vec<String>>>) {
+    let methods = Arc::new(Mutex::new(Vec::new()));
+    let called = methods.clone();
+    let url = common::spawn_http(move |_, body| {
+        let request: Value = serde_json::from_slice(body).unwrap();
+        let method = request["method"].as_str().unwrap().to_string();
+        called.lock().unwrap().push(method.clone());
+        match method.as_str() {
+            "eth_chainId" => {
+                json!({ "jsonrpc": "2.0", "id": request["id"], "result": format!("{chain_id:#x}") })
+            }
+            _ => json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": -32000, "message": "stand-in node" }
+            }),
+        }
+    })
+    .await;
+    (url, methods)
+}
+
+fn sent(methods: &Mutex<Vec<String>>) -> bool {
+    methods.lock().unwrap().iter().any(|method| method == "eth_sendRawTransaction")
+}
+
+#[tokio::test]
+async fn refuses_a_node_on_another_chain() {
+    let (url, methods) = spawn_node(1).await;
+
+    let err = broadcast(&signed_file(31337), url, &GuardArgs::default()).await.unwrap_err();
+    assert!(err.to_string().contains("the node reports 1"), "{err}");
+    assert!(!sent(&methods));
+}
+
+#[tokio::test]
+async fn refuses_a_production_chain_without_confirmation() {
+    let (url, methods) = spawn_node(1).await;
+
+    let err = broadcast(&signed_file(1), url, &GuardArgs::default()).await.unwrap_err();
+    assert!(err.to_string().contains("Ethereum mainnet"), "{err}");
+    assert!(err.to_string().contains("--confirm-production"), "{err}");
+    assert!(!sent(&methods));
+}
+
+#[tokio::test]
+async fn broadcasts_on_a_production_chain_with_confirmation() {
+    let (url, methods) = spawn_node(1).await;
+    let guard = GuardArgs { confirm_production: true, max_cost: None };
+
+    let err = broadcast(&signed_file(1), url, &guard).await.unwrap_err();
+    assert!(err.to_string().contains("stand-in node"), "{err}");
+    assert!(sent(&methods));
+}
+
+#[tokio::test]
+async fn refuses_a_tx_above_max_cost() {
+    let (url, methods) = spawn_node(31337).await;
+    let guard = GuardArgs { confirm_production: false, max_cost: Some(U256::from(COST - 1)) };
+
+    let err = broadcast(&signed_file(31337), url.clone(), &guard).await.unwrap_err();
+    assert!(err.to_string().contains("--max-cost"), "{err}");
+    assert!(!sent(&methods));
+
+    let guard = GuardArgs { confirm_production: false, max_cost: Some(U256::from(COST)) };
+    let err = broadcast(&signed_file(31337), url, &guard).await.unwrap_err();
+    assert!(err.to_string().contains("stand-in node"), "{err}");
+    assert!(sent(&methods));
+}
+
+#[tokio::test]
+async fn checks_the_node_chain_id() {
+    let (url, _) = spawn_node(31337).await;
+    let provider = ProviderBuilder::new().on_http(url);
+
+    guard::check_chain_id(&provider, 31337).await.unwrap();
+    let err = guard::check_chain_id(&provider, 1).await.unwrap_err();
+    assert!(err.to_string().contains("tx is for chain id 1"), "{err}");
+}
+
+#[test]
+fn knows_production_chains() {
+    assert_eq!(production_chain(1), Some("Ethereum mainnet"));
+    assert_eq!(production_chain(8453), Some("Base"));
+    assert_eq!(production_chain(31337), None);
+    assert_eq!(production_chain(11155111), None);
+
+    GuardArgs::default().check_broadcast(31337, U256::MAX).unwrap();
+    assert!(GuardArgs::default().check_broadcast(137, U256::ZERO).is_err());
+}
+
+#[test]
+fn options_carry_the_cost_cap() {
+    let guard = GuardArgs { max_cost: Some(U256::from(COST)), ..Default::default() };
+
+    let options = guard.options(Some(3));
+    assert_eq!(options.max_cost, Some(U256::from(COST)));
+    assert_eq!(options.threads, 3);
+
+    assert_eq!(guard.options(None).threads, Options::default().threads);
+}
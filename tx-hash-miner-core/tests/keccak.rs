@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use alloy::{
+    consensus::TxEip1559,
+    eips::eip2718::Encodable2718,
+    primitives::{keccak256, Bytes, TxKind, U256},
+    signers::local::PrivateKeySigner,
+};
+use miner_core::{
+    engine,
+    keccak::{self, LANES},
+    tx::recover_signer,
+    MinerSigner, Options, Strategy, Target,
+};
+
+/// Deterministic filler so failures are reproducible.
+fn data(len: usize) -> Vec<u8> {
+    (0..len as u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect()
+}
+
+// around every multiple of the 136 byte rate, where the padding changes shape
+fn lengths() -> impl Iterator<Item = usize> {
+    (0..600).chain([1_000, 4_095, 4_096, 4_097])
+}
+
+#[test]
+fn scalar_matches_alloy() {
+    let data = data(5_000);
+    for len in lengths() {
+        assert_eq!(keccak::keccak256(&data[..len]), keccak256(&data[..len]), "len {len}");
+    }
+}
+
+#[test]
+fn lanes_match_alloy() {
+    let data = data(5_000);
+    for len in lengths() {
+        // same block count in every lane, different contents
+        let inputs: [&[u8]; LANES] = std::array::from_fn(|i| &data[i..i + len]);
+        let expected = inputs.map(keccak256);
+
+        assert_eq!(keccak::keccak256_x4(inputs), expected, "len {len}");
+        assert_eq!(keccak::keccak256_x4_portable(inputs), expected, "len {len}");
+    }
+}
+
+#[test]
+fn mixed_lengths_match_alloy() {
+    let data = data(1_000);
+    let inputs: Vec<&[u8]> = (0..300).map(|len| &data[..len * 3 % 700]).collect();
+
+    let hashes = keccak::keccak256_batch(&inputs);
+    assert_eq!(hashes.len(), inputs.len());
+    for (input, hash) in inputs.iter().zip(hashes) {
+        assert_eq!(hash, keccak256(input), "len {}", input.len());
+    }
+}
+
+#[tokio::test]
+async fn mined_hash_is_the_tx_hash() {
+    let base = TxEip1559 {
+        chain_id: 31337,
+        nonce: 3,
+        gas_limit: 100_000,
+        max_fee_per_gas: 2_000_000_000,
+        max_priority_fee_per_gas: 1,
+        to: TxKind::Create,
+        value: U256::ZERO,
+        access_list: Default::default(),
+        input: Bytes::from(data(68)),
+    };
+    let signer = MinerSigner::Local(PrivateKeySigner::random());
+    let target: Target = "ab".parse().unwrap();
+    let options = Options { threads: 2, ..Default::default() };
+
+    for strategy in [Strategy::Fee, Strategy::Priority, Strategy::Gas, Strategy::Data] {
+        let mined = engine::mine(&base, strategy, &target, &signer, &options, &Arc::default())
+            .await
+            .unwrap();
+
+        assert!(target.matches(mined.hash()));
+        assert_eq!(mined.hash(), keccak256(mined.envelope().encoded_2718()));
+        assert_eq!(recover_signer(&mined.tx).unwrap(), signer.address());
+    }
+}
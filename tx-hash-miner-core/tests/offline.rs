@@ -0,0 +1,157 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use alloy::{
+    consensus::TxEip1559,
+    primitives::{address, Bytes, TxKind, U256},
+    signers::local::PrivateKeySigner,
+};
+use clap::Parser;
+use miner_core::{
+    cli::{Cli, Command, TxArgs},
+    engine, tx, MinerSigner, Options, SignedTxFile, Strategy, Target, TxTemplate,
+};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{name}-{}", std::process::id()))
+}
+
+fn base_tx() -> TxEip1559 {
+    TxEip1559 {
+        chain_id: 31337,
+        nonce: 3,
+        gas_limit: 21_000,
+        max_fee_per_gas: 2_000_000_000,
+        max_priority_fee_per_gas: 1,
+        to: TxKind::Call(address!("328eBc7bb2ca4Bf4216863042a960E3C64Ed4c10")),
+        value: U256::from(5),
+        access_list: Default::default(),
+        input: Bytes::new(),
+    }
+}
+
+async fn mined_file() -> SignedTxFile {
+    let signer = MinerSigner::Local(PrivateKeySigner::random());
+    let target: Target = "0".parse().unwrap();
+    let mined = engine::mine(
+        &base_tx(),
+        Strategy::Gas,
+        &target,
+        &signer,
+        &Options::default(),
+        &Arc::default(),
+    )
+    .await
+    .unwrap();
+    SignedTxFile::new(&mined, signer.address(), Strategy::Gas, &target)
+}
+
+/// The tx args of `offline` with `flags`.
+fn tx_args(flags: &[&str]) -> TxArgs {
+    let args = ["miner", "offline"].iter().chain(flags);
+    match Cli::try_parse_from(args).unwrap().command {
+        Command::Offline(args) => args.tx,
+        _ => unreachable!(),
+    }
+}
+
+fn complete_template() -> TxTemplate {
+    TxTemplate {
+        chain_id: Some(31337),
+        nonce: Some(0),
+        gas_limit: Some(21_000),
+        max_fee_per_gas: Some(2_000_000_000),
+        max_priority_fee_per_gas: Some(1),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn round_trips_through_a_file() {
+    let file = mined_file().await;
+    let path = temp_path("offline-round-trip.json");
+
+    file.write(&path).unwrap();
+    let read = SignedTxFile::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(read.raw, file.raw);
+    assert_eq!(read.hash, file.hash);
+    assert_eq!((read.from, read.chain_id, read.nonce), (file.from, 31337, 3));
+    assert_eq!(
+        (read.strategy, read.target.as_str(), read.attempts),
+        (Strategy::Gas, "0", file.attempts)
+    );
+
+    let envelope = read.decode().unwrap();
+    assert_eq!(*envelope.tx_hash(), file.hash);
+    let signed = envelope.as_eip1559().unwrap();
+    assert_eq!(tx::recover_signer(signed).unwrap(), file.from);
+    assert_eq!(signed.tx().value, U256::from(5));
+}
+
+#[tokio::test]
+async fn rejects_a_tampered_file() {
+    let file = mined_file().await;
+    let path = temp_path("offline-tampered.json");
+
+    // another hash edited into the file
+    let mut tampered = file.clone();
+    tampered.hash.0[31] ^= 1;
+    tampered.write(&path).unwrap();
+    let err = SignedTxFile::read(&path).unwrap().decode().unwrap_err();
+    assert!(err.to_string().contains("the file says"), "{err}");
+
+    // a raw byte changed, here the value right after the recipient
+    let mut raw = file.raw.to_vec();
+    let to = base_tx().to.to().unwrap().0;
+    let value = raw.windows(20).position(|window| window == to.as_slice()).unwrap() + 20;
+    assert_eq!(raw[value], 5);
+    raw[value] = 6;
+    let tampered = SignedTxFile { raw: raw.into(), ..file.clone() };
+    tampered.write(&path).unwrap();
+    let err = SignedTxFile::read(&path).unwrap().decode().unwrap_err();
+    assert!(err.to_string().contains("the file says"), "{err}");
+
+    // raw bytes cut short
+    let tampered = SignedTxFile { raw: file.raw.slice(..file.raw.len() - 1), ..file };
+    tampered.write(&path).unwrap();
+    let err = SignedTxFile::read(&path).unwrap().decode().unwrap_err();
+    assert!(err.to_string().contains("not a valid EIP-2718 envelope"), "{err}");
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn flags_override_the_template_file() {
+    let path = temp_path("offline-template.json");
+    fs::write(&path, r#"{ "chainId": 5, "nonce": 1, "gasLimit": 30000, "maxFeePerGas": 7 }"#)
+        .unwrap();
+
+    let args = tx_args(&["--template", path.to_str().unwrap(), "--nonce", "9", "--chain-id", "10"]);
+    let template = args.template(complete_template()).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // flags over the file, the file over the miner defaults
+    assert_eq!(template.chain_id, Some(10));
+    assert_eq!(template.nonce, Some(9));
+    assert_eq!(template.gas_limit, Some(30_000));
+    assert_eq!(template.max_fee_per_gas, Some(7));
+    assert_eq!(template.max_priority_fee_per_gas, Some(1));
+}
+
+#[test]
+fn reports_missing_fields_offline() {
+    for (unset, field) in [
+        (TxTemplate { nonce: None, ..complete_template() }, "nonce"),
+        (TxTemplate { gas_limit: None, ..complete_template() }, "gasLimit"),
+        (TxTemplate { chain_id: None, ..complete_template() }, "chainId"),
+    ] {
+        let template = tx_args(&[]).template(unset).unwrap();
+        let err = template.build().unwrap_err();
+        assert_eq!(err.to_string(), format!("tx template is missing `{field}`"));
+    }
+
+    let template = tx_args(&["--nonce", "0"]).template(TxTemplate::default()).unwrap();
+    assert!(template.build().is_err());
+    assert!(complete_template().build().is_ok());
+}
@@ -0,0 +1,189 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use alloy::{
+    consensus::TxEip1559,
+    primitives::{address, keccak256, Address, Bytes, Signature, TxKind, B256, U256},
+    signers::local::PrivateKeySigner,
+    transports::http::reqwest::Url,
+};
+use miner_core::{relay, GuardArgs, Mined, MinerSigner, Relay, SignedTxFile, Strategy, Target};
+use serde_json::{json, Value};
+
+mod common;
+
+/// A node and a relay in one: every `eth_blockNumber` mines a block, and if `include` is set a
+/// bundle lands in the first block it was submitted for.
+#[derive(Default)]
+struct Chain {
+    head: u64,
+    include: bool,
+    bundles: Vec<(u64, Vec<Bytes>)>,
+    identities: Vec<Address>,
+}
+
+impl Chain {
+    fn handle(&mut self, headers: &HashMap<String, String>, body: &[u8]) -> Value {
+        let request: Value = serde_json::from_slice(body).unwrap();
+        let params = &request["params"];
+
+        let result = match request["method"].as_str().unwrap() {
+            "eth_chainId" => json!("0x7a69"),
+            "eth_blockNumber" => {
+                self.head += 1;
+                json!(format!("{:#x}", self.head - 1))
+            }
+            "eth_sendBundle" => {
+                self.identities.push(recover_identity(headers, body));
+
+                let block = params[0]["blockNumber"].as_str().unwrap();
+                let block = u64::from_str_radix(block.trim_start_matches("0x"), 16).unwrap();
+                let txs = serde_json::from_value(params[0]["txs"].clone()).unwrap();
+                self.bundles.push((block, txs));
+
+                json!({ "bundleHash": keccak256(body) })
+            }
+            "eth_getTransactionReceipt" => {
+                let hash: B256 = serde_json::from_value(params[0].clone()).unwrap();
+                match self.included_in(hash) {
+                    Some(block) => receipt(hash, block),
+                    None => Value::Null,
+                }
+            }
+            method => panic!("unexpected {method}"),
+        };
+
+        json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+    }
+
+    fn included_in(&self, hash: B256) -> Option<u64> {
+        if !self.include {
+            return None;
+        }
+        self.bundles
+            .iter()
+            .filter(|(block, txs)| *block < self.head && txs.iter().any(|tx| keccak256(tx) == hash))
+            .map(|(block, _)| *block)
+            .min()
+    }
+}
+
+/// Checks `X-Flashbots-Signature` the way a relay does and returns the identity.
+fn recover_identity(headers: &HashMap<String, String>, body: &[u8]) -> Address {
+    let (address, signature) = headers["x-flashbots-signature"].split_once(':').unwrap();
+    let signature = Signature::try_from(&alloy::hex::decode(signature).unwrap()[..]).unwrap();
+
+    let recovered = signature.recover_address_from_msg(keccak256(body).to_string()).unwrap();
+    assert_eq!(recovered, address.parse::<Address>().unwrap());
+    recovered
+}
+
+fn receipt(hash: B256, block: u64) -> Value {
+    json!({
+        "type": "0x2",
+        "status": "0x1",
+        "cumulativeGasUsed": "0x5208",
+        "logs": [],
+        "logsBloom": format!("0x{}", "00".repeat(256)),
+        "transactionHash": hash,
+        "transactionIndex": "0x0",
+        "blockHash": B256::repeat_byte(0x11),
+        "blockNumber": format!("{block:#x}"),
+        "gasUsed": "0x5208",
+        "effectiveGasPrice": "0x1",
+        "from": Address::ZERO,
+        "to": Address::ZERO,
+        "contractAddress": null,
+    })
+}
+
+async fn spawn_chain(include: bool) -> (Url, Arc<Mutex<Chain>>) {
+    let chain = Arc::new(Mutex::new(Chain { head: 100, include, ..Default::default() }));
+    let handler = chain.clone();
+    let url =
+        common::spawn_http(move |headers, body| handler.lock().unwrap().handle(headers, body))
+            .await;
+    (url, chain)
+}
+
+async fn signed_files(key: &PrivateKeySigner, count: u64) -> Vec<SignedTxFile> {
+    let signer = MinerSigner::Local(key.clone());
+    let target: Target = "0".parse().unwrap();
+
+    let mut files = Vec::new();
+    for nonce in 0..count {
+        let tx = TxEip1559 {
+            chain_id: 31337,
+            nonce,
+            gas_limit: 21_000,
+            max_fee_per_gas: 2_000_000_000,
+            max_priority_fee_per_gas: 1,
+            to: TxKind::Call(address!("328eBc7bb2ca4Bf4216863042a960E3C64Ed4c10")),
+            value: U256::ZERO,
+            access_list: Default::default(),
+            input: Bytes::new(),
+        };
+        let mined = Mined { tx: signer.sign(&tx).await.unwrap(), attempts: 1 };
+        files.push(SignedTxFile::new(&mined, key.address(), Strategy::Fee, &target));
+    }
+    files
+}
+
+fn fast_relay(url: Url, identity: &PrivateKeySigner) -> Relay {
+    let mut relay = Relay::new(url, identity.clone());
+    relay.poll_interval = Duration::from_millis(10);
+    relay
+}
+
+#[tokio::test]
+async fn submits_signed_bundle_and_tracks_inclusion() {
+    let (url, chain) = spawn_chain(true).await;
+    let identity = PrivateKeySigner::random();
+    let files = signed_files(&PrivateKeySigner::random(), 2).await;
+
+    let inclusion =
+        relay::submit(&files, url.clone(), &fast_relay(url, &identity), 3, &GuardArgs::default())
+            .await
+            .unwrap();
+
+    let chain = chain.lock().unwrap();
+    // the head was 100 when the bundle went out, so it targets 101..=103
+    let blocks: Vec<u64> = chain.bundles.iter().map(|(block, _)| *block).collect();
+    assert_eq!(blocks, [101, 102, 103]);
+    assert_eq!(inclusion.block_number, 101);
+    assert_eq!(inclusion.hashes, files.iter().map(|file| file.hash).collect::<Vec<_>>());
+
+    // the whole batch, in order, signed by the identity rather than the tx sender
+    for (_, txs) in &chain.bundles {
+        assert_eq!(*txs, files.iter().map(|file| file.raw.clone()).collect::<Vec<_>>());
+    }
+    assert!(chain.identities.iter().all(|address| *address == identity.address()));
+}
+
+#[tokio::test]
+async fn gives_up_after_last_target_block() {
+    let (url, chain) = spawn_chain(false).await;
+    let files = signed_files(&PrivateKeySigner::random(), 1).await;
+    let relay = fast_relay(url.clone(), &PrivateKeySigner::random());
+
+    let err = relay::submit(&files, url, &relay, 2, &GuardArgs::default()).await.unwrap_err();
+    assert!(err.to_string().contains("not included in blocks 101..=102"), "{err}");
+    assert_eq!(chain.lock().unwrap().bundles.len(), 2);
+}
+
+#[tokio::test]
+async fn rejects_bundle_over_cost_cap() {
+    let (url, chain) = spawn_chain(true).await;
+    let files = signed_files(&PrivateKeySigner::random(), 2).await;
+    let relay = fast_relay(url.clone(), &PrivateKeySigner::random());
+
+    // each tx alone fits, both together don't
+    let guard =
+        GuardArgs { max_cost: Some(U256::from(21_000u64 * 2_000_000_000)), ..Default::default() };
+    let err = relay::submit(&files, url, &relay, 2, &guard).await.unwrap_err();
+    assert!(err.to_string().contains("--max-cost"), "{err}");
+    assert!(chain.lock().unwrap().bundles.is_empty());
+}
@@ -0,0 +1,105 @@
+use alloy::{
+    consensus::{SignableTransaction, TxEip1559, TxEnvelope},
+    eips::eip2718::Encodable2718,
+    network::TxSignerSync,
+    node_bindings::Anvil,
+    primitives::{address, Address, Bytes, TxKind, U256},
+    providers::{Provider, ProviderBuilder},
+    signers::local::PrivateKeySigner,
+};
+use miner_core::{
+    mine_tx,
+    replace::{self, bumped, Pending},
+    MineRequest, MinerSigner, Strategy, Target,
+};
+
+const CONTRACT: Address = address!("328eBc7bb2ca4Bf4216863042a960E3C64Ed4c10");
+
+fn stuck_tx(key: &PrivateKeySigner, chain_id: u64) -> TxEnvelope {
+    let mut tx = TxEip1559 {
+        chain_id,
+        nonce: 0,
+        gas_limit: 50_000,
+        max_fee_per_gas: 2_000_000_000,
+        max_priority_fee_per_gas: 1_000_000,
+        to: TxKind::Call(CONTRACT),
+        value: U256::from(5),
+        access_list: Default::default(),
+        input: Bytes::from_static(&[0xc4, 0x7f, 0x00, 0x27]),
+    };
+    let signature = key.sign_transaction_sync(&mut tx).unwrap();
+    tx.into_signed(signature).into()
+}
+
+#[test]
+fn bumps_by_ten_percent_rounding_up() {
+    assert_eq!(bumped(100), 110);
+    assert_eq!(bumped(101), 112);
+    assert_eq!(bumped(0), 0);
+}
+
+#[test]
+fn speed_up_keeps_the_call() {
+    let key = PrivateKeySigner::random();
+    let pending = Pending::from_envelope(&stuck_tx(&key, 1), key.address()).unwrap();
+
+    let template = pending.replacement(false, None);
+    assert_eq!(template.nonce, Some(0));
+    assert_eq!(template.to, Some(CONTRACT));
+    assert_eq!(template.value, Some(U256::from(5)));
+    assert_eq!(template.gas_limit, Some(50_000));
+    assert_eq!(template.input, Some(pending.input.clone()));
+    assert_eq!(template.max_fee_per_gas, Some(2_200_000_000));
+    assert_eq!(template.max_priority_fee_per_gas, Some(1_100_000));
+
+    // the network moved on since, so its fees win
+    let template = pending.replacement(false, Some((3_000_000_000, 500_000)));
+    assert_eq!(template.max_fee_per_gas, Some(3_000_000_000));
+    assert_eq!(template.max_priority_fee_per_gas, Some(1_100_000));
+}
+
+#[test]
+fn cancel_is_an_empty_self_transfer() {
+    let key = PrivateKeySigner::random();
+    let pending = Pending::from_envelope(&stuck_tx(&key, 1), key.address()).unwrap();
+
+    let template = pending.replacement(true, None);
+    assert_eq!(template.nonce, Some(0));
+    assert_eq!(template.to, Some(key.address()));
+    assert_eq!(template.value, Some(U256::ZERO));
+    assert_eq!(template.gas_limit, Some(21_000));
+    assert_eq!(template.input, Some(Bytes::new()));
+    assert_eq!(template.max_fee_per_gas, Some(2_200_000_000));
+}
+
+#[tokio::test]
+#[ignore = "needs anvil"]
+async fn mined_cancel_replaces_the_stuck_tx() {
+    // nothing gets included until the test mines a block itself
+    let anvil = Anvil::new().arg("--no-mining").try_spawn().expect("failed to spawn anvil");
+    let provider = ProviderBuilder::new().on_http(anvil.endpoint_url());
+    let key = PrivateKeySigner::from(anvil.keys()[0].clone());
+
+    let stuck = stuck_tx(&key, anvil.chain_id());
+    let _ = provider.send_raw_transaction(&stuck.encoded_2718()).await.unwrap();
+
+    let pending = replace::fetch_pending(&provider, *stuck.tx_hash()).await.unwrap();
+    assert_eq!(pending.from, key.address());
+
+    let target: Target = "0".parse().unwrap();
+    let request = MineRequest::new(
+        pending.replacement(true, None),
+        Strategy::Fee,
+        target.clone(),
+        MinerSigner::Local(key.clone()),
+    );
+    let mined = mine_tx(request).await.unwrap();
+    assert!(target.matches(mined.hash));
+
+    let _ = provider.send_raw_transaction(&mined.envelope.encoded_2718()).await.unwrap();
+    provider.raw_request::<_, String>("evm_mine".into(), ()).await.unwrap();
+
+    let receipt = provider.get_transaction_receipt(mined.hash).await.unwrap().unwrap();
+    assert!(receipt.status());
+    assert!(provider.get_transaction_receipt(*stuck.tx_hash()).await.unwrap().is_none());
+}
@@ -0,0 +1,85 @@
+use std::{collections::HashMap, fs};
+
+use alloy::primitives::{keccak256, Selector};
+use miner_core::{selector, Progress, Target};
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("{name}-{}", std::process::id()))
+}
+
+fn selector_of(signature: &str) -> Selector {
+    Selector::from_slice(&keccak256(signature.as_bytes())[..4])
+}
+
+#[test]
+fn suffixes_count_in_base_36() {
+    let suffixes: Vec<_> = [0, 9, 10, 35, 36, 36 * 36 + 1].map(selector::suffix).into();
+    assert_eq!(suffixes, ["0", "9", "a", "z", "10", "101"]);
+}
+
+#[test]
+fn parses_functions_with_or_without_the_keyword() {
+    let bare = selector::parse_function("setName(string)").unwrap();
+    let full = selector::parse_function(" function setName(string name) ").unwrap();
+    assert_eq!(bare.signature(), "setName(string)");
+    assert_eq!(full.signature(), "setName(string)");
+    assert!(selector::parse_function("setName(strin").is_err());
+}
+
+#[test]
+fn mines_a_matching_selector() {
+    let function = selector::parse_function("setName(string)").unwrap();
+    let target: Target = "00".parse().unwrap();
+
+    let ((signature, mined), attempts) =
+        selector::search(&function, &target, &HashMap::new(), 2, &Progress::default()).unwrap();
+
+    assert!(attempts > 0);
+    assert!(signature.starts_with("setName_") && signature.ends_with("(string)"), "{signature}");
+    assert_eq!(mined, selector_of(&signature));
+    assert_eq!(mined[0], 0);
+}
+
+#[test]
+fn skips_selectors_taken_by_errors() {
+    let function = selector::parse_function("setName(string)").unwrap();
+    let target: Target = "0".parse().unwrap();
+    let search = |taken: &HashMap<Selector, String>| {
+        selector::search(&function, &target, taken, 1, &Progress::default()).unwrap().0
+    };
+
+    // one thread tries suffixes in order, so the same search finds the same name again
+    let (first, first_selector) = search(&HashMap::new());
+    let name = first.strip_suffix("(string)").unwrap();
+
+    let path = temp_path("selector-abi.json");
+    let abi = serde_json::json!({
+        "abi": [
+            { "type": "function", "name": "setName", "inputs": [{ "name": "", "type": "string" }],
+              "outputs": [], "stateMutability": "nonpayable" },
+            { "type": "function", "name": "name", "inputs": [], "outputs": [], "stateMutability": "view" },
+            { "type": "error", "name": name, "inputs": [{ "name": "", "type": "string" }] },
+            { "type": "event", "name": "Renamed", "inputs": [], "anonymous": false },
+        ]
+    });
+    fs::write(&path, abi.to_string()).unwrap();
+    let taken = selector::taken_selectors(&path, "setName(string)").unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(taken.len(), 2);
+    assert_eq!(taken[&selector_of("name()")], "name()");
+    assert_eq!(taken[&first_selector], format!("error {first}"));
+    assert!(!taken.contains_key(&selector_of("setName(string)")));
+
+    let (second, second_selector) = search(&taken);
+    assert_ne!(second, first);
+    assert_eq!(second_selector, selector_of(&second));
+    assert!(target.matches(second_selector));
+}
+
+#[test]
+fn rejects_targets_longer_than_a_selector() {
+    let function = selector::parse_function("setName(string)").unwrap();
+    let target: Target = "0".repeat(9).parse().unwrap();
+    assert!(selector::search(&function, &target, &HashMap::new(), 1, &Progress::default()).is_err());
+}
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use alloy::{
+    consensus::{SignableTransaction, TxEip1559, TxEnvelope, TypedTransaction},
+    eips::eip2718::Encodable2718,
+    network::TxSignerSync,
+    primitives::{address, Bytes, TxKind, U256},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+    transports::http::reqwest::Url,
+};
+use miner_core::{
+    engine, tx::recover_signer, ExternalSigner, MinerSigner, Options, SignerArgs, Strategy, Target,
+};
+use serde_json::{json, Value};
+
+mod common;
+
+const MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+fn base_tx() -> TxEip1559 {
+    TxEip1559 {
+        chain_id: 31337,
+        nonce: 0,
+        gas_limit: 21_000,
+        max_fee_per_gas: 2_000_000_000,
+        max_priority_fee_per_gas: 1,
+        to: TxKind::Call(address!("328eBc7bb2ca4Bf4216863042a960E3C64Ed4c10")),
+        value: U256::ZERO,
+        access_list: Default::default(),
+        input: Bytes::new(),
+    }
+}
+
+/// Stand-in for Clef: answers `account_signTransaction` over HTTP, signing with `key`.
+/// With `tamper` set it bumps the nonce before signing, like a signer rewriting the tx.
+async fn spawn_signer(key: PrivateKeySigner, tamper: bool) -> Url {
+    common::spawn_http(move |_, body| sign_request(&key, tamper, body)).await
+}
+
+fn sign_request(key: &PrivateKeySigner, tamper: bool, body: &[u8]) -> Value {
+    let request: Value = serde_json::from_slice(body).unwrap();
+    assert_eq!(request["method"], "account_signTransaction");
+
+    let tx: TransactionRequest = serde_json::from_value(request["params"][0].clone()).unwrap();
+    assert_eq!(tx.from, Some(key.address()));
+
+    let Ok(TypedTransaction::Eip1559(mut tx)) = tx.build_typed_tx() else {
+        panic!("expected an EIP-1559 tx");
+    };
+    if tamper {
+        tx.nonce += 1;
+    }
+
+    let signature = key.sign_transaction_sync(&mut tx).unwrap();
+    let raw = Bytes::from(TxEnvelope::from(tx.into_signed(signature)).encoded_2718());
+
+    json!({ "jsonrpc": "2.0", "id": request["id"], "result": { "raw": raw, "tx": {} } })
+}
+
+#[tokio::test]
+async fn mines_with_external_signer() {
+    let key = PrivateKeySigner::random();
+    let url = spawn_signer(key.clone(), false).await;
+    let signer = MinerSigner::External(ExternalSigner::new(url, key.address()));
+
+    let target: Target = "0".parse().unwrap();
+    let mined = engine::mine(
+        &base_tx(),
+        Strategy::Priority,
+        &target,
+        &signer,
+        &Options::default(),
+        &Arc::default(),
+    )
+    .await
+    .unwrap();
+
+    assert!(target.matches(mined.hash()));
+    assert_eq!(recover_signer(&mined.tx).unwrap(), key.address());
+    assert_eq!(mined.tx.tx().max_priority_fee_per_gas, mined.attempts as u128);
+}
+
+#[tokio::test]
+async fn rejects_external_signer_rewriting_the_tx() {
+    let key = PrivateKeySigner::random();
+    let url = spawn_signer(key.clone(), true).await;
+    let signer = ExternalSigner::new(url, key.address());
+
+    let err = signer.sign(&base_tx()).await.unwrap_err();
+    assert!(err.to_string().contains("changed the tx"), "{err}");
+}
+
+#[tokio::test]
+async fn rejects_external_signer_using_another_account() {
+    let key = PrivateKeySigner::random();
+    let url = spawn_signer(key, false).await;
+    let signer = ExternalSigner::new(url, PrivateKeySigner::random().address());
+
+    assert!(signer.sign(&base_tx()).await.is_err());
+}
+
+fn mnemonic_args(derivation_path: &str) -> SignerArgs {
+    SignerArgs {
+        private_key: None,
+        keystore: None,
+        password_file: None,
+        mnemonic: Some(MNEMONIC.to_string()),
+        derivation_path: derivation_path.to_string(),
+        external_signer: None,
+        from: None,
+    }
+}
+
+#[test]
+fn derives_mnemonic_accounts() {
+    let signer = mnemonic_args("m/44'/60'/0'/0/0").signer().unwrap();
+    assert_eq!(signer.address(), address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266"));
+
+    let signer = mnemonic_args("m/44'/60'/0'/0/1").signer().unwrap();
+    assert_eq!(signer.address(), address!("70997970C51812dc3A010C7d01b50e0d17dc79C8"));
+}
+
+#[test]
+fn rejects_bad_derivation_path() {
+    let err = mnemonic_args("not/a/path").signer().unwrap_err();
+    assert!(err.to_string().contains("derivation path"), "{err}");
+}
@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+
+use alloy::{
+    primitives::{address, U256},
+    signers::local::PrivateKeySigner,
+};
+use miner_core::{
+    vanity::{self, AddressPattern},
+    Progress,
+};
+
+#[test]
+fn keys_are_independent() {
+    let keys: Vec<U256> =
+        (0..2_000).map(|_| U256::from_be_bytes(vanity::random_key().to_bytes().0)).collect();
+
+    let unique: HashSet<_> = keys.iter().collect();
+    assert_eq!(unique.len(), keys.len());
+
+    // stepped keys (k, k + d, k + 2d, ...) would all share one difference
+    let steps: HashSet<_> = keys.windows(2).map(|w| w[1].wrapping_sub(w[0])).collect();
+    assert!(steps.len() > 1);
+}
+
+#[test]
+fn checksum_patterns() {
+    // the EIP-55 example address
+    let addr = address!("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+
+    let matches = |pattern: &str| pattern.parse::<AddressPattern>().unwrap().matches(&addr);
+    assert!(matches("5aae"));
+    assert!(matches("0x5aAe"));
+    assert!(matches("5aAeb6053F"));
+    assert!(!matches("5AAe"));
+    assert!(!matches("5aaf"));
+
+    assert!("0".repeat(41).parse::<AddressPattern>().is_err());
+    assert!("xyz".parse::<AddressPattern>().is_err());
+}
+
+#[test]
+fn search_finds_pattern() {
+    let pattern: AddressPattern = "0".parse().unwrap();
+    let (key, attempts) = vanity::search(&pattern, 2, &Progress::default()).unwrap();
+
+    assert!(pattern.matches(&key.address()));
+    assert!(attempts >= 1);
+}
+
+#[test]
+fn keystore_round_trip() {
+    let dir = std::env::temp_dir().join(format!("eoa-miner-test-{}", std::process::id()));
+    let key = vanity::random_key();
+
+    assert!(vanity::write_keystore(&key, &dir, "").is_err());
+
+    let path = vanity::write_keystore(&key, &dir, "hunter2").unwrap();
+    let decrypted = PrivateKeySigner::decrypt_keystore(&path, "hunter2").unwrap();
+    assert_eq!(decrypted.address(), key.address());
+    assert!(PrivateKeySigner::decrypt_keystore(&path, "hunter3").is_err());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
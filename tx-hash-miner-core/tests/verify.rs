@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use alloy::{
+    consensus::{TxEip1559, TxEip4844, TxEip7702, TxEnvelope},
+    dyn_abi::DynSolValue,
+    eips::eip2718::Encodable2718,
+    primitives::{address, keccak256, Address, Bytes, TxKind, U256},
+    signers::local::PrivateKeySigner,
+};
+use miner_core::{
+    blob, delegation, engine,
+    verify::{self, parse_function, VerifiedTx},
+    MinerSigner, Options, SignedTxFile, Strategy, Target,
+};
+
+fn set_name_tx() -> TxEip1559 {
+    let function = parse_function("setName(string)").unwrap();
+    let mut input = function.selector().to_vec();
+    input.extend(DynSolValue::Tuple(vec![DynSolValue::String("poop".into())]).abi_encode_params());
+
+    TxEip1559 {
+        chain_id: 31337,
+        nonce: 0,
+        gas_limit: 100_000,
+        max_fee_per_gas: 2_000_000_000,
+        max_priority_fee_per_gas: 1_000_000,
+        to: TxKind::Call(address!("5FbDB2315678afecb367f032d93F642f64180aa3")),
+        value: U256::ZERO,
+        access_list: Default::default(),
+        input: input.into(),
+    }
+}
+
+async fn mine(strategy: Strategy, key: &PrivateKeySigner) -> SignedTxFile {
+    let target: Target = "0".parse().unwrap();
+    let signer = MinerSigner::Local(key.clone());
+    let mined = engine::mine(
+        &set_name_tx(),
+        strategy,
+        &target,
+        &signer,
+        &Options::default(),
+        &Arc::default(),
+    )
+    .await
+    .unwrap();
+    SignedTxFile::new(&mined, key.address(), strategy, &target)
+}
+
+#[tokio::test]
+async fn verifies_data_mined_tx() {
+    let key = PrivateKeySigner::random();
+    let file = mine(Strategy::Data, &key).await;
+    let function = parse_function("function setName(string s)").unwrap();
+
+    let verification =
+        verify::verify(&file.raw, Some("0".parse().unwrap()), None, Some(&function)).unwrap();
+
+    assert_eq!(verification.hash, file.hash);
+    assert_eq!(verification.from, key.address());
+    assert!(verification.matches_target());
+
+    let calldata = verification.calldata.unwrap();
+    assert_eq!(calldata.args, [DynSolValue::String("poop".into())]);
+    assert_eq!(calldata.trailing.len(), 8);
+    assert!(calldata.padding.is_empty());
+    assert_eq!(verification.mined_field.unwrap().0, Strategy::Data);
+}
+
+#[tokio::test]
+async fn guesses_mined_field_without_tx_file() {
+    let key = PrivateKeySigner::random();
+
+    for strategy in [Strategy::Gas, Strategy::Data] {
+        let file = mine(strategy, &key).await;
+        let verification = verify::verify(&file.raw, None, None, None).unwrap();
+        // a gas limit that happens to land on a round number leaves nothing to spot
+        if let Some((guess, _)) = verification.mined_field {
+            assert_eq!(guess, strategy);
+        }
+    }
+}
+
+#[tokio::test]
+async fn reports_target_mismatch() {
+    let file = mine(Strategy::Fee, &PrivateKeySigner::random()).await;
+    let verification = verify::verify(&file.raw, Some("1".parse().unwrap()), None, None).unwrap();
+    assert!(!verification.matches_target());
+}
+
+#[tokio::test]
+async fn rejects_tx_file_claiming_another_sender() {
+    let mut file = mine(Strategy::Priority, &PrivateKeySigner::random()).await;
+    file.from = Address::repeat_byte(0x42);
+
+    let err = verify::verify(&file.raw, None, Some(&file), None).unwrap_err();
+    assert!(err.to_string().contains("sender"), "{err}");
+}
+
+#[tokio::test]
+async fn verifies_calldata_of_another_function() {
+    let key = PrivateKeySigner::random();
+    let file = mine(Strategy::Data, &key).await;
+    let function = parse_function("transfer(address,uint256)").unwrap();
+
+    let verification =
+        verify::verify(&file.raw, Some("0".parse().unwrap()), None, Some(&function)).unwrap();
+
+    assert_eq!(verification.hash, file.hash);
+    assert_eq!(verification.from, key.address());
+    assert!(verification.matches_target());
+    assert!(verification.calldata.is_none());
+    let reason = verification.undecoded.unwrap();
+    assert!(reason.contains("transfer(address,uint256)"), "{reason}");
+    // without decoded arguments the counter still shows as a partial ABI word
+    assert_eq!(verification.mined_field.unwrap().0, Strategy::Data);
+}
+
+#[tokio::test]
+async fn finds_counter_in_padding() {
+    let mut input = set_name_tx().input.to_vec();
+    // the last padding byte after "poop": offset, length, then the data word
+    *input.last_mut().unwrap() = 0x07;
+    let tx = TxEip1559 { input: Bytes::from(input), ..set_name_tx() };
+
+    let key = PrivateKeySigner::random();
+    let signed = MinerSigner::Local(key.clone()).sign(&tx).await.unwrap();
+    let raw = Bytes::from(TxEnvelope::from(signed).encoded_2718());
+
+    let function = parse_function("setName(string)").unwrap();
+    let verification = verify::verify(&raw, None, None, Some(&function)).unwrap();
+    let calldata = verification.calldata.unwrap();
+    assert_eq!(calldata.args, [DynSolValue::String("poop".into())]);
+    assert_eq!(calldata.padding, [3 * 32 - 1]);
+    assert!(calldata.trailing.is_empty());
+}
+
+#[tokio::test]
+async fn verifies_blob_tx_without_its_sidecar() {
+    let path = std::env::temp_dir().join(format!("verify-blob-{}.bin", std::process::id()));
+    std::fs::write(&path, b"rollup batch goes here").unwrap();
+    let sidecar = blob::sidecar_from_file(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let base = TxEip4844 {
+        chain_id: 31337,
+        nonce: 0,
+        gas_limit: 21_000,
+        max_fee_per_gas: 2_000_000_000,
+        max_priority_fee_per_gas: 1_000_000,
+        to: address!("328eBc7bb2ca4Bf4216863042a960E3C64Ed4c10"),
+        value: U256::ZERO,
+        access_list: Default::default(),
+        blob_versioned_hashes: blob::versioned_hashes(&sidecar),
+        max_fee_per_blob_gas: 1_000_000_000,
+        input: Bytes::new(),
+    };
+    let key = PrivateKeySigner::random();
+    let target: Target = "0".parse().unwrap();
+    let mined = engine::mine(
+        &base,
+        Strategy::BlobFee,
+        &target,
+        &MinerSigner::Local(key.clone()),
+        &Options::default(),
+        &Arc::default(),
+    )
+    .await
+    .unwrap();
+
+    let envelope = blob::envelope(mined.tx.clone(), sidecar).unwrap();
+    let file = SignedTxFile::from_envelope(
+        &envelope,
+        mined.attempts,
+        key.address(),
+        Strategy::BlobFee,
+        &target,
+    );
+    // the raw bytes carry the sidecar, the hash doesn't
+    assert_ne!(keccak256(&file.raw), mined.hash());
+
+    let verification = verify::verify(&file.raw, Some(target), Some(&file), None).unwrap();
+    assert_eq!(verification.hash, mined.hash());
+    assert_eq!(verification.from, key.address());
+    assert!(verification.matches_target());
+    assert_eq!(verification.tx, VerifiedTx::Eip4844(mined.tx.tx().clone()));
+
+    // the bare tx, as found in a block, verifies the same
+    let raw = Bytes::from(mined.envelope().encoded_2718());
+    let verification = verify::verify(&raw, None, None, None).unwrap();
+    assert_eq!(verification.hash, mined.hash());
+    // a fee that happens to land on a round number leaves nothing to spot
+    if let Some((guess, _)) = verification.mined_field {
+        assert_eq!(guess, Strategy::BlobFee);
+    }
+}
+
+#[tokio::test]
+async fn verifies_set_code_tx() {
+    let authority = PrivateKeySigner::random();
+    let authorization =
+        delegation::sign_authorization(&authority, 31337, Address::repeat_byte(0x77), 0).unwrap();
+    let base = TxEip7702 {
+        chain_id: 31337,
+        nonce: 0,
+        gas_limit: 100_000,
+        max_fee_per_gas: 2_000_000_000,
+        max_priority_fee_per_gas: 1_000_000,
+        to: address!("328eBc7bb2ca4Bf4216863042a960E3C64Ed4c10"),
+        value: U256::ZERO,
+        access_list: Default::default(),
+        authorization_list: vec![authorization],
+        input: Bytes::new(),
+    };
+    let key = PrivateKeySigner::random();
+    let target: Target = "0".parse().unwrap();
+    let mined = engine::mine(
+        &base,
+        Strategy::Priority,
+        &target,
+        &MinerSigner::Local(key.clone()),
+        &Options::default(),
+        &Arc::default(),
+    )
+    .await
+    .unwrap();
+    let file = SignedTxFile::new(&mined, key.address(), Strategy::Priority, &target);
+
+    let verification = verify::verify(&file.raw, Some(target), Some(&file), None).unwrap();
+    assert_eq!(verification.hash, mined.hash());
+    assert_eq!(verification.from, key.address());
+    let VerifiedTx::Eip7702(tx) = verification.tx else { panic!("{:?}", verification.tx) };
+    assert_eq!(tx.authorization_list, base.authorization_list);
+}